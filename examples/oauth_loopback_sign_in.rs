@@ -0,0 +1,28 @@
+use graph_rs_sdk::oauth::{OAuth, DEFAULT_LOOPBACK_TIMEOUT};
+
+// The client_id must be changed before running this example.
+static CLIENT_ID: &str = "<CLIENT_ID>";
+
+// This example signs in a user with the authorization code grant and
+// proof key for code exchange (PKCE) without running a web server.
+//
+// OAuth starts a server on 127.0.0.1 to receive the redirect, opens the
+// browser to the sign in page and exchanges the returned code for an
+// access token. Add http://127.0.0.1/redirect as a redirect URI of the
+// application in the Azure portal. The port of loopback redirect URIs
+// is ignored by Microsoft identity platform.
+//
+// For more info see: https://docs.microsoft.com/en-us/azure/active-directory/develop/reply-url#localhost-exceptions
+fn main() {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id(CLIENT_ID)
+        .add_scope("user.read")
+        .add_scope("offline_access")
+        .authorize_url("https://login.microsoftonline.com/common/oauth2/v2.0/authorize")
+        .access_token_url("https://login.microsoftonline.com/common/oauth2/v2.0/token")
+        .refresh_token_url("https://login.microsoftonline.com/common/oauth2/v2.0/token");
+
+    let access_token = oauth.loopback_sign_in(DEFAULT_LOOPBACK_TIMEOUT).unwrap();
+    println!("{:#?}", access_token);
+}
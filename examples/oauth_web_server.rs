@@ -87,7 +87,7 @@ async fn main() {
     // Get the oauth client and request a browser sign in
     let mut oauth = oauth_web_client();
     let mut request = oauth.build().code_flow();
-    request.browser_authorization().open().unwrap();
+    request.browser_authorization().unwrap().open().unwrap();
 
     warp::serve(routes).run(([127, 0, 0, 1], 8000)).await;
 }
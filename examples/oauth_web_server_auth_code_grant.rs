@@ -41,7 +41,7 @@ async fn main() {
 
     let mut oauth = oauth_web_client();
     let mut request = oauth.build().authorization_code_grant();
-    request.browser_authorization().open().unwrap();
+    request.browser_authorization().unwrap().open().unwrap();
 
     warp::serve(routes).run(([127, 0, 0, 1], 8000)).await;
 }
@@ -26,7 +26,7 @@ fn main() {
     // the fragment of the Uri.
     let mut oauth = native_client();
     let mut request = oauth.build().implicit_grant();
-    let _ = request.browser_authorization().open().unwrap();
+    let _ = request.browser_authorization().unwrap().open().unwrap();
 }
 
 fn native_client() -> OAuth {
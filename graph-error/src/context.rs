@@ -0,0 +1,141 @@
+use graph_core::resource::ResourceIdentity;
+use std::fmt;
+use url::Url;
+
+/// Query parameters that are kept when redacting a url. The values of
+/// all other query parameters are replaced.
+const UNREDACTED_QUERY_PARAMETERS: [&str; 6] = [
+    "$select", "$expand", "$top", "$orderby", "$count", "$format",
+];
+
+const REDACTED: &str = "REDACTED";
+
+/// The kind of operation a request was made for.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum RequestOrigin {
+    /// A single api request.
+    Request,
+    /// A request for the next page of a collection or delta query.
+    Paging,
+    /// A file download.
+    Download,
+    /// A request uploading bytes to an upload session.
+    Upload,
+}
+
+impl AsRef<str> for RequestOrigin {
+    fn as_ref(&self) -> &str {
+        match self {
+            RequestOrigin::Request => "Request",
+            RequestOrigin::Paging => "Paging",
+            RequestOrigin::Download => "Download",
+            RequestOrigin::Upload => "Upload",
+        }
+    }
+}
+
+/// The request that caused an error.
+///
+/// Credentials and query values in the url are redacted, except for
+/// common OData query parameters such as $select and $expand. Path
+/// segments with a user principal name or email address, such as
+/// `/users/alice@contoso.com`, are redacted. The path of upload session
+/// urls is also redacted as it is used to authorize the upload.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RequestContext {
+    pub method: String,
+    pub url: String,
+    pub resource_identity: Option<ResourceIdentity>,
+    pub attempt: u32,
+    pub origin: RequestOrigin,
+}
+
+impl RequestContext {
+    pub fn new(
+        method: &str,
+        url: &Url,
+        resource_identity: Option<ResourceIdentity>,
+        origin: RequestOrigin,
+    ) -> RequestContext {
+        RequestContext {
+            method: method.to_string(),
+            url: RequestContext::redact_url(url, origin),
+            resource_identity,
+            attempt: 1,
+            origin,
+        }
+    }
+
+    /// Set the attempt number of the request. Starts at 1. Requests for
+    /// the next page of a collection or delta query are counted as
+    /// further attempts of the initial request.
+    pub fn set_attempt(&mut self, attempt: u32) {
+        self.attempt = attempt;
+    }
+
+    /// Redact a url so that it can be safely logged.
+    pub fn redact_url(url: &Url, origin: RequestOrigin) -> String {
+        let mut url = url.clone();
+        let _ = url.set_username("");
+        let _ = url.set_password(None);
+        url.set_fragment(None);
+
+        if origin == RequestOrigin::Upload {
+            url.set_path(REDACTED);
+            url.set_query(None);
+            return url.to_string();
+        }
+
+        let path = url.path_segments().map(|segments| {
+            segments
+                .map(|segment| {
+                    if RequestContext::identifies_user(segment) {
+                        REDACTED
+                    } else {
+                        segment
+                    }
+                })
+                .collect::<Vec<&str>>()
+                .join("/")
+        });
+        if let Some(path) = path {
+            url.set_path(&path);
+        }
+
+        if url.query().is_some() {
+            let pairs: Vec<(String, String)> = url
+                .query_pairs()
+                .map(|(key, value)| {
+                    if UNREDACTED_QUERY_PARAMETERS.contains(&key.as_ref()) {
+                        (key.into_owned(), value.into_owned())
+                    } else {
+                        (key.into_owned(), REDACTED.to_string())
+                    }
+                })
+                .collect();
+            url.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+        url.to_string()
+    }
+
+    // User principal names and email addresses, including OData keys
+    // such as users('alice@contoso.com').
+    fn identifies_user(segment: &str) -> bool {
+        segment.contains('@') || segment.to_lowercase().contains("%40")
+    }
+}
+
+impl fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} (", self.method, self.url)?;
+        if let Some(resource_identity) = self.resource_identity.as_ref() {
+            write!(f, "resource: {}, ", resource_identity.as_ref())?;
+        }
+        write!(
+            f,
+            "origin: {}, attempt: {})",
+            self.origin.as_ref(),
+            self.attempt
+        )
+    }
+}
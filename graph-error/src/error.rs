@@ -1,10 +1,15 @@
-use crate::{GraphHeaders, GraphResult};
+use crate::{GraphHeaders, GraphResult, RequestContext};
 use async_trait::async_trait;
 use reqwest::StatusCode;
 use serde::Serialize;
 use std::error::Error;
 use std::fmt;
 use std::string::ToString;
+use std::time::Duration;
+
+/// The maximum number of characters of a raw response body shown
+/// when displaying a GraphError.
+const DISPLAY_BODY_LIMIT: usize = 1024;
 
 #[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct InnerError {
@@ -42,6 +47,17 @@ pub struct GraphError {
     pub code: StatusCode,
 
     pub error_message: ErrorMessage,
+
+    /// The raw response body when the body is not a Graph error message,
+    /// such as html returned from a gateway.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<String>,
+
+    /// The request that caused the error.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<RequestContext>,
 }
 
 impl GraphError {
@@ -54,6 +70,42 @@ impl GraphError {
             headers,
             code,
             error_message,
+            response_body: None,
+            context: None,
+        }
+    }
+
+    /// Set the request that caused the error if it has not already been set.
+    pub fn with_context(mut self, context: RequestContext) -> GraphError {
+        if self.context.is_none() {
+            self.context = Some(context);
+        }
+        self
+    }
+
+    /// The request that caused the error.
+    pub fn context(&self) -> Option<&RequestContext> {
+        self.context.as_ref()
+    }
+
+    /// Create a GraphError from the body of an error response. Bodies that
+    /// are not a Graph error message are kept as the raw response body.
+    pub fn from_response_body(
+        headers: Option<GraphHeaders>,
+        code: StatusCode,
+        body: String,
+    ) -> GraphError {
+        match serde_json::from_str::<ErrorMessage>(&body) {
+            Ok(error_message) if error_message.error.is_some() => {
+                GraphError::new(headers, code, error_message)
+            }
+            _ => {
+                let mut error = GraphError::new(headers, code, Default::default());
+                if !body.trim().is_empty() {
+                    error.response_body = Some(body);
+                }
+                error
+            }
         }
     }
 
@@ -117,6 +169,41 @@ impl GraphError {
             .code
             .clone()
     }
+
+    /// The raw response body if the body was not a Graph error message.
+    pub fn response_body(&self) -> Option<&str> {
+        self.response_body.as_deref()
+    }
+
+    /// The client-request-id header of the response.
+    pub fn client_request_id(&self) -> Option<&str> {
+        self.headers.as_ref()?.client_request_id()
+    }
+
+    /// The Retry-After header of the response.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.headers.as_ref()?.retry_after()
+    }
+
+    /// Returns true if the request was throttled (429 Too Many Requests
+    /// or 509 Bandwidth Limit Exceeded).
+    pub fn is_throttled(&self) -> bool {
+        self.code == StatusCode::TOO_MANY_REQUESTS || self.code.as_u16() == 509
+    }
+
+    /// Returns true if the request can be retried. This includes throttled
+    /// requests, timeouts and transient server errors.
+    pub fn is_retryable(&self) -> bool {
+        self.is_throttled()
+            || matches!(
+                self.code,
+                StatusCode::REQUEST_TIMEOUT
+                    | StatusCode::INTERNAL_SERVER_ERROR
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            )
+    }
 }
 
 impl Error for GraphError {
@@ -141,7 +228,35 @@ impl std::fmt::Display for GraphError {
             "\nError Code: {:#?}\nError Message: {:#?}",
             &self.code,
             self.code.canonical_reason().unwrap_or_default()
-        )
+        )?;
+        if let Some(code) = self.code_property() {
+            write!(f, "\nGraph Error Code: {}", code)?;
+        }
+        if let Some(message) = self.message() {
+            write!(f, "\nGraph Error Message: {}", message)?;
+        }
+        let request_id = self
+            .request_id()
+            .or_else(|| self.headers.as_ref()?.request_id().map(|id| id.to_string()));
+        if let Some(request_id) = request_id {
+            write!(f, "\nRequest Id: {}", request_id)?;
+        }
+        if let Some(client_request_id) = self.client_request_id() {
+            write!(f, "\nClient Request Id: {}", client_request_id)?;
+        }
+        if let Some(date) = self.date() {
+            write!(f, "\nDate: {}", date)?;
+        }
+        if let Some(context) = self.context() {
+            write!(f, "\nRequest: {}", context)?;
+        }
+        if let Some(body) = self.response_body() {
+            match body.char_indices().nth(DISPLAY_BODY_LIMIT) {
+                Some((index, _)) => write!(f, "\nResponse Body: {}...", &body[..index])?,
+                None => write!(f, "\nResponse Body: {}", body)?,
+            }
+        }
+        Ok(())
     }
 }
 
@@ -242,21 +357,19 @@ impl ToString for ErrorType {
     }
 }
 
+#[cfg(feature = "blocking")]
 pub trait WithGraphError: Sized {
     fn with_graph_error(self) -> Result<Self, GraphError>;
 }
 
+#[cfg(feature = "blocking")]
 impl WithGraphError for reqwest::blocking::Response {
     fn with_graph_error(self) -> Result<Self, GraphError> {
         let code = self.status();
         if code.is_client_error() || code.is_server_error() {
             let headers = Some(GraphHeaders::from(&self));
-            let error_message = self.json().unwrap_or_default();
-            Err(GraphError {
-                code,
-                headers,
-                error_message,
-            })
+            let body = self.text().unwrap_or_default();
+            Err(GraphError::from_response_body(headers, code, body))
         } else {
             Ok(self)
         }
@@ -273,12 +386,8 @@ impl WithGraphErrorAsync for reqwest::Response {
         let code = self.status();
         if code.is_client_error() || code.is_server_error() {
             let headers = Some(GraphHeaders::from(&self));
-            let error_message = self.json().await.unwrap_or_default();
-            Err(GraphError {
-                code,
-                headers,
-                error_message,
-            })
+            let body = self.text().await.unwrap_or_default();
+            Err(GraphError::from_response_body(headers, code, body))
         } else {
             Ok(self)
         }
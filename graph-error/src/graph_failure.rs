@@ -1,9 +1,13 @@
 use crate::error::GraphError;
 use crate::internal::GraphRsError;
+use crate::jwt_error::JwtValidationError;
+use crate::oauth_error::OAuthErrorResponse;
+use crate::RequestContext;
 use std::cell::BorrowMutError;
 use std::io::ErrorKind;
 use std::str::Utf8Error;
 use std::sync::mpsc;
+use std::time::Duration;
 use std::{io, num, string};
 
 #[derive(Debug, thiserror::Error)]
@@ -36,7 +40,7 @@ pub enum GraphFailure {
     #[error("Base64 decode error:\n{0:#?}")]
     DecodeError(#[from] base64::DecodeError),
 
-    #[error("Graph error:\n{0:#?}")]
+    #[error("Graph error:{0}")]
     GraphError(#[from] GraphError),
 
     #[error("Recv error:\n{0:#?}")]
@@ -68,6 +72,12 @@ pub enum GraphFailure {
 
     #[error("Crypto Error (Unknown)")]
     CryptoError,
+
+    #[error("OAuth error: {0}")]
+    OAuthError(#[from] OAuthErrorResponse),
+
+    #[error("JWT validation error: {0}")]
+    JwtValidationError(#[from] JwtValidationError),
 }
 
 impl GraphFailure {
@@ -88,6 +98,94 @@ impl GraphFailure {
         GraphFailure::internal(GraphRsError::InvalidOrMissing { msg: msg.into() })
     }
 
+    /// Create a failure from an error response of the Microsoft identity
+    /// platform. Responses with an OAuth error body become
+    /// GraphFailure::OAuthError.
+    pub fn from_oauth_response(error: GraphError) -> GraphFailure {
+        match error.oauth_error_response() {
+            Some(response) => GraphFailure::OAuthError(response),
+            None => GraphFailure::GraphError(error),
+        }
+    }
+
+    /// The OAuth error returned by the Microsoft identity platform.
+    pub fn oauth_error(&self) -> Option<&OAuthErrorResponse> {
+        match self {
+            GraphFailure::OAuthError(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// The reason a JWT failed validation.
+    pub fn jwt_validation_error(&self) -> Option<&JwtValidationError> {
+        match self {
+            GraphFailure::JwtValidationError(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Set the request that caused the failure. Only failures from error
+    /// responses (GraphFailure::GraphError) carry a request context.
+    pub fn with_context(self, context: RequestContext) -> GraphFailure {
+        match self {
+            GraphFailure::GraphError(e) => GraphFailure::GraphError(e.with_context(context)),
+            _ => self,
+        }
+    }
+
+    /// The request that caused the failure.
+    pub fn context(&self) -> Option<&RequestContext> {
+        match self {
+            GraphFailure::GraphError(e) => e.context(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the request was throttled by the service.
+    pub fn is_throttled(&self) -> bool {
+        match self {
+            GraphFailure::GraphError(e) => e.is_throttled(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the request failed with a transient error and can
+    /// be retried, such as throttling, timeouts, connection errors and
+    /// 5xx gateway or availability errors.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            GraphFailure::GraphError(e) => e.is_retryable(),
+            GraphFailure::OAuthError(e) => e.is_retryable(),
+            GraphFailure::ReqwestError(e) => {
+                e.is_timeout()
+                    || e.is_connect()
+                    || matches!(e.status(), Some(status) if status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS)
+            }
+            GraphFailure::HyperError(e) => {
+                e.is_timeout() || e.is_closed() || e.is_incomplete_message()
+            }
+            GraphFailure::Io(e) => matches!(
+                e.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::Interrupted
+                    | ErrorKind::UnexpectedEof
+            ),
+            _ => false,
+        }
+    }
+
+    /// The duration to wait before retrying the request, taken from
+    /// the Retry-After header of the response.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            GraphFailure::GraphError(e) => e.retry_after(),
+            _ => None,
+        }
+    }
+
     // pub fn from_response(r: &reqwest::blocking::Response) -> Option<GraphFailure> {
     //     GraphFailure::try_from(r).ok()
     // }
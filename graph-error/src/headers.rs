@@ -1,4 +1,5 @@
-use reqwest::header::HeaderMap;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::time::{Duration, SystemTime};
 
 #[allow(dead_code)]
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -41,6 +42,58 @@ pub struct GraphHeaders {
     header_map: HeaderMap,
 }
 
+impl GraphHeaders {
+    pub fn new(url: &str, status: u16, header_map: HeaderMap) -> GraphHeaders {
+        GraphHeaders {
+            url: url.to_string(),
+            status,
+            header_map,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn header_map(&self) -> &HeaderMap {
+        &self.header_map
+    }
+
+    /// Get a header value as a str.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.header_map.get(key)?.to_str().ok()
+    }
+
+    /// The request-id header. Include this when contacting Microsoft support.
+    pub fn request_id(&self) -> Option<&str> {
+        self.get("request-id")
+    }
+
+    /// The client-request-id header.
+    pub fn client_request_id(&self) -> Option<&str> {
+        self.get("client-request-id")
+    }
+
+    /// The Retry-After header, either as a number of seconds or an
+    /// http date. Dates in the past return a zero duration.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let value = self.header_map.get(RETRY_AFTER)?.to_str().ok()?.trim();
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+        let date = httpdate::parse_http_date(value).ok()?;
+        Some(
+            date.duration_since(SystemTime::now())
+                .unwrap_or_else(|_| Duration::from_secs(0)),
+        )
+    }
+}
+
+#[cfg(feature = "blocking")]
 impl From<reqwest::blocking::Response> for GraphHeaders {
     fn from(r: reqwest::blocking::Response) -> Self {
         GraphHeaders {
@@ -51,6 +104,7 @@ impl From<reqwest::blocking::Response> for GraphHeaders {
     }
 }
 
+#[cfg(feature = "blocking")]
 impl From<&reqwest::blocking::Response> for GraphHeaders {
     fn from(r: &reqwest::blocking::Response) -> Self {
         GraphHeaders {
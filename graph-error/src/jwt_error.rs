@@ -0,0 +1,73 @@
+use std::error::Error;
+use std::fmt;
+
+/// Errors returned when a JWT fails validation.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum JwtValidationError {
+    /// The token is not a signed JWT (header.payload.signature) or
+    /// could not be decoded.
+    Malformed(String),
+    /// The alg header is not one of the algorithms allowed by the validator.
+    UnsupportedAlgorithm(String),
+    /// No key matches the kid or x5t header of the token.
+    KeyNotFound(Option<String>),
+    /// The key is missing values or does not match the algorithm.
+    InvalidKey(String),
+    /// The signature does not match the key.
+    InvalidSignature,
+    /// A claim required by the validator is not in the token.
+    MissingClaim(&'static str),
+    /// The issuer (iss) is not one of the expected issuers.
+    InvalidIssuer(String),
+    /// The audience (aud) does not contain an expected audience.
+    InvalidAudience,
+    /// The tenant id (tid) does not match the expected tenant.
+    InvalidTenant(String),
+    /// The nonce does not match the nonce sent in the authorization request.
+    InvalidNonce,
+    /// The token expired (exp) at the given time.
+    Expired(i64),
+    /// The token is not valid before (nbf) the given time.
+    NotYetValid(i64),
+    /// The claims could not be deserialized into the requested type.
+    InvalidClaims(String),
+    /// The validator does not have an expected issuer or audience, or an
+    /// expected nonce for an ID token that has a nonce. Tokens are rejected
+    /// unless these are set.
+    NotConfigured(&'static str),
+}
+
+impl fmt::Display for JwtValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtValidationError::Malformed(msg) => write!(f, "Malformed JWT: {}", msg),
+            JwtValidationError::UnsupportedAlgorithm(alg) => {
+                write!(f, "Unsupported JWT algorithm: {}", alg)
+            }
+            JwtValidationError::KeyNotFound(Some(kid)) => {
+                write!(f, "No signing key found for key id: {}", kid)
+            }
+            JwtValidationError::KeyNotFound(None) => {
+                write!(
+                    f,
+                    "JWT header does not have a key id (kid) or thumbprint (x5t)"
+                )
+            }
+            JwtValidationError::InvalidKey(msg) => write!(f, "Invalid signing key: {}", msg),
+            JwtValidationError::InvalidSignature => write!(f, "Invalid JWT signature"),
+            JwtValidationError::MissingClaim(claim) => write!(f, "Missing JWT claim: {}", claim),
+            JwtValidationError::InvalidIssuer(iss) => write!(f, "Invalid JWT issuer: {}", iss),
+            JwtValidationError::InvalidAudience => write!(f, "Invalid JWT audience"),
+            JwtValidationError::InvalidTenant(tid) => write!(f, "Invalid JWT tenant: {}", tid),
+            JwtValidationError::InvalidNonce => write!(f, "Invalid JWT nonce"),
+            JwtValidationError::Expired(exp) => write!(f, "JWT expired at: {}", exp),
+            JwtValidationError::NotYetValid(nbf) => write!(f, "JWT not valid before: {}", nbf),
+            JwtValidationError::InvalidClaims(msg) => write!(f, "Invalid JWT claims: {}", msg),
+            JwtValidationError::NotConfigured(name) => {
+                write!(f, "JWT validator does not have an expected {}", name)
+            }
+        }
+    }
+}
+
+impl Error for JwtValidationError {}
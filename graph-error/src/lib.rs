@@ -4,14 +4,20 @@
 #[macro_use]
 extern crate serde;
 
+mod context;
 mod error;
 mod graph_failure;
 mod headers;
 mod internal;
+mod jwt_error;
+mod oauth_error;
 
+pub use context::*;
 pub use error::*;
 pub use graph_failure::*;
 pub use headers::*;
 pub use internal::*;
+pub use jwt_error::*;
+pub use oauth_error::*;
 
 pub type GraphResult<T> = Result<T, GraphFailure>;
@@ -0,0 +1,109 @@
+use crate::GraphError;
+use std::error::Error;
+use std::fmt;
+
+/// Error response returned by the Microsoft identity platform token and
+/// authorization endpoints.
+///
+/// # See
+/// [Microsoft identity platform error codes](https://docs.microsoft.com/en-us/azure/active-directory/develop/reference-aadsts-error-codes)
+#[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OAuthErrorResponse {
+    /// The error code, such as invalid_grant or interaction_required.
+    pub error: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_description: Option<String>,
+    /// The AADSTS error codes.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub error_codes: Vec<i64>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_uri: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Further classifies an invalid_grant error, such as consent_required
+    /// or basic_action.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suberror: Option<String>,
+    /// Claims that must be requested to satisfy a conditional access policy.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claims: Option<String>,
+    /// The status code of the response.
+    #[serde(skip)]
+    pub status: Option<u16>,
+}
+
+impl OAuthErrorResponse {
+    /// Parse the body of an error response. Returns None if the body is
+    /// not an OAuth error.
+    pub fn from_response_body(status: u16, body: &str) -> Option<OAuthErrorResponse> {
+        let mut response: OAuthErrorResponse = serde_json::from_str(body).ok()?;
+        if response.error.is_empty() {
+            return None;
+        }
+        response.status = Some(status);
+        Some(response)
+    }
+
+    /// Returns true if a new refresh token or authorization code is needed.
+    pub fn is_invalid_grant(&self) -> bool {
+        self.error == "invalid_grant"
+    }
+
+    /// Returns true if the user has to sign in interactively, for instance
+    /// to consent, complete multi-factor authentication or change their
+    /// password.
+    pub fn requires_interaction(&self) -> bool {
+        matches!(
+            self.error.as_str(),
+            "interaction_required" | "login_required" | "consent_required"
+        ) || matches!(
+            self.suberror.as_deref(),
+            Some("consent_required")
+                | Some("basic_action")
+                | Some("additional_action")
+                | Some("user_password_expired")
+        )
+    }
+
+    /// Returns true if the service is temporarily unable to handle the
+    /// request and the request can be retried.
+    pub fn is_retryable(&self) -> bool {
+        self.error == "temporarily_unavailable"
+            || matches!(self.status, Some(status) if status >= 500 || status == 429)
+    }
+}
+
+impl fmt::Display for OAuthErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        if let Some(suberror) = self.suberror.as_ref() {
+            write!(f, " ({})", suberror)?;
+        }
+        if let Some(description) = self.error_description.as_ref() {
+            write!(f, ": {}", description)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for OAuthErrorResponse {}
+
+impl GraphError {
+    /// The OAuth error in the response body if the error is from the
+    /// Microsoft identity platform.
+    pub fn oauth_error_response(&self) -> Option<OAuthErrorResponse> {
+        OAuthErrorResponse::from_response_body(self.code.as_u16(), self.response_body()?)
+    }
+}
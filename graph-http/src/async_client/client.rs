@@ -8,7 +8,7 @@ use crate::{
 };
 use graph_core::resource::ResourceIdentity;
 use graph_error::WithGraphErrorAsync;
-use graph_error::{GraphFailure, GraphResult};
+use graph_error::{GraphFailure, GraphResult, RequestOrigin};
 use handlebars::Handlebars;
 use reqwest::header::{HeaderMap, HeaderValue, IntoHeaderName, CONTENT_TYPE};
 use reqwest::redirect::Policy;
@@ -60,7 +60,13 @@ impl AsyncClient {
             .take()
             .ok_or_else(|| GraphFailure::invalid("file for upload session"))?;
 
-        let response = self.response().await?.with_graph_error().await?;
+        let context = self.request_context(RequestOrigin::Request);
+        let response = self
+            .response()
+            .await?
+            .with_graph_error()
+            .await
+            .map_err(|e| e.with_context(context))?;
         let upload_session: serde_json::Value = response.json().await?;
         let mut session = UploadSessionClient::new_async(upload_session)?;
         session.set_file(file).await?;
@@ -107,8 +113,11 @@ impl AsyncClient {
     where
         for<'de> T: serde::Deserialize<'de>,
     {
+        let context = self.request_context(RequestOrigin::Request);
         let response = self.response().await?;
-        AsyncTryFrom::<reqwest::Response>::async_try_from(response).await
+        let result: GraphResult<GraphResponse<T>> =
+            AsyncTryFrom::<reqwest::Response>::async_try_from(response).await;
+        result.map_err(|e| e.with_context(context))
     }
 
     pub fn clone(&mut self) -> Self {
@@ -162,6 +171,14 @@ impl HttpClient<std::sync::Arc<tokio::sync::Mutex<AsyncClient>>> {
         self.client.lock().await.token.clone()
     }
 
+    pub(crate) async fn inner_reqwest_client(&self) -> reqwest::Client {
+        self.client.lock().await.client.clone()
+    }
+
+    pub fn inner_http_client(&self) -> reqwest::Client {
+        futures::executor::block_on(self.inner_reqwest_client())
+    }
+
     async fn inner_set_token(&self, token: &str) {
         self.client.lock().await.token = token.to_string();
     }
@@ -5,7 +5,7 @@ use crate::{
     GraphRequest, GraphResponse, HttpClient, Registry, RequestAttribute, RequestClient, RequestType,
 };
 use graph_core::resource::ResourceIdentity;
-use graph_error::{GraphFailure, GraphResult, WithGraphError};
+use graph_error::{GraphFailure, GraphResult, RequestOrigin, WithGraphError};
 use handlebars::Handlebars;
 use reqwest::header::{HeaderMap, HeaderValue, IntoHeaderName, CONTENT_TYPE};
 use reqwest::redirect::Policy;
@@ -63,7 +63,11 @@ impl BlockingClient {
             .take()
             .ok_or_else(|| GraphFailure::invalid("file for upload session"))?;
 
-        let response = self.response()?.with_graph_error()?;
+        let context = self.request_context(RequestOrigin::Request);
+        let response = self
+            .response()?
+            .with_graph_error()
+            .map_err(|e| e.with_context(context))?;
         let upload_session: serde_json::Value = response.json()?;
         let mut session = UploadSessionClient::new(upload_session)?;
         session.set_file(file)?;
@@ -110,7 +114,10 @@ impl BlockingClient {
     where
         for<'de> T: serde::Deserialize<'de>,
     {
-        std::convert::TryFrom::try_from(self.response()?)
+        let context = self.request_context(RequestOrigin::Request);
+        let result: GraphResult<GraphResponse<T>> =
+            std::convert::TryFrom::try_from(self.response()?);
+        result.map_err(|e| e.with_context(context))
     }
 
     pub fn clone(&mut self) -> Self {
@@ -193,6 +200,10 @@ impl HttpClient<RefCell<BlockingClient>> {
     {
         f(&self.client.borrow().url)
     }
+
+    pub fn inner_http_client(&self) -> reqwest::blocking::Client {
+        self.client.borrow().client.clone()
+    }
 }
 
 impl RequestClient for HttpClient<RefCell<BlockingClient>> {
@@ -5,7 +5,9 @@ use crate::types::*;
 use crate::uploadsession::UploadSessionClient;
 use crate::url::GraphUrl;
 use crate::GraphResponse;
-use graph_error::{GraphFailure, GraphResult, WithGraphError, WithGraphErrorAsync};
+use graph_error::{
+    GraphFailure, GraphResult, RequestContext, RequestOrigin, WithGraphError, WithGraphErrorAsync,
+};
 use reqwest::header::CONTENT_TYPE;
 use std::marker::PhantomData;
 use std::path::PathBuf;
@@ -17,11 +19,27 @@ pub struct DispatchRequest<T, Builder> {
     ident: PhantomData<T>,
     file: Option<PathBuf>,
     error: Option<GraphFailure>,
+    context: Option<RequestContext>,
 }
 
 pub type DispatchBlocking<T> = DispatchRequest<T, reqwest::blocking::RequestBuilder>;
 pub type DispatchAsync<T> = DispatchRequest<T, reqwest::RequestBuilder>;
 
+impl<T, Builder> DispatchRequest<T, Builder> {
+    /// Set the request context that is attached to error responses.
+    pub fn with_context(mut self, context: RequestContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    fn map_context<U>(context: Option<RequestContext>, result: GraphResult<U>) -> GraphResult<U> {
+        match context {
+            Some(context) => result.map_err(|e| e.with_context(context)),
+            None => result,
+        }
+    }
+}
+
 impl<T> DispatchBlocking<T> {
     pub fn new(
         request: reqwest::blocking::RequestBuilder,
@@ -33,6 +51,7 @@ impl<T> DispatchBlocking<T> {
             ident: Default::default(),
             file,
             error,
+            context: None,
         }
     }
 }
@@ -59,7 +78,7 @@ where
             return Err(self.error.unwrap_or_default());
         }
         let response = self.request.send()?;
-        Ok(std::convert::TryFrom::try_from(response)?)
+        DispatchBlocking::<T>::map_context(self.context, std::convert::TryFrom::try_from(response))
     }
 }
 
@@ -69,7 +88,10 @@ impl DispatchBlocking<GraphResponse<NoContent>> {
             return Err(self.error.unwrap_or_default());
         }
         let response = self.request.send()?;
-        GraphResponse::<serde_json::Value>::from_no_content(response)
+        DispatchBlocking::<GraphResponse<NoContent>>::map_context(
+            self.context,
+            GraphResponse::<serde_json::Value>::from_no_content(response),
+        )
     }
 }
 
@@ -83,7 +105,13 @@ impl DispatchBlocking<UploadSessionClient<BlockingHttpClient>> {
             .file
             .ok_or_else(|| GraphFailure::invalid("file for upload session"))?;
 
-        let response = self.request.send()?.with_graph_error()?;
+        let response = DispatchBlocking::<UploadSessionClient<BlockingHttpClient>>::map_context(
+            self.context,
+            self.request
+                .send()?
+                .with_graph_error()
+                .map_err(GraphFailure::from),
+        )?;
         let upload_session: serde_json::Value = response.json()?;
         let mut session = UploadSessionClient::new(upload_session)?;
         session.set_file(file)?;
@@ -104,6 +132,7 @@ impl<T> DispatchAsync<T> {
             ident: Default::default(),
             file,
             error,
+            context: None,
         }
     }
 }
@@ -130,7 +159,10 @@ where
             return Err(self.error.unwrap_or_default());
         }
         let response = self.request.send().await?;
-        AsyncTryFrom::<reqwest::Response>::async_try_from(response).await
+        DispatchAsync::<T>::map_context(
+            self.context,
+            AsyncTryFrom::<reqwest::Response>::async_try_from(response).await,
+        )
     }
 }
 
@@ -140,7 +172,10 @@ impl DispatchAsync<GraphResponse<NoContent>> {
             return Err(self.error.unwrap_or_default());
         }
         let response = self.request.send().await?;
-        GraphResponse::<serde_json::Value>::async_from_no_content(response).await
+        DispatchAsync::<GraphResponse<NoContent>>::map_context(
+            self.context,
+            GraphResponse::<serde_json::Value>::async_from_no_content(response).await,
+        )
     }
 }
 
@@ -154,7 +189,15 @@ impl DispatchAsync<UploadSessionClient<AsyncHttpClient>> {
             .file
             .ok_or_else(|| GraphFailure::invalid("file for upload session"))?;
 
-        let response = self.request.send().await?.with_graph_error().await?;
+        let response = DispatchAsync::<UploadSessionClient<AsyncHttpClient>>::map_context(
+            self.context,
+            self.request
+                .send()
+                .await?
+                .with_graph_error()
+                .await
+                .map_err(GraphFailure::from),
+        )?;
         let upload_session: serde_json::Value = response.json().await?;
         let mut session = UploadSessionClient::new_async(upload_session)?;
         session.set_file(file).await?;
@@ -162,29 +205,64 @@ impl DispatchAsync<UploadSessionClient<AsyncHttpClient>> {
     }
 }
 
-pub struct DispatchDelta<T, Builder> {
+pub struct DispatchDelta<T, Builder, Client> {
     token: String,
     request: Builder,
+    client: Client,
     error: Option<GraphFailure>,
+    context: Option<RequestContext>,
     phantom: PhantomData<T>,
 }
 
-impl<T, Builder> DispatchDelta<T, Builder> {
+impl<T, Builder, Client> DispatchDelta<T, Builder, Client> {
+    /// The next pages are requested with `client`, which is the client
+    /// of the initial request.
     pub fn new(
         token: String,
-        client: Builder,
+        request: Builder,
+        client: Client,
         error: Option<GraphFailure>,
-    ) -> DispatchDelta<T, Builder> {
+    ) -> DispatchDelta<T, Builder, Client> {
         DispatchDelta {
             token,
-            request: client,
+            request,
+            client,
             error,
+            context: None,
             phantom: Default::default(),
         }
     }
+
+    /// Set the request context of the initial request. Errors returned
+    /// for the next pages carry a context for the page that failed.
+    pub fn with_context(mut self, context: RequestContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    fn page_context(
+        context: &Option<RequestContext>,
+        next_link: &str,
+        attempt: u32,
+    ) -> Option<RequestContext> {
+        let url = reqwest::Url::parse(next_link).ok()?;
+        let resource_identity = context.as_ref().and_then(|c| c.resource_identity);
+        let mut page_context =
+            RequestContext::new("GET", &url, resource_identity, RequestOrigin::Paging);
+        page_context.set_attempt(attempt);
+        Some(page_context)
+    }
+
+    fn map_context(context: Option<RequestContext>, err: GraphFailure) -> GraphFailure {
+        match context {
+            Some(context) => err.with_context(context),
+            None => err,
+        }
+    }
 }
 
-impl<T: 'static + Send + ODataLink + Clone> DispatchDelta<T, reqwest::blocking::RequestBuilder>
+impl<T: 'static + Send + ODataLink + Clone>
+    DispatchDelta<T, reqwest::blocking::RequestBuilder, reqwest::blocking::Client>
 where
     for<'de> T: serde::Deserialize<'de>,
 {
@@ -201,19 +279,24 @@ where
             self.request.send().map_err(GraphFailure::from);
         let response: GraphResult<GraphResponse<T>> = std::convert::TryFrom::try_from(initial_res);
         if let Err(err) = response {
+            let err = Self::map_context(self.context, err);
             sender.send(Delta::Done(Some(err))).unwrap();
             return receiver;
         }
 
         let token = self.token;
+        let client = self.client;
+        let context = self.context;
         let response = response.unwrap();
         let mut next_link = response.body().next_link();
         sender.send(Delta::Next(response)).unwrap();
 
         thread::spawn(move || {
             let mut is_done = false;
-            let client = reqwest::blocking::Client::new();
+            let mut attempt = 1;
             while let Some(next) = next_link {
+                attempt += 1;
+                let page_context = Self::page_context(&context, next.as_str(), attempt);
                 let res = client
                     .get(next.as_str())
                     .header(CONTENT_TYPE, "application/json")
@@ -249,7 +332,8 @@ where
                         }
                         Err(err) => {
                             next_link = None;
-                            sender.send(Delta::Done(Some(err.into()))).unwrap();
+                            let err = Self::map_context(page_context, err.into());
+                            sender.send(Delta::Done(Some(err))).unwrap();
                             is_done = true;
                         }
                     }
@@ -264,7 +348,8 @@ where
     }
 }
 
-impl<T: 'static + Send + ODataLink + Clone> DispatchDelta<T, reqwest::RequestBuilder>
+impl<T: 'static + Send + ODataLink + Clone>
+    DispatchDelta<T, reqwest::RequestBuilder, reqwest::Client>
 where
     for<'de> T: serde::Deserialize<'de>,
 {
@@ -284,19 +369,24 @@ where
         let response: GraphResult<GraphResponse<T>> =
             AsyncTryFrom::<GraphResult<reqwest::Response>>::async_try_from(initial_res).await;
         if let Err(err) = response {
+            let err = Self::map_context(self.context, err);
             sender.send(Delta::Done(Some(err))).await.unwrap();
             return receiver;
         }
 
         let token = self.token;
+        let client = self.client;
+        let context = self.context;
         let response = response.unwrap();
         let mut next_link = response.body().next_link();
         sender.send(Delta::Next(response)).await.unwrap();
 
         tokio::spawn(async move {
             let mut is_done = false;
-            let client = reqwest::Client::new();
+            let mut attempt = 1;
             while let Some(next) = next_link {
+                attempt += 1;
+                let page_context = Self::page_context(&context, next.as_str(), attempt);
                 let res = client
                     .get(next.as_str())
                     .header(CONTENT_TYPE, "application/json")
@@ -335,7 +425,8 @@ where
 
                         Err(err) => {
                             next_link = None;
-                            sender.send(Delta::Done(Some(err.into()))).await.unwrap();
+                            let err = Self::map_context(page_context, err.into());
+                            sender.send(Delta::Done(Some(err))).await.unwrap();
                             is_done = true;
                         }
                     }
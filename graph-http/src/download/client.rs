@@ -4,7 +4,7 @@ use crate::blocking_client::BlockingClient;
 use crate::iotools;
 use crate::url::GraphUrl;
 use crate::{HttpClient, RequestClient, RequestType};
-use graph_error::{WithGraphError, WithGraphErrorAsync};
+use graph_error::{RequestOrigin, WithGraphError, WithGraphErrorAsync};
 use reqwest::header::HeaderMap;
 use reqwest::Method;
 use std::cell::RefCell;
@@ -155,7 +155,13 @@ impl BlockingDownload {
         }
 
         if self.client.request_type() == RequestType::Redirect {
-            let response = self.client.build().send()?.with_graph_error()?;
+            let context = self.client.request_context(RequestOrigin::Download);
+            let response = self
+                .client
+                .build()
+                .send()?
+                .with_graph_error()
+                .map_err(|e| e.with_context(context))?;
             let mut client = self.client.client.borrow_mut();
             client.headers.clear();
             client.method = Method::GET;
@@ -163,7 +169,13 @@ impl BlockingDownload {
             client.url = GraphUrl::from(response.url().clone());
         }
 
-        let response = self.client.build().send()?.with_graph_error()?;
+        let context = self.client.request_context(RequestOrigin::Download);
+        let response = self
+            .client
+            .build()
+            .send()?
+            .with_graph_error()
+            .map_err(|e| e.with_context(context))?;
 
         let path = {
             if let Some(name) = request
@@ -276,6 +288,7 @@ impl AsyncDownload {
         }
 
         if self.client.request_type() == RequestType::Redirect {
+            let context = self.client.request_context(RequestOrigin::Download);
             let response = self
                 .client
                 .build()
@@ -283,7 +296,8 @@ impl AsyncDownload {
                 .send()
                 .await?
                 .with_graph_error()
-                .await?;
+                .await
+                .map_err(|e| e.with_context(context))?;
 
             let mut client = self.client.client.lock().await;
             (*client).headers.clear();
@@ -292,6 +306,7 @@ impl AsyncDownload {
             (*client).url = GraphUrl::from(response.url().clone());
         }
 
+        let context = self.client.request_context(RequestOrigin::Download);
         let response = self
             .client
             .build()
@@ -299,7 +314,8 @@ impl AsyncDownload {
             .send()
             .await?
             .with_graph_error()
-            .await?;
+            .await
+            .map_err(|e| e.with_context(context))?;
 
         let path = {
             if let Some(name) = request
@@ -1,7 +1,7 @@
 use crate::request::{RequestAttribute, RequestType};
 use crate::url::GraphUrl;
 use graph_core::resource::ResourceIdentity;
-use graph_error::{GraphFailure, GraphResult};
+use graph_error::{GraphFailure, GraphResult, RequestContext, RequestOrigin};
 use handlebars::Handlebars;
 use reqwest::header::{HeaderMap, HeaderValue, IntoHeaderName};
 use reqwest::Method;
@@ -48,6 +48,16 @@ pub trait RequestClient {
         req_attr: Vec<RequestAttribute<Self::Body, Self::Form>>,
     ) -> GraphResult<()>;
 
+    /// The redacted context of the current request used for errors.
+    fn request_context(&self, origin: RequestOrigin) -> RequestContext {
+        RequestContext::new(
+            self.method().as_str(),
+            &self.to_url(),
+            Some(self.ident()),
+            origin,
+        )
+    }
+
     fn set_body_with_serialize<B: serde::Serialize>(&self, body: &B) -> GraphResult<()> {
         let body_result = serde_json::to_string(body).map_err(GraphFailure::from);
         if let Ok(body) = body_result {
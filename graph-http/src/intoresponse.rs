@@ -4,7 +4,7 @@ use crate::traits::{AsyncTryFrom, ODataLink};
 use crate::types::{Delta, DeltaPhantom, NoContent};
 use crate::uploadsession::UploadSessionClient;
 use crate::{DispatchAsync, DispatchBlocking, DispatchDelta, GraphResponse, RequestClient};
-use graph_error::{GraphFailure, GraphResult};
+use graph_error::{GraphFailure, GraphResult, RequestOrigin};
 use reqwest::header::{HeaderValue, IntoHeaderName};
 use std::marker::PhantomData;
 use std::sync::mpsc::Receiver;
@@ -139,6 +139,7 @@ where
     pub fn build(self) -> DispatchBlocking<T> {
         let builder = self.client.build();
         DispatchBlocking::new(builder, None, self.error)
+            .with_context(self.client.request_context(RequestOrigin::Request))
     }
 
     pub fn send(self) -> GraphResult<GraphResponse<T>> {
@@ -153,6 +154,7 @@ impl<'a> IntoResponseBlocking<'a, UploadSessionClient<BlockingHttpClient>> {
     pub fn build(self) -> DispatchBlocking<UploadSessionClient<BlockingHttpClient>> {
         let (file, builder) = self.client.build_upload_session();
         DispatchBlocking::new(builder, file, self.error)
+            .with_context(self.client.request_context(RequestOrigin::Request))
     }
 
     pub fn send(self) -> GraphResult<UploadSessionClient<BlockingHttpClient>> {
@@ -167,14 +169,17 @@ impl<'a> IntoResponseBlocking<'a, NoContent> {
     pub fn build(self) -> DispatchBlocking<GraphResponse<NoContent>> {
         let builder = self.client.build();
         DispatchBlocking::new(builder, None, self.error)
+            .with_context(self.client.request_context(RequestOrigin::Request))
     }
 
     pub fn send(self) -> GraphResult<GraphResponse<serde_json::Value>> {
         if self.error.is_some() {
             return Err(self.error.unwrap_or_default());
         }
+        let context = self.client.request_context(RequestOrigin::Request);
         let response = self.client.response()?;
         GraphResponse::<serde_json::Value>::from_no_content(response)
+            .map_err(|e| e.with_context(context))
     }
 }
 
@@ -182,11 +187,14 @@ impl<'a, T: 'static + Send + ODataLink + Clone> IntoResponseBlocking<'a, DeltaPh
 where
     for<'de> T: serde::Deserialize<'de>,
 {
-    pub fn build(self) -> DispatchDelta<T, reqwest::blocking::RequestBuilder> {
+    pub fn build(
+        self,
+    ) -> DispatchDelta<T, reqwest::blocking::RequestBuilder, reqwest::blocking::Client> {
         let client = self.client;
         let builder = client.build();
         let token = client.token();
-        DispatchDelta::<T, reqwest::blocking::RequestBuilder>::new(token, builder, self.error)
+        DispatchDelta::new(token, builder, client.inner_http_client(), self.error)
+            .with_context(client.request_context(RequestOrigin::Request))
     }
 
     pub fn send(self) -> Receiver<Delta<T>> {
@@ -227,14 +235,18 @@ where
     pub async fn build(self) -> DispatchAsync<T> {
         let builder = self.client.build().await;
         DispatchAsync::new(builder, None, self.error)
+            .with_context(self.client.request_context(RequestOrigin::Request))
     }
 
     pub async fn send(self) -> GraphResult<GraphResponse<T>> {
         if self.error.is_some() {
             return Err(self.error.unwrap_or_default());
         }
+        let context = self.client.request_context(RequestOrigin::Request);
         let response = self.client.response().await?;
-        AsyncTryFrom::<reqwest::Response>::async_try_from(response).await
+        let result: GraphResult<GraphResponse<T>> =
+            AsyncTryFrom::<reqwest::Response>::async_try_from(response).await;
+        result.map_err(|e| e.with_context(context))
     }
 }
 
@@ -242,14 +254,18 @@ impl<'a> IntoResponseAsync<'a, NoContent> {
     pub async fn build(self) -> DispatchAsync<GraphResponse<NoContent>> {
         let builder = self.client.build().await;
         DispatchAsync::new(builder, None, self.error)
+            .with_context(self.client.request_context(RequestOrigin::Request))
     }
 
     pub async fn send(self) -> GraphResult<GraphResponse<serde_json::Value>> {
         if self.error.is_some() {
             return Err(self.error.unwrap_or_default());
         }
+        let context = self.client.request_context(RequestOrigin::Request);
         let response = self.client.response().await?;
-        GraphResponse::<serde_json::Value>::async_from_no_content(response).await
+        GraphResponse::<serde_json::Value>::async_from_no_content(response)
+            .await
+            .map_err(|e| e.with_context(context))
     }
 }
 
@@ -257,6 +273,7 @@ impl<'a> IntoResponseAsync<'a, UploadSessionClient<AsyncHttpClient>> {
     pub async fn build(self) -> DispatchAsync<UploadSessionClient<AsyncHttpClient>> {
         let (file, builder) = self.client.build_upload_session().await;
         DispatchAsync::new(builder, file, self.error)
+            .with_context(self.client.request_context(RequestOrigin::Request))
     }
 
     pub async fn send(self) -> GraphResult<UploadSessionClient<AsyncHttpClient>> {
@@ -271,10 +288,12 @@ impl<'a, T: 'static + Send + ODataLink + Clone> IntoResponseAsync<'a, DeltaPhant
 where
     for<'de> T: serde::Deserialize<'de>,
 {
-    pub async fn build(self) -> DispatchDelta<T, reqwest::RequestBuilder> {
+    pub async fn build(self) -> DispatchDelta<T, reqwest::RequestBuilder, reqwest::Client> {
         let builder = self.client.build().await;
         let token = self.client.token();
-        DispatchDelta::<T, reqwest::RequestBuilder>::new(token, builder, self.error)
+        let http_client = self.client.inner_reqwest_client().await;
+        DispatchDelta::new(token, builder, http_client, self.error)
+            .with_context(self.client.request_context(RequestOrigin::Request))
     }
 
     pub async fn send(self) -> tokio::sync::mpsc::Receiver<Delta<T>> {
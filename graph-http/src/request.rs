@@ -1,5 +1,6 @@
 use crate::url::GraphUrl;
 use graph_core::resource::ResourceIdentity;
+use graph_error::{RequestContext, RequestOrigin};
 use handlebars::Handlebars;
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::Method;
@@ -49,6 +50,17 @@ pub struct GraphRequest<Client, Body, Form> {
     pub req_type: RequestType,
 }
 
+impl<Client, Body, Form> GraphRequest<Client, Body, Form> {
+    pub(crate) fn request_context(&self, origin: RequestOrigin) -> RequestContext {
+        RequestContext::new(
+            self.method.as_str(),
+            &self.url.to_url(),
+            Some(self.ident),
+            origin,
+        )
+    }
+}
+
 impl<Client, Body, Form> Debug for GraphRequest<Client, Body, Form> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("GraphRequest")
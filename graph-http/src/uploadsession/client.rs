@@ -5,7 +5,7 @@ use crate::traits::*;
 use crate::url::GraphUrl;
 use crate::{GraphResponse, RequestAttribute, RequestClient};
 use async_trait::async_trait;
-use graph_error::{GraphFailure, GraphResult, WithGraphError, WithGraphErrorAsync};
+use graph_error::{GraphFailure, GraphResult, RequestOrigin, WithGraphError, WithGraphErrorAsync};
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE};
 use reqwest::StatusCode;
 use std::convert::TryFrom;
@@ -149,7 +149,9 @@ impl Iterator for UploadSessionClient<BlockingHttpClient> {
                     let result = std::convert::TryFrom::try_from(response);
                     NextSession::from_response((status, result))
                 }
-                Err(e) => Some(Err(e.into())),
+                Err(e) => Some(Err(e
+                    .with_context(self.client.request_context(RequestOrigin::Upload))
+                    .into())),
             },
             Err(e) => Some(Err(e)),
         }
@@ -199,7 +201,9 @@ impl AsyncIterator for UploadSessionClient<AsyncHttpClient> {
                     let result = AsyncTryFrom::<reqwest::Response>::async_try_from(response).await;
                     NextSession::from_response((status, result))
                 }
-                Err(e) => Some(Err(e.into())),
+                Err(e) => Some(Err(e
+                    .with_context(self.client.request_context(RequestOrigin::Upload))
+                    .into())),
             },
             Err(e) => Some(Err(e)),
         }
@@ -1,13 +1,22 @@
-use crate::idtoken::IdToken;
-use crate::jwt::{Claim, JsonWebToken, JwtParser};
+use crate::encryption::{EncryptedData, EncryptionKey};
+use crate::idtoken::{IdToken, IdTokenClaims};
+use crate::jwt::{
+    Audience, Claim, JsonWebToken, JwtClaims, JwtParser, JwtValidationError, JwtValidator,
+};
+use crate::tokencache::normalize_scopes;
 use chrono::{DateTime, Duration, TimeZone, Utc};
 use chrono_humanize::HumanTime;
 use from_as::*;
-use graph_error::{GraphFailure, WithGraphError, WithGraphErrorAsync};
+#[cfg(feature = "blocking")]
+use graph_error::WithGraphError;
+use graph_error::{GraphFailure, WithGraphErrorAsync};
 use serde_aux::prelude::*;
+use serde_json::Value;
+use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::fmt;
 use std::io::{Read, Write};
+use zeroize::Zeroize;
 
 /// OAuth 2.0 Access Token
 ///
@@ -264,6 +273,47 @@ impl AccessToken {
         self.scope.as_ref()
     }
 
+    /// Returns true if the scope of the token has all of the scopes.
+    /// Scopes are compared without case or the Microsoft Graph resource
+    /// prefix, and offline_access, openid and profile are ignored since
+    /// they are not returned in the scope of a token response. Returns false
+    /// if no scopes are given.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::AccessToken;
+    /// let access_token = AccessToken::new("Bearer", 3600, "User.Read Mail.Send", "ASODFIUJ34KJ");
+    /// assert!(access_token.has_scopes(&["https://graph.microsoft.com/mail.send", "offline_access"]));
+    /// assert!(!access_token.has_scopes(&["Files.Read"]));
+    /// assert!(!access_token.has_scopes::<&str>(&[]));
+    /// ```
+    pub fn has_scopes<T: AsRef<str>>(&self, scopes: &[T]) -> bool {
+        let scopes = normalize_scopes(scopes);
+        let granted = normalize_scopes(self.scope.as_deref().unwrap_or_default().split(' '));
+        !scopes.is_empty() && scopes.is_subset(&granted)
+    }
+
+    /// The scopes of the token. These are the delegated permissions in the
+    /// scp claim of the access token or, for tokens that cannot be parsed,
+    /// the scope of the token response. App-only tokens have no scopes;
+    /// see [AccessToken::roles].
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::AccessToken;
+    /// let access_token = AccessToken::new("Bearer", 3600, "User.Read Mail.Send", "ASODFIUJ34KJ");
+    /// assert!(access_token.scopes_set().contains("Mail.Send"));
+    /// ```
+    pub fn scopes_set(&self) -> BTreeSet<String> {
+        let scopes = match self.claim("scp") {
+            Some(Value::String(scp)) => scp,
+            Some(_) => String::new(),
+            None if self.parsed_claims().is_some() => String::new(),
+            None => self.scope.clone().unwrap_or_default(),
+        };
+        scopes.split_whitespace().map(String::from).collect()
+    }
+
     /// Get the access token.
     ///
     /// # Example
@@ -299,11 +349,8 @@ impl AccessToken {
     /// let mut access_token = AccessToken::default();
     /// println!("{:#?}", access_token.refresh_token());
     /// ```
-    pub fn refresh_token(self) -> Option<String> {
-        match self.refresh_token {
-            Some(t) => Some(t),
-            None => None,
-        }
+    pub fn refresh_token(mut self) -> Option<String> {
+        self.refresh_token.take()
     }
 
     /// Get the id token.
@@ -319,6 +366,21 @@ impl AccessToken {
         self.id_token.clone()
     }
 
+    /// Validate the id token of the token response and get its claims.
+    /// Returns an error if the response does not have an id token, which
+    /// is only returned for the openid scope. The validator must have the
+    /// client id as the audience and the nonce of the authorization
+    /// request, if one was sent. See [JwtValidator::validate_id_token].
+    pub fn id_token_claims(
+        &self,
+        validator: &JwtValidator,
+    ) -> Result<IdTokenClaims, JwtValidationError> {
+        let id_token = self.id_token.as_ref().ok_or_else(|| {
+            JwtValidationError::Malformed("the token response does not have an id token".into())
+        })?;
+        validator.validate_id_token(id_token.as_str())
+    }
+
     /// Get the state.
     ///
     /// # Example
@@ -364,6 +426,33 @@ impl AccessToken {
         true
     }
 
+    /// Returns true if the access token is expired or expires within the
+    /// duration. Use a margin for clock skew and request latency when
+    /// deciding whether to refresh a token before a request. Tokens without
+    /// a timestamp are considered expired.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::AccessToken;
+    /// # use std::time::Duration;
+    /// let access_token = AccessToken::new("Bearer", 600, "User.Read", "ASODFIUJ34KJ");
+    /// assert!(!access_token.expires_within(Duration::from_secs(60)));
+    /// assert!(access_token.expires_within(Duration::from_secs(900)));
+    /// ```
+    pub fn expires_within(&self, duration: std::time::Duration) -> bool {
+        let timestamp = match self.timestamp {
+            Some(timestamp) => timestamp,
+            None => return true,
+        };
+        match Duration::from_std(duration)
+            .ok()
+            .and_then(|duration| Utc::now().checked_add_signed(duration))
+        {
+            Some(deadline) => timestamp <= deadline,
+            None => true,
+        }
+    }
+
     // TODO: This should checked using the bearer token.
     /// Get the time left in seconds until the access token expires.
     /// See the HumanTime crate. If you just need to know if the access token
@@ -385,6 +474,24 @@ impl AccessToken {
         None
     }
 
+    /// Encrypt the access token for storage using AES-256-GCM.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{AccessToken, EncryptionKey};
+    /// let key = EncryptionKey::generate().unwrap();
+    /// let access_token = AccessToken::new("Bearer", 3600, "User.Read", "ASODFIUJ34KJ;LADSK");
+    /// let encrypted = access_token.encrypt(&key).unwrap();
+    /// ```
+    pub fn encrypt(&self, key: &EncryptionKey) -> Result<EncryptedData, GraphFailure> {
+        EncryptedData::encrypt(self, key)
+    }
+
+    /// Decrypt an access token that was encrypted using [AccessToken::encrypt].
+    pub fn decrypt(data: &EncryptedData, key: &EncryptionKey) -> Result<AccessToken, GraphFailure> {
+        data.decrypt(key)
+    }
+
     fn parse_jwt(&mut self) {
         let mut set_timestamp = false;
         if let Ok(jwt) = JwtParser::parse(self.bearer_token()) {
@@ -393,10 +500,16 @@ impl AccessToken {
                     .iter()
                     .find(|item| item.key().eq(&String::from("exp")))
                 {
+                    // A malformed exp is ignored and expires_in is used instead.
                     let value = claim.value();
-                    let number = value.as_i64().unwrap();
-                    self.timestamp = Some(Utc.timestamp(number, 0));
-                    set_timestamp = true;
+                    let timestamp = value
+                        .as_i64()
+                        .or_else(|| value.as_str()?.parse().ok())
+                        .and_then(|exp| Utc.timestamp_opt(exp, 0).single());
+                    if let Some(timestamp) = timestamp {
+                        self.timestamp = Some(timestamp);
+                        set_timestamp = true;
+                    }
                 }
             }
             self.jwt = Some(jwt);
@@ -415,14 +528,105 @@ impl AccessToken {
         self.jwt.as_ref()
     }
 
-    pub(crate) async fn try_from_async(
+    /// Validate the access token and get its claims. An API that accepts
+    /// access tokens should make authorization decisions with these claims.
+    /// The validator must have the issuer and the audience of the API. See
+    /// [JwtValidator::validate].
+    ///
+    /// Tokens for other resources, such as Microsoft Graph, are meant for
+    /// that resource and may not be verifiable by the client.
+    pub fn validated_claims(
+        &self,
+        validator: &JwtValidator,
+    ) -> Result<JwtClaims, JwtValidationError> {
+        validator.validate(self.bearer_token())
+    }
+
+    /// The app roles in the roles claim of the access token. App-only
+    /// tokens have the application permissions granted to the app and
+    /// delegated tokens have the app roles assigned to the user.
+    ///
+    /// The claim helpers of AccessToken decode the token without verifying
+    /// its signature, issuer or audience, so anyone can forge them. They are
+    /// meant for the client that requested the token, such as for display or
+    /// to decide when to refresh, and must not be used for authorization
+    /// decisions. Use [AccessToken::validated_claims] for that.
+    pub fn roles(&self) -> Vec<String> {
+        match self.claim("roles") {
+            Some(Value::Array(roles)) => roles
+                .iter()
+                .filter_map(|role| role.as_str().map(String::from))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The audience (aud) claim of the access token, which is the resource
+    /// the token is for. The claim is not verified, see [AccessToken::roles].
+    pub fn audience(&self) -> Option<Audience> {
+        serde_json::from_value(self.claim("aud")?).ok()
+    }
+
+    /// The tenant id (tid) claim of the access token. The claim is not
+    /// verified, see [AccessToken::roles].
+    pub fn tenant_id(&self) -> Option<String> {
+        self.string_claim("tid")
+    }
+
+    /// The application id of the client the token was issued to. This is the
+    /// azp claim of v2.0 tokens or the appid claim of v1.0 tokens. The claims
+    /// are not verified, see [AccessToken::roles].
+    pub fn app_id(&self) -> Option<String> {
+        self.string_claim("azp")
+            .or_else(|| self.string_claim("appid"))
+    }
+
+    /// Returns true if the access token was issued to an application
+    /// without a signed in user, such as a token from the client
+    /// credentials grant. The idtyp optional claim is used when present,
+    /// otherwise a token without a scp claim is app-only. Returns false
+    /// for tokens that cannot be parsed. The claims are not verified, see
+    /// [AccessToken::roles].
+    pub fn is_app_only(&self) -> bool {
+        if let Some(idtyp) = self.string_claim("idtyp") {
+            return idtyp.eq_ignore_ascii_case("app");
+        }
+        self.parsed_claims().is_some() && self.claim("scp").is_none()
+    }
+
+    // The claims of the access token. Tokens that were deserialized have
+    // not been parsed yet.
+    fn parsed_claims(&self) -> Option<Vec<Claim>> {
+        match self.jwt.as_ref() {
+            Some(jwt) => jwt.claims(),
+            None => JwtParser::parse(self.bearer_token()).ok()?.claims(),
+        }
+    }
+
+    fn claim(&self, key: &str) -> Option<Value> {
+        self.parsed_claims()?
+            .into_iter()
+            .find(|claim| claim.key() == key)
+            .map(|claim| claim.value())
+    }
+
+    fn string_claim(&self, key: &str) -> Option<String> {
+        self.claim(key)?.as_str().map(String::from)
+    }
+
+    /// Send a token request and convert the response to an access token.
+    /// Error responses of the token endpoint are returned as
+    /// [GraphFailure::OAuthError]. This is the async version of
+    /// `AccessToken::try_from(reqwest::blocking::RequestBuilder)`.
+    pub async fn try_from_async(
         builder: reqwest::RequestBuilder,
     ) -> Result<AccessToken, GraphFailure> {
         let mut access_token = builder
             .send()
             .await?
             .with_graph_error()
-            .await?
+            .await
+            .map_err(GraphFailure::from_oauth_response)?
             .json::<AccessToken>()
             .await?;
 
@@ -431,6 +635,14 @@ impl AccessToken {
     }
 }
 
+impl Drop for AccessToken {
+    fn drop(&mut self) {
+        self.access_token.zeroize();
+        self.refresh_token.zeroize();
+        self.id_token.zeroize();
+    }
+}
+
 impl Default for AccessToken {
     fn default() -> Self {
         AccessToken {
@@ -458,6 +670,7 @@ impl TryFrom<&str> for AccessToken {
     }
 }
 
+#[cfg(feature = "blocking")]
 impl TryFrom<reqwest::blocking::RequestBuilder> for AccessToken {
     type Error = GraphFailure;
 
@@ -468,6 +681,7 @@ impl TryFrom<reqwest::blocking::RequestBuilder> for AccessToken {
     }
 }
 
+#[cfg(feature = "blocking")]
 impl TryFrom<Result<reqwest::blocking::Response, reqwest::Error>> for AccessToken {
     type Error = GraphFailure;
 
@@ -479,6 +693,7 @@ impl TryFrom<Result<reqwest::blocking::Response, reqwest::Error>> for AccessToke
     }
 }
 
+#[cfg(feature = "blocking")]
 impl TryFrom<reqwest::blocking::Response> for AccessToken {
     type Error = GraphFailure;
 
@@ -486,7 +701,10 @@ impl TryFrom<reqwest::blocking::Response> for AccessToken {
     where
         Self: for<'de> serde::Deserialize<'de>,
     {
-        let mut access_token = value.with_graph_error()?.json::<AccessToken>()?;
+        let mut access_token = value
+            .with_graph_error()
+            .map_err(GraphFailure::from_oauth_response)?
+            .json::<AccessToken>()?;
         access_token.parse_jwt();
         Ok(access_token)
     }
@@ -0,0 +1,166 @@
+use crate::auth::{AuthorizationRequest, OAuth, OAuthCredential, OAuthReq};
+use crate::oautherror::OAuthError;
+use crate::redirect::{redirect_params, AuthorizationErrorResponse, ResponseMode};
+use std::collections::HashMap;
+use url::Url;
+
+/// Successful response of the admin consent flow. The permissions of the
+/// application were granted for the tenant of the administrator.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AdminConsentResponse {
+    /// The tenant that granted the permissions of the application.
+    pub tenant: String,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+}
+
+impl AdminConsentResponse {
+    /// The scopes that were granted.
+    pub fn scopes(&self) -> Vec<&str> {
+        self.scope
+            .as_deref()
+            .map(|scope| scope.split_whitespace().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The parameters returned to the redirect URI after an administrator
+/// grants or declines the permissions of the application.
+///
+/// Use [OAuth::parse_admin_consent](crate::oauth::OAuth::parse_admin_consent)
+/// to also validate the state of the response.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum AdminConsentRedirect {
+    Granted(AdminConsentResponse),
+    Error(AuthorizationErrorResponse),
+}
+
+impl AdminConsentRedirect {
+    /// Parse the redirect URL or the query of the redirect.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::AdminConsentRedirect;
+    /// let response = AdminConsentRedirect::parse(
+    ///     "http://localhost:8000/redirect?admin_consent=True&tenant=fa00d692-e9c7-4460-a743-29f2956fd429&state=12345",
+    /// )
+    /// .unwrap();
+    ///
+    /// match response {
+    ///     AdminConsentRedirect::Granted(response) => {
+    ///         assert_eq!(response.tenant, "fa00d692-e9c7-4460-a743-29f2956fd429")
+    ///     }
+    ///     AdminConsentRedirect::Error(_) => panic!(),
+    /// }
+    /// ```
+    pub fn parse(input: &str) -> OAuthReq<AdminConsentRedirect> {
+        AdminConsentRedirect::from_params(redirect_params(input, Some(ResponseMode::Query)))
+    }
+
+    /// Create the response from the parameters of a redirect.
+    pub fn from_params(mut params: HashMap<String, String>) -> OAuthReq<AdminConsentRedirect> {
+        let state = params.remove("state");
+        if let Some(error) = params.remove("error") {
+            return Ok(AdminConsentRedirect::Error(AuthorizationErrorResponse {
+                error,
+                error_description: params.remove("error_description"),
+                error_uri: params.remove("error_uri"),
+                state,
+            }));
+        }
+
+        let admin_consent = params
+            .remove(OAuthCredential::AdminConsent.alias())
+            .unwrap_or_default();
+        if !admin_consent.eq_ignore_ascii_case("true") {
+            return OAuthError::invalid_data(
+                "admin consent response does not have admin_consent=True or an error",
+            );
+        }
+        let tenant = params
+            .remove("tenant")
+            .ok_or_else(|| OAuthError::invalid("admin consent response is missing tenant"))?;
+        Ok(AdminConsentRedirect::Granted(AdminConsentResponse {
+            tenant,
+            scope: params.remove("scope"),
+            state,
+        }))
+    }
+
+    pub fn state(&self) -> Option<&str> {
+        match self {
+            AdminConsentRedirect::Granted(response) => response.state.as_deref(),
+            AdminConsentRedirect::Error(response) => response.state.as_deref(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, AdminConsentRedirect::Error(_))
+    }
+}
+
+/// The admin consent flow. An administrator is sent to the admin consent
+/// endpoint of their tenant, or of the organizations authority, to grant
+/// the permissions in the scope for the whole tenant. The administrator is
+/// then redirected back with the tenant that granted the permissions.
+///
+/// The scope is usually the `.default` scope of a resource, such as
+/// `https://graph.microsoft.com/.default`, which requests the permissions
+/// configured for the application.
+///
+/// # Example
+/// ```
+/// # use graph_oauth::oauth::{Authority, OAuth};
+/// let mut oauth = OAuth::new();
+/// oauth
+///     .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+///     .authority(&Authority::Organizations)
+///     .redirect_uri("http://localhost:8000/redirect")
+///     .add_scope("https://graph.microsoft.com/.default")
+///     .state("12345");
+///
+/// let url = oauth.build().admin_consent().url().unwrap();
+/// assert_eq!(url.path(), "/organizations/v2.0/adminconsent");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AdminConsentGrant {
+    oauth: OAuth,
+}
+
+impl AdminConsentGrant {
+    pub(crate) fn new(oauth: OAuth) -> AdminConsentGrant {
+        AdminConsentGrant { oauth }
+    }
+
+    /// The admin consent url with the client id, redirect uri, scope and
+    /// state of OAuth.
+    pub fn url(&mut self) -> OAuthReq<Url> {
+        self.oauth.get_or_else(OAuthCredential::ClientId)?;
+        self.oauth.get_or_else(OAuthCredential::RedirectURI)?;
+        if self.oauth.get_scopes().is_empty() {
+            return Err(OAuthError::credential_error(OAuthCredential::Scopes));
+        }
+        self.oauth.encode_admin_consent_url()
+    }
+
+    pub fn browser_authorization(&mut self) -> OAuthReq<AuthorizationRequest> {
+        Ok(AuthorizationRequest::new(self.url()?.to_string()))
+    }
+
+    /// Parse the redirect of the admin consent request and check the state.
+    pub fn parse_response(&mut self, input: &str) -> OAuthReq<AdminConsentRedirect> {
+        self.oauth.parse_admin_consent(input)
+    }
+}
+
+impl From<AdminConsentGrant> for OAuth {
+    fn from(grant: AdminConsentGrant) -> Self {
+        grant.oauth
+    }
+}
+
+impl AsRef<OAuth> for AdminConsentGrant {
+    fn as_ref(&self) -> &OAuth {
+        &self.oauth
+    }
+}
@@ -1,23 +1,41 @@
 use crate::accesstoken::AccessToken;
-use crate::grants::{GrantRequest, GrantType};
+use crate::adminconsent::{AdminConsentGrant, AdminConsentRedirect};
+use crate::authority::Authority;
+use crate::bearerassertion::{check_assertion, AsyncBearerAssertionGrant, BearerAssertionGrant};
+use crate::certificate::ClientCertificateCredential;
+use crate::claimschallenge::ClaimsChallenge;
+use crate::devicecode::{AsyncDeviceCodeGrant, DeviceCodeGrant, DEVICE_CODE_GRANT_TYPE};
+use crate::encryption::{EncryptedOAuth, EncryptionKey, OAuthSecrets};
+use crate::grants::{GrantRequest, GrantType, JWT_BEARER_GRANT_TYPE};
+use crate::httpclient::TokenHttpClient;
 use crate::idtoken::IdToken;
+use crate::loopback::LoopbackServer;
 use crate::oautherror::OAuthError;
+use crate::redirect::{RedirectResponse, ResponseMode};
 use crate::strum::IntoEnumIterator;
+use crate::tokencache::{
+    expires_soon, normalize_scopes, scope_resources, CachedToken, TokenCache, TokenCacheKey,
+};
 use from_as::*;
-use graph_error::GraphFailure;
+use graph_error::{GraphFailure, OAuthErrorResponse};
 use ring::rand::SecureRandom;
 use std::collections::btree_map::BTreeMap;
 use std::collections::{BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::fmt;
-use std::io::{Read, Write};
+use std::io::{ErrorKind, Read, Write};
 use std::marker::PhantomData;
+#[cfg(feature = "browser")]
 use std::process::Output;
 use url::form_urlencoded::Serializer;
 use url::Url;
+use zeroize::Zeroize;
 
 pub type OAuthReq<T> = Result<T, GraphFailure>;
 
+pub(crate) const CLIENT_ASSERTION_TYPE: &str =
+    "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
 /// Fields that represent common OAuth credentials.
 #[derive(
     Debug,
@@ -64,8 +82,14 @@ pub enum OAuthCredential {
     PostLogoutRedirectURI,
     LogoutURL,
     AdminConsent,
+    AdminConsentURL,
     Username,
     Password,
+    DeviceCodeURL,
+    DeviceCode,
+    Assertion,
+    RequestedTokenUse,
+    Claims,
 }
 
 impl OAuthCredential {
@@ -100,12 +124,21 @@ impl OAuthCredential {
             OAuthCredential::LogoutURL => "logout_url",
             OAuthCredential::PostLogoutRedirectURI => "post_logout_redirect_uri",
             OAuthCredential::AdminConsent => "admin_consent",
+            OAuthCredential::AdminConsentURL => "admin_consent_url",
             OAuthCredential::Username => "username",
             OAuthCredential::Password => "password",
+            OAuthCredential::DeviceCodeURL => "device_code_url",
+            OAuthCredential::DeviceCode => "device_code",
+            OAuthCredential::Assertion => "assertion",
+            OAuthCredential::RequestedTokenUse => "requested_token_use",
+            OAuthCredential::Claims => "claims",
         }
     }
 
-    fn is_debug_redacted(&self) -> bool {
+    /// Returns true for credentials that are secrets. These are redacted
+    /// from debug output, encrypted by [OAuth::encrypt] and zeroed in
+    /// memory when the OAuth instance is dropped.
+    pub fn is_sensitive(&self) -> bool {
         matches!(
             self,
             OAuthCredential::ClientId
@@ -117,10 +150,17 @@ impl OAuthCredential {
                 | OAuthCredential::CodeChallenge
                 | OAuthCredential::Password
                 | OAuthCredential::AccessCode
+                | OAuthCredential::DeviceCode
+                | OAuthCredential::Assertion
+                | OAuthCredential::ClientAssertion
         )
     }
 }
 
+pub(crate) fn is_sensitive_key(key: &str) -> bool {
+    OAuthCredential::iter().any(|oac| oac.alias().eq(key) && oac.is_sensitive())
+}
+
 impl ToString for OAuthCredential {
     fn to_string(&self) -> String {
         self.alias().to_string()
@@ -158,6 +198,12 @@ pub struct OAuth {
     access_token: Option<AccessToken>,
     scopes: BTreeSet<String>,
     credentials: BTreeMap<String, String>,
+    #[serde(default)]
+    tokens: Vec<AccessToken>,
+    #[serde(skip)]
+    certificate: Option<ClientCertificateCredential>,
+    #[serde(skip)]
+    http_client: TokenHttpClient,
 }
 
 impl OAuth {
@@ -174,6 +220,25 @@ impl OAuth {
             access_token: None,
             scopes: BTreeSet::new(),
             credentials: BTreeMap::new(),
+            tokens: Vec::new(),
+            certificate: None,
+            http_client: TokenHttpClient::default(),
+        }
+    }
+
+    pub(crate) fn from_parts(
+        scopes: BTreeSet<String>,
+        credentials: BTreeMap<String, String>,
+        access_token: Option<AccessToken>,
+        tokens: Vec<AccessToken>,
+    ) -> OAuth {
+        OAuth {
+            access_token,
+            scopes,
+            credentials,
+            tokens,
+            certificate: None,
+            http_client: TokenHttpClient::default(),
         }
     }
 
@@ -198,13 +263,17 @@ impl OAuth {
             | OAuthCredential::PostLogoutRedirectURI
             | OAuthCredential::AccessTokenURL
             | OAuthCredential::AuthorizeURL
-            | OAuthCredential::LogoutURL => {
+            | OAuthCredential::LogoutURL
+            | OAuthCredential::DeviceCodeURL
+            | OAuthCredential::AdminConsentURL => {
                 Url::parse(v.as_ref()).unwrap();
             }
             _ => {}
         }
 
-        self.credentials.insert(oac.to_string(), v);
+        if let Some(mut previous) = self.credentials.insert(oac.to_string(), v) {
+            previous.zeroize();
+        }
         self
     }
 
@@ -227,7 +296,9 @@ impl OAuth {
             | OAuthCredential::PostLogoutRedirectURI
             | OAuthCredential::AccessTokenURL
             | OAuthCredential::AuthorizeURL
-            | OAuthCredential::LogoutURL => {
+            | OAuthCredential::LogoutURL
+            | OAuthCredential::DeviceCodeURL
+            | OAuthCredential::AdminConsentURL => {
                 Url::parse(v.as_ref()).unwrap();
             }
             _ => {}
@@ -286,7 +357,9 @@ impl OAuth {
     /// assert_eq!(oauth.contains(OAuthCredential::ClientId), false);
     /// ```
     pub fn remove(&mut self, oac: OAuthCredential) -> &mut OAuth {
-        self.credentials.remove(oac.alias());
+        if let Some(mut value) = self.credentials.remove(oac.alias()) {
+            value.zeroize();
+        }
         self
     }
 
@@ -634,6 +707,116 @@ impl OAuth {
         self.insert(OAuthCredential::ClientAssertionType, value)
     }
 
+    /// Authenticate the client with a certificate. A signed client assertion
+    /// is generated for each token request of grants that support client
+    /// assertions and the client secret is not sent.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// # use graph_oauth::oauth::{ClientCertificateCredential, OAuth};
+    /// # let mut oauth = OAuth::new();
+    /// let credential = ClientCertificateCredential::from_pem(
+    ///     "<CLIENT_ID>",
+    ///     &std::fs::read("key.pem").unwrap(),
+    ///     &std::fs::read("cert.pem").unwrap(),
+    /// ).unwrap();
+    /// oauth.client_certificate(credential);
+    /// ```
+    pub fn client_certificate(&mut self, credential: ClientCertificateCredential) -> &mut OAuth {
+        self.certificate = Some(credential);
+        self
+    }
+
+    /// The certificate credential used for client authentication.
+    pub fn get_client_certificate(&self) -> Option<&ClientCertificateCredential> {
+        self.certificate.as_ref()
+    }
+
+    /// Set the client used to send the token and device code requests
+    /// of the async grants. Use this to send token requests through a
+    /// proxy, with custom TLS or timeouts, or to the same client as the
+    /// Graph requests. A new default client is used when it is not set.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// let client = reqwest::Client::builder()
+    ///     .timeout(std::time::Duration::from_secs(30))
+    ///     .build()
+    ///     .unwrap();
+    /// oauth.http_client(client);
+    /// ```
+    pub fn http_client(&mut self, client: reqwest::Client) -> &mut OAuth {
+        self.http_client.http_client(client);
+        self
+    }
+
+    /// Set the client used to send the token and device code requests
+    /// of the blocking grants. A new default client is used when it is
+    /// not set.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// let client = reqwest::blocking::Client::builder()
+    ///     .timeout(std::time::Duration::from_secs(30))
+    ///     .build()
+    ///     .unwrap();
+    /// oauth.blocking_http_client(client);
+    /// ```
+    #[cfg(feature = "blocking")]
+    pub fn blocking_http_client(&mut self, client: reqwest::blocking::Client) -> &mut OAuth {
+        self.http_client.blocking_http_client(client);
+        self
+    }
+
+    /// Send the token and device code requests with a
+    /// [TokenHttpClient] shared with other requests, such as the token
+    /// credentials, [JwksCache](crate::oauth::jwkscache::JwksCache) or
+    /// the Graph client. This replaces the clients set with
+    /// [OAuth::http_client] and `OAuth::blocking_http_client`.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{OAuth, TokenHttpClient};
+    /// # let mut oauth = OAuth::new();
+    /// let http_client = TokenHttpClient::new();
+    /// oauth.share_http_client(&http_client);
+    /// ```
+    pub fn share_http_client(&mut self, http_client: &TokenHttpClient) -> &mut OAuth {
+        self.http_client = http_client.clone();
+        self
+    }
+
+    /// Set the authorization, access token, refresh token and logout
+    /// urls of an authority. The admin consent url is only set for the
+    /// organizations authority and single tenants, and is removed for
+    /// other authorities.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{Authority, OAuth, OAuthCredential};
+    /// # let mut oauth = OAuth::new();
+    /// oauth.authority(&Authority::b2c("contoso", "B2C_1_signupsignin"));
+    /// assert_eq!(
+    ///     oauth.get(OAuthCredential::AuthorizeURL).unwrap(),
+    ///     "https://contoso.b2clogin.com/contoso.onmicrosoft.com/B2C_1_signupsignin/oauth2/v2.0/authorize"
+    /// );
+    /// ```
+    pub fn authority(&mut self, authority: &Authority) -> &mut OAuth {
+        self.authorize_url(authority.authorize_url().as_str())
+            .access_token_url(authority.token_url().as_str())
+            .refresh_token_url(authority.token_url().as_str())
+            .logout_url(authority.logout_url().as_str());
+        if authority.has_admin_consent() {
+            self.admin_consent_url(authority.admin_consent_url().as_str())
+        } else {
+            self.remove(OAuthCredential::AdminConsentURL)
+        }
+    }
+
     /// Set the url to send a post request that will log out the user.
     ///
     /// # Example
@@ -684,6 +867,107 @@ impl OAuth {
         self.insert(OAuthCredential::Password, value)
     }
 
+    /// Set the url to request a device code from for the device code flow.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{OAuth, OAuthCredential};
+    /// # let mut oauth = OAuth::new();
+    /// oauth.device_code_url("https://login.microsoftonline.com/common/oauth2/v2.0/devicecode");
+    /// assert!(oauth.contains(OAuthCredential::DeviceCodeURL))
+    /// ```
+    pub fn device_code_url(&mut self, value: &str) -> &mut OAuth {
+        self.insert(OAuthCredential::DeviceCodeURL, value)
+    }
+
+    /// Set the url of the admin consent endpoint.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{OAuth, OAuthCredential};
+    /// # let mut oauth = OAuth::new();
+    /// oauth.admin_consent_url("https://login.microsoftonline.com/organizations/v2.0/adminconsent");
+    /// assert!(oauth.contains(OAuthCredential::AdminConsentURL))
+    /// ```
+    pub fn admin_consent_url(&mut self, value: &str) -> &mut OAuth {
+        self.insert(OAuthCredential::AdminConsentURL, value)
+    }
+
+    /// Set the device code returned by the device authorization request.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{OAuth, OAuthCredential};
+    /// # let mut oauth = OAuth::new();
+    /// oauth.device_code("device_code");
+    /// assert!(oauth.contains(OAuthCredential::DeviceCode))
+    /// ```
+    pub fn device_code(&mut self, value: &str) -> &mut OAuth {
+        self.insert(OAuthCredential::DeviceCode, value)
+    }
+
+    /// Set the assertion for the on-behalf-of flow or the JWT bearer grant.
+    /// This is the access token that was sent to the middle-tier API or
+    /// the JWT issued by a trusted identity provider.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{OAuth, OAuthCredential};
+    /// # let mut oauth = OAuth::new();
+    /// oauth.assertion("assertion");
+    /// assert!(oauth.contains(OAuthCredential::Assertion))
+    /// ```
+    pub fn assertion(&mut self, value: &str) -> &mut OAuth {
+        self.insert(OAuthCredential::Assertion, value)
+    }
+
+    /// Set the assertion for the SAML bearer grants from the XML of a SAML
+    /// assertion. The assertion is sent base64url encoded.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{OAuth, OAuthCredential};
+    /// # let mut oauth = OAuth::new();
+    /// oauth.saml_assertion("<saml:Assertion>...</saml:Assertion>");
+    /// assert_eq!(
+    ///     oauth.get(OAuthCredential::Assertion).unwrap(),
+    ///     "PHNhbWw6QXNzZXJ0aW9uPi4uLjwvc2FtbDpBc3NlcnRpb24-"
+    /// );
+    /// ```
+    pub fn saml_assertion(&mut self, saml: &str) -> &mut OAuth {
+        let assertion = base64::encode_config(saml.trim(), base64::URL_SAFE_NO_PAD);
+        self.insert(OAuthCredential::Assertion, assertion)
+    }
+
+    /// Set how the request should be processed. For the on-behalf-of
+    /// flow the value is on_behalf_of which is set by default.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{OAuth, OAuthCredential};
+    /// # let mut oauth = OAuth::new();
+    /// oauth.requested_token_use("on_behalf_of");
+    /// assert!(oauth.contains(OAuthCredential::RequestedTokenUse))
+    /// ```
+    pub fn requested_token_use(&mut self, value: &str) -> &mut OAuth {
+        self.insert(OAuthCredential::RequestedTokenUse, value)
+    }
+
+    /// Set the claims requested by a claims challenge. The claims are
+    /// a JSON object such as the one returned by
+    /// [ClaimsChallenge::claims](crate::oauth::ClaimsChallenge::claims).
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{OAuth, OAuthCredential};
+    /// # let mut oauth = OAuth::new();
+    /// oauth.claims(r#"{"access_token":{"acrs":{"essential":true,"value":"c1"}}}"#);
+    /// assert!(oauth.contains(OAuthCredential::Claims))
+    /// ```
+    pub fn claims(&mut self, value: &str) -> &mut OAuth {
+        self.insert(OAuthCredential::Claims, value)
+    }
+
     /// Add a scope' for the OAuth URL.
     ///
     /// # Example
@@ -862,114 +1146,978 @@ impl OAuth {
         }
     }
 
-    pub fn build(&mut self) -> GrantSelector<AccessTokenGrant> {
-        GrantSelector {
-            oauth: self.clone(),
-            t: PhantomData,
-        }
+    /// Sign in a user with the authorization code grant and PKCE using a
+    /// loopback redirect server.
+    ///
+    /// A [LoopbackServer] is bound on a free port of `127.0.0.1` and its
+    /// address is set as the redirect URI, so `http://127.0.0.1/redirect`
+    /// must be registered as a redirect URI of the application. The
+    /// user's browser is opened to the authorization URL and the server
+    /// waits for the redirect until the timeout elapses. The state returned
+    /// in the redirect is checked against the state that was sent and the
+    /// code is exchanged for an access token using the code verifier.
+    ///
+    /// A random state and a code challenge and verifier are generated if
+    /// they have not already been set. The access token is also set as the
+    /// access token of OAuth.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// # use graph_oauth::oauth::{OAuth, DEFAULT_LOOPBACK_TIMEOUT};
+    /// let mut oauth = OAuth::new();
+    /// oauth
+    ///     .client_id("<CLIENT_ID>")
+    ///     .add_scope("User.Read")
+    ///     .add_scope("offline_access")
+    ///     .authorize_url("https://login.microsoftonline.com/common/oauth2/v2.0/authorize")
+    ///     .access_token_url("https://login.microsoftonline.com/common/oauth2/v2.0/token")
+    ///     .refresh_token_url("https://login.microsoftonline.com/common/oauth2/v2.0/token");
+    ///
+    /// let access_token = oauth.loopback_sign_in(DEFAULT_LOOPBACK_TIMEOUT).unwrap();
+    /// ```
+    #[cfg(all(feature = "blocking", feature = "browser"))]
+    pub fn loopback_sign_in(&mut self, timeout: std::time::Duration) -> OAuthReq<AccessToken> {
+        self.loopback_sign_in_with(timeout, |request| request.open().map(|_| ()))
+    }
+
+    /// Same as [OAuth::loopback_sign_in] but calls `open` with the
+    /// authorization request instead of opening the default browser.
+    /// This can be used to print the authorization URL or open a
+    /// specific browser.
+    #[cfg(feature = "blocking")]
+    pub fn loopback_sign_in_with<F>(
+        &mut self,
+        timeout: std::time::Duration,
+        open: F,
+    ) -> OAuthReq<AccessToken>
+    where
+        F: FnOnce(&AuthorizationRequest) -> OAuthReq<()>,
+    {
+        let server = self.loopback_authorization()?;
+        open(
+            &self
+                .build()
+                .authorization_code_grant()
+                .browser_authorization()?,
+        )?;
+        self.loopback_access_code(server.wait_for_redirect(timeout)?)?;
+        let result = self
+            .build()
+            .authorization_code_grant()
+            .access_token()
+            .send();
+        self.loopback_complete(result)
+    }
+
+    /// Sign in a user with the authorization code grant and PKCE using a
+    /// loopback redirect server without blocking the async runtime. See
+    /// [OAuth::loopback_sign_in].
+    #[cfg(feature = "browser")]
+    pub async fn async_loopback_sign_in(
+        &mut self,
+        timeout: std::time::Duration,
+    ) -> OAuthReq<AccessToken> {
+        self.async_loopback_sign_in_with(timeout, |request| request.open().map(|_| ()))
+            .await
     }
 
-    pub fn build_async(&mut self) -> GrantSelector<AsyncAccessTokenGrant> {
-        GrantSelector {
-            oauth: self.clone(),
-            t: PhantomData,
+    /// Same as [OAuth::async_loopback_sign_in] but calls `open` with the
+    /// authorization request instead of opening the default browser.
+    pub async fn async_loopback_sign_in_with<F>(
+        &mut self,
+        timeout: std::time::Duration,
+        open: F,
+    ) -> OAuthReq<AccessToken>
+    where
+        F: FnOnce(&AuthorizationRequest) -> OAuthReq<()>,
+    {
+        let server = self.loopback_authorization()?;
+        open(
+            &self
+                .build_async()
+                .authorization_code_grant()
+                .browser_authorization()?,
+        )?;
+        self.loopback_access_code(server.async_wait_for_redirect(timeout).await?)?;
+        let result = self
+            .build_async()
+            .authorization_code_grant()
+            .access_token()
+            .send()
+            .await;
+        self.loopback_complete(result)
+    }
+
+    // Bind the loopback server and set the redirect uri, state and
+    // code verifier of the authorization request.
+    fn loopback_authorization(&mut self) -> OAuthReq<LoopbackServer> {
+        self.get_or_else(OAuthCredential::AuthorizeURL)?;
+        self.get_or_else(OAuthCredential::AccessTokenURL)?;
+        let server = LoopbackServer::bind("/redirect")?;
+        self.redirect_uri(&server.redirect_uri());
+        if !self.contains(OAuthCredential::State) {
+            let mut buf = [0; 16];
+            ring::rand::SystemRandom::new().fill(&mut buf)?;
+            self.state(&base64::encode_config(buf, base64::URL_SAFE_NO_PAD));
         }
+        if !self.contains(OAuthCredential::CodeVerifier) {
+            self.generate_sha256_challenge_and_verifier()?;
+        }
+        Ok(server)
+    }
+
+    fn loopback_access_code(&mut self, params: HashMap<String, String>) -> OAuthReq<()> {
+        let response = RedirectResponse::from_params(params)?;
+        self.check_redirect_state(&response)?;
+        let code = match response {
+            RedirectResponse::Code(response) => response.code,
+            RedirectResponse::Error(error) => {
+                return OAuthError::invalid_data(&format!(
+                    "authorization request failed: {}",
+                    error
+                ));
+            }
+            RedirectResponse::Implicit(_) => {
+                return Err(OAuthError::credential_error(OAuthCredential::AccessCode));
+            }
+        };
+        self.access_code(&code);
+        Ok(())
     }
 
-    /// Sign the user out using the OneDrive v1.0 endpoint.
+    fn loopback_complete(&mut self, result: OAuthReq<AccessToken>) -> OAuthReq<AccessToken> {
+        self.remove(OAuthCredential::AccessCode);
+        let access_token = result?;
+        self.access_token(access_token.clone());
+        Ok(access_token)
+    }
+
+    /// Parse the authorization response that is returned to the redirect
+    /// URI. The input can be the full redirect URL or the body of a
+    /// form_post response. The response mode set for OAuth decides whether
+    /// the query or the fragment of a URL is used.
+    ///
+    /// If a state was set for OAuth the state of the response must match.
+    /// Error responses are returned as [RedirectResponse::Error] so that the
+    /// caller can show the reason to the user.
     ///
     /// # Example
-    /// ```rust,ignore
-    /// use graph_oauth::oauth::OAuth;
+    /// ```
+    /// # use graph_oauth::oauth::{OAuth, RedirectResponse};
     /// let mut oauth = OAuth::new();
+    /// oauth.state("12345");
     ///
-    /// oauth.v1_logout().unwrap();
+    /// let response = oauth
+    ///     .parse_redirect("http://localhost:8000/redirect?code=M0ab92efe&state=12345")
+    ///     .unwrap();
+    /// if let RedirectResponse::Code(response) = response {
+    ///     oauth.access_code(&response.code);
+    /// }
+    ///
+    /// assert!(oauth
+    ///     .parse_redirect("http://localhost:8000/redirect?code=M0ab92efe&state=67890")
+    ///     .is_err());
     /// ```
-    pub fn v1_logout(&mut self) -> OAuthReq<Output> {
-        let mut url = self.get_or_else(OAuthCredential::LogoutURL)?;
-        if !url.ends_with('?') {
-            url.push('?');
+    pub fn parse_redirect(&self, input: &str) -> OAuthReq<RedirectResponse> {
+        let response_mode = self
+            .get(OAuthCredential::ResponseMode)
+            .and_then(|response_mode| response_mode.parse::<ResponseMode>().ok());
+        let response = RedirectResponse::parse(input, response_mode)?;
+        self.check_redirect_state(&response)?;
+        Ok(response)
+    }
+
+    /// Parse the response of an admin consent request that is returned to
+    /// the redirect URI. If a state was set for OAuth the state of the
+    /// response must match. When the permissions were granted the
+    /// [OAuthCredential::AdminConsent] credential is set.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{AdminConsentRedirect, OAuth, OAuthCredential};
+    /// let mut oauth = OAuth::new();
+    /// oauth.state("12345");
+    ///
+    /// let response = oauth
+    ///     .parse_admin_consent("http://localhost:8000/redirect?admin_consent=True&tenant=fa00d692-e9c7-4460-a743-29f2956fd429&state=12345")
+    ///     .unwrap();
+    /// assert!(!response.is_error());
+    /// assert_eq!(oauth.get(OAuthCredential::AdminConsent).unwrap(), "True");
+    /// ```
+    pub fn parse_admin_consent(&mut self, input: &str) -> OAuthReq<AdminConsentRedirect> {
+        let response = AdminConsentRedirect::parse(input)?;
+        self.check_state(response.state(), response.is_error())?;
+        if !response.is_error() {
+            self.insert(OAuthCredential::AdminConsent, "True");
         }
+        Ok(response)
+    }
 
-        let mut vec = vec![
-            url,
-            "&client_id=".to_string(),
-            self.get_or_else(OAuthCredential::ClientId)?,
-            "&redirect_uri=".to_string(),
-        ];
+    fn check_redirect_state(&self, response: &RedirectResponse) -> OAuthReq<()> {
+        self.check_state(response.state(), response.is_error())
+    }
+
+    pub(crate) fn check_state(&self, returned: Option<&str>, is_error: bool) -> OAuthReq<()> {
+        if let Some(state) = self.get(OAuthCredential::State) {
+            match returned {
+                Some(returned) => {
+                    ring::constant_time::verify_slices_are_equal(
+                        state.as_bytes(),
+                        returned.as_bytes(),
+                    )
+                    .map_err(|_| {
+                        OAuthError::invalid("state in authorization response does not match")
+                    })?;
+                }
+                None if is_error => {}
+                None => return OAuthError::invalid_data("authorization response is missing state"),
+            }
+        }
+        Ok(())
+    }
 
-        if let Some(redirect) = self.get(OAuthCredential::PostLogoutRedirectURI) {
-            vec.push(redirect)
-        } else if let Some(redirect) = self.get(OAuthCredential::RedirectURI) {
-            vec.push(redirect);
+    /// Encrypt OAuth for storage. Sensitive credentials and the access token
+    /// are encrypted using AES-256-GCM and the remaining credentials and
+    /// scopes are stored in plain text. A client certificate is not included.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{EncryptionKey, OAuth};
+    /// # let mut oauth = OAuth::new();
+    /// oauth.client_secret("<CLIENT_SECRET>");
+    ///
+    /// let key = EncryptionKey::generate().unwrap();
+    /// let encrypted = oauth.encrypt(&key).unwrap();
+    /// assert_eq!(encrypted.decrypt(&key).unwrap(), oauth);
+    /// ```
+    pub fn encrypt(&self, key: &EncryptionKey) -> OAuthReq<EncryptedOAuth> {
+        let mut secrets = OAuthSecrets {
+            credentials: BTreeMap::new(),
+            access_token: self.access_token.clone(),
+            tokens: self.tokens.clone(),
+        };
+        let mut credentials = BTreeMap::new();
+        for (key, value) in self.credentials.iter() {
+            if is_sensitive_key(key) {
+                secrets.credentials.insert(key.clone(), value.clone());
+            } else {
+                credentials.insert(key.clone(), value.clone());
+            }
         }
-        webbrowser::open(vec.join("").as_str()).map_err(GraphFailure::from)
+        EncryptedOAuth::seal(key, self.scopes.clone(), credentials, &secrets)
     }
 
-    /// Sign the user out using the OneDrive v2.0 endpoint.
+    /// Get an access token for the scopes of OAuth from a token cache without
+    /// user interaction. If the cache does not have an unexpired access token
+    /// for the account and scopes the most recent refresh token of the account
+    /// is used to request a new access token which is then stored in the cache.
+    ///
+    /// The returned access token is also set as the access token of OAuth.
     ///
     /// # Example
     /// ```rust,ignore
-    /// use graph_oauth::oauth::OAuth;
-    /// let mut oauth = OAuth::new();
+    /// # use graph_oauth::oauth::{EncryptionKey, FileTokenCache, GrantType, OAuth, TokenCache};
+    /// # let mut oauth = OAuth::new();
+    /// let key = EncryptionKey::from_passphrase("correct horse battery staple");
+    /// let mut cache = FileTokenCache::new("./token_cache.json", key);
+    /// let account = cache.accounts().unwrap().remove(0);
+    /// let access_token = oauth
+    ///     .acquire_token_silent(&mut cache, GrantType::AuthorizationCode, &account.home_account_id)
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "blocking")]
+    pub fn acquire_token_silent<C: TokenCache + ?Sized>(
+        &mut self,
+        cache: &mut C,
+        grant: GrantType,
+        home_account_id: &str,
+    ) -> OAuthReq<AccessToken> {
+        let (authority, client_id) = self.token_cache_authority()?;
+        if let Some(cached) = cache.find(&authority, &client_id, home_account_id, &self.scopes)? {
+            self.access_token(cached.access_token().clone());
+            return Ok(cached.access_token().clone());
+        }
+
+        let (oauth, refresh_token) =
+            self.cached_refresh_token(&*cache, &authority, &client_id, home_account_id)?;
+        let access_token = AccessTokenGrant { oauth, grant }.refresh_token().send()?;
+        let key = self.refreshed_token_key(&authority, &client_id, home_account_id, &access_token);
+        self.cache_refreshed_token(cache, key, access_token, refresh_token)
+    }
+
+    /// Get an access token for the scopes of OAuth from a token cache without
+    /// user interaction. See [OAuth::acquire_token_silent].
+    pub async fn async_acquire_token_silent<C: TokenCache + ?Sized>(
+        &mut self,
+        cache: &mut C,
+        grant: GrantType,
+        home_account_id: &str,
+    ) -> OAuthReq<AccessToken> {
+        let (authority, client_id) = self.token_cache_authority()?;
+        if let Some(cached) = cache.find(&authority, &client_id, home_account_id, &self.scopes)? {
+            self.access_token(cached.access_token().clone());
+            return Ok(cached.access_token().clone());
+        }
+
+        let (oauth, refresh_token) =
+            self.cached_refresh_token(&*cache, &authority, &client_id, home_account_id)?;
+        let access_token = AsyncAccessTokenGrant { oauth, grant }
+            .refresh_token()
+            .send()
+            .await?;
+        let key = self.refreshed_token_key(&authority, &client_id, home_account_id, &access_token);
+        self.cache_refreshed_token(cache, key, access_token, refresh_token)
+    }
+
+    /// Hold an access token for the scopes of its token response, or the
+    /// scopes of OAuth if the response does not have a scope. A token that
+    /// was held for the same scopes is replaced. Held tokens are used by
+    /// [OAuth::acquire_token_for_scopes] so that an application can keep
+    /// tokens for Microsoft Graph and other APIs, or for scopes that were
+    /// consented to at different times, in a single OAuth instance.
     ///
-    /// oauth.v2_logout().unwrap();
+    /// # Example
     /// ```
-    pub fn v2_logout(&self) -> OAuthReq<Output> {
-        let mut url = self.get_or_else(OAuthCredential::LogoutURL)?;
-        if !url.ends_with('?') {
-            url.push('?');
+    /// # use graph_oauth::oauth::{AccessToken, OAuth};
+    /// let mut oauth = OAuth::new();
+    /// oauth.add_token(AccessToken::new("Bearer", 3600, "User.Read", "ASODFIUJ34KJ"));
+    /// oauth.add_token(AccessToken::new("Bearer", 3600, "https://storage.azure.com/user_impersonation", "LKJ3LK2J"));
+    ///
+    /// assert_eq!(oauth.tokens().len(), 2);
+    /// assert_eq!(
+    ///     oauth.token_for_scopes(&["https://storage.azure.com/user_impersonation"]).unwrap().bearer_token(),
+    ///     "LKJ3LK2J"
+    /// );
+    /// ```
+    pub fn add_token(&mut self, mut access_token: AccessToken) -> &mut OAuth {
+        if access_token
+            .scopes()
+            .filter(|scope| !scope.is_empty())
+            .is_none()
+            && !self.scopes.is_empty()
+        {
+            access_token.set_scope(&self.join_scopes(" "));
         }
-        if let Some(redirect) = self.get(OAuthCredential::PostLogoutRedirectURI) {
-            url.push_str("post_logout_redirect_uri=");
-            url.push_str(redirect.as_str());
-        } else {
-            let redirect_uri = self.get_or_else(OAuthCredential::RedirectURI)?;
-            url.push_str("post_logout_redirect_uri=");
-            url.push_str(redirect_uri.as_str());
+        let scopes = normalize_scopes(access_token.scopes().unwrap_or(&String::new()).split(' '));
+        self.tokens.retain(|token| {
+            normalize_scopes(token.scopes().unwrap_or(&String::new()).split(' ')) != scopes
+        });
+        self.tokens.push(access_token);
+        self
+    }
+
+    /// The tokens held for each set of scopes.
+    pub fn tokens(&self) -> &[AccessToken] {
+        self.tokens.as_slice()
+    }
+
+    /// Remove the held tokens.
+    pub fn clear_tokens(&mut self) -> &mut OAuth {
+        self.tokens.clear();
+        self
+    }
+
+    /// The held token, or the access token of OAuth, that has all of the
+    /// scopes and does not expire in the next 5 minutes. Returns None if
+    /// no scopes are given.
+    pub fn token_for_scopes<T: AsRef<str>>(&self, scopes: &[T]) -> Option<&AccessToken> {
+        self.tokens
+            .iter()
+            .chain(self.access_token.iter())
+            .filter(|token| token.has_scopes(scopes) && !expires_soon(token))
+            .max_by_key(|token| token.timestamp())
+    }
+
+    /// Get an access token for the scopes, requesting one only when the
+    /// tokens of OAuth do not already have all of the scopes.
+    ///
+    /// If no token has the scopes, an authorization code that was set with
+    /// [OAuth::access_code] is exchanged for a token with the scopes. When
+    /// there is no code the refresh token of a held token is used, since a
+    /// refresh token can be used to get tokens for any scopes the user has
+    /// consented to. The new token is held for the scopes. It is also set as
+    /// the access token of OAuth unless that token is for a different
+    /// resource, such as a token for Microsoft Graph when the new token is
+    /// for Azure Storage.
+    ///
+    /// Returns an error if no scopes are given.
+    ///
+    /// If the user still has to consent to the scopes an error is returned
+    /// for which [OAuthErrorResponse::requires_interaction] is true. Send
+    /// the user to [OAuth::incremental_consent_url] and then call this
+    /// method again with the authorization code of the redirect.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// # use graph_oauth::oauth::{GrantType, OAuth};
+    /// # let mut oauth = OAuth::new();
+    /// match oauth.acquire_token_for_scopes(GrantType::AuthorizationCode, &["Mail.Send"]) {
+    ///     Ok(access_token) => println!("{:#?}", access_token),
+    ///     Err(err) if err.oauth_error().map_or(false, |e| e.requires_interaction()) => {
+    ///         let url = oauth
+    ///             .incremental_consent_url(GrantType::AuthorizationCode, &["Mail.Send"])
+    ///             .unwrap();
+    ///         // Redirect the user to the url.
+    ///     }
+    ///     Err(err) => panic!("{:#?}", err),
+    /// }
+    /// ```
+    #[cfg(feature = "blocking")]
+    pub fn acquire_token_for_scopes<T: AsRef<str>>(
+        &mut self,
+        grant: GrantType,
+        scopes: &[T],
+    ) -> OAuthReq<AccessToken> {
+        if let Some(access_token) = self.token_for_scopes(scopes) {
+            return Ok(access_token.clone());
         }
-        webbrowser::open(url.as_str()).map_err(GraphFailure::from)
+        let (oauth, request, refresh_token) = self.scoped_token_request(scopes)?;
+        let mut grant = AccessTokenGrant { oauth, grant };
+        let access_token = match request {
+            GrantRequest::AccessToken => grant.access_token().send(),
+            _ => grant.refresh_token().send(),
+        };
+        self.scoped_token_complete(access_token, refresh_token, scopes)
+    }
+
+    /// Get an access token for the scopes, requesting one only when the
+    /// tokens of OAuth do not already have all of the scopes. See
+    /// [OAuth::acquire_token_for_scopes].
+    pub async fn async_acquire_token_for_scopes<T: AsRef<str>>(
+        &mut self,
+        grant: GrantType,
+        scopes: &[T],
+    ) -> OAuthReq<AccessToken> {
+        if let Some(access_token) = self.token_for_scopes(scopes) {
+            return Ok(access_token.clone());
+        }
+        let (oauth, request, refresh_token) = self.scoped_token_request(scopes)?;
+        let mut grant = AsyncAccessTokenGrant { oauth, grant };
+        let access_token = match request {
+            GrantRequest::AccessToken => grant.access_token().send().await,
+            _ => grant.refresh_token().send().await,
+        };
+        self.scoped_token_complete(access_token, refresh_token, scopes)
     }
-}
 
-impl OAuth {
-    fn get_or_else(&self, c: OAuthCredential) -> OAuthReq<String> {
-        self.get(c).ok_or_else(|| OAuthError::credential_error(c))
+    /// The authorization url to ask the user to consent to additional
+    /// scopes. Returns None if a token of OAuth already has all of the
+    /// scopes so that the user is only asked when needed.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::{AccessToken, GrantType, OAuth};
+    /// let mut oauth = OAuth::new();
+    /// oauth
+    ///     .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+    ///     .authorize_url("https://login.microsoftonline.com/common/oauth2/v2.0/authorize")
+    ///     .redirect_uri("http://localhost:8000/redirect")
+    ///     .add_scope("User.Read")
+    ///     .add_scope("offline_access");
+    /// oauth.add_token(AccessToken::new("Bearer", 3600, "User.Read", "ASODFIUJ34KJ"));
+    ///
+    /// assert!(oauth
+    ///     .incremental_consent_url(GrantType::AuthorizationCode, &["User.Read"])
+    ///     .unwrap()
+    ///     .is_none());
+    /// let url = oauth
+    ///     .incremental_consent_url(GrantType::AuthorizationCode, &["Mail.Send"])
+    ///     .unwrap()
+    ///     .unwrap();
+    /// assert!(url.query().unwrap().contains("scope=Mail.Send+offline_access"));
+    /// ```
+    pub fn incremental_consent_url<T: AsRef<str>>(
+        &self,
+        grant: GrantType,
+        scopes: &[T],
+    ) -> OAuthReq<Option<Url>> {
+        OAuth::check_scopes(scopes)?;
+        if self.token_for_scopes(scopes).is_some() {
+            return Ok(None);
+        }
+        self.get_or_else(OAuthCredential::AuthorizeURL)?;
+        let oauth = self.scoped_oauth(scopes);
+        Ok(Some(AccessTokenGrant { oauth, grant }.authorization_url()?))
     }
 
-    fn form_encode_credentials(
+    /// Get a new access token that satisfies a claims challenge, such as
+    /// one returned by Microsoft Graph when Continuous Access Evaluation
+    /// revokes the current token.
+    ///
+    /// The claims are sent in a refresh token request, or an access token
+    /// request for the client credentials, on-behalf-of and resource owner
+    /// password credentials grants. If the request fails the claims stay
+    /// set so that they are included when the user signs in again
+    /// interactively; they are removed once a new token is returned.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// # use graph_oauth::oauth::{ClaimsChallenge, GrantType, OAuth};
+    /// # let mut oauth = OAuth::new();
+    /// # let failure: graph_error::GraphFailure = unimplemented!();
+    /// if let Some(challenge) = ClaimsChallenge::from_failure(&failure) {
+    ///     let access_token = oauth
+    ///         .acquire_token_for_claims_challenge(GrantType::AuthorizationCode, &challenge)
+    ///         .unwrap();
+    /// }
+    /// ```
+    #[cfg(feature = "blocking")]
+    pub fn acquire_token_for_claims_challenge(
         &mut self,
-        pairs: Vec<OAuthCredential>,
-        encoder: &mut Serializer<String>,
-    ) {
-        pairs
-            .iter()
-            .filter(|oac| self.contains_key(oac.alias()) || oac.alias().eq("scope"))
-            .for_each(|oac| {
-                if oac.alias().eq("scope") && !self.scopes.is_empty() {
-                    encoder.append_pair("scope", self.join_scopes(" ").as_str());
-                } else if let Some(val) = self.get(*oac) {
-                    encoder.append_pair(oac.alias(), val.as_str());
-                }
-            });
+        grant: GrantType,
+        challenge: &ClaimsChallenge,
+    ) -> OAuthReq<AccessToken> {
+        let (request_type, refresh_token) = self.claims_challenge_request(grant, challenge)?;
+        let mut request = AccessTokenGrant {
+            oauth: self.clone(),
+            grant,
+        };
+        let access_token = match request_type {
+            GrantRequest::RefreshToken => request.refresh_token().send()?,
+            _ => request.access_token().send()?,
+        };
+        Ok(self.claims_challenge_complete(access_token, refresh_token))
+    }
+
+    /// Get a new access token that satisfies a claims challenge. See
+    /// [OAuth::acquire_token_for_claims_challenge].
+    pub async fn async_acquire_token_for_claims_challenge(
+        &mut self,
+        grant: GrantType,
+        challenge: &ClaimsChallenge,
+    ) -> OAuthReq<AccessToken> {
+        let (request_type, refresh_token) = self.claims_challenge_request(grant, challenge)?;
+        let mut request = AsyncAccessTokenGrant {
+            oauth: self.clone(),
+            grant,
+        };
+        let access_token = match request_type {
+            GrantRequest::RefreshToken => request.refresh_token().send().await?,
+            _ => request.access_token().send().await?,
+        };
+        Ok(self.claims_challenge_complete(access_token, refresh_token))
     }
 
-    pub fn params(&mut self, pairs: Vec<OAuthCredential>) -> OAuthReq<HashMap<String, String>> {
-        let mut map: HashMap<String, String> = HashMap::new();
-        for oac in pairs.iter() {
-            if oac.eq(&OAuthCredential::RefreshToken) {
-                map.insert("refresh_token".into(), self.get_refresh_token()?);
-            } else if oac.alias().eq("scope") && !self.scopes.is_empty() {
-                map.insert("scope".into(), self.join_scopes(" "));
-            } else if let Some(val) = self.get(*oac) {
-                map.insert(oac.to_string(), val);
-            }
+    pub fn build(&mut self) -> GrantSelector<AccessTokenGrant> {
+        GrantSelector {
+            oauth: self.clone(),
+            t: PhantomData,
         }
-        Ok(map)
     }
 
-    pub fn encode_uri(&mut self, grant: GrantType, request_type: GrantRequest) -> OAuthReq<String> {
-        let mut encoder = Serializer::new(String::new());
+    pub fn build_async(&mut self) -> GrantSelector<AsyncAccessTokenGrant> {
+        GrantSelector {
+            oauth: self.clone(),
+            t: PhantomData,
+        }
+    }
+
+    /// The url to sign the user out using the OneDrive v1.0 endpoint. The
+    /// client id and the post logout redirect uri, or the redirect uri if
+    /// there is no post logout redirect uri, are added to the query of the
+    /// logout url.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// let mut oauth = OAuth::new();
+    /// oauth
+    ///     .client_id("<CLIENT_ID>")
+    ///     .logout_url("https://login.live.com/oauth20_logout.srf")
+    ///     .redirect_uri("http://localhost:8000/redirect");
+    ///
+    /// assert_eq!(
+    ///     oauth.v1_logout_url().unwrap().as_str(),
+    ///     "https://login.live.com/oauth20_logout.srf?client_id=%3CCLIENT_ID%3E&redirect_uri=http%3A%2F%2Flocalhost%3A8000%2Fredirect"
+    /// );
+    /// ```
+    pub fn v1_logout_url(&self) -> OAuthReq<Url> {
+        let mut url = self.logout_url_base()?;
+        let client_id = self.get_or_else(OAuthCredential::ClientId)?;
+        let redirect_uri = self
+            .get(OAuthCredential::PostLogoutRedirectURI)
+            .or_else(|| self.get(OAuthCredential::RedirectURI))
+            .unwrap_or_default();
+        url.query_pairs_mut()
+            .append_pair("client_id", &client_id)
+            .append_pair("redirect_uri", &redirect_uri);
+        Ok(url)
+    }
+
+    /// The url to sign the user out using the v2.0 endpoint. The post
+    /// logout redirect uri, or the redirect uri if there is no post logout
+    /// redirect uri, is added to the query of the logout url.
+    ///
+    /// Web apps and servers should redirect the user to this url instead of
+    /// opening a browser.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// let mut oauth = OAuth::new();
+    /// oauth
+    ///     .logout_url("https://login.microsoftonline.com/common/oauth2/v2.0/logout")
+    ///     .post_logout_redirect_uri("http://localhost:8000/redirect");
+    ///
+    /// assert_eq!(
+    ///     oauth.v2_logout_url().unwrap().as_str(),
+    ///     "https://login.microsoftonline.com/common/oauth2/v2.0/logout?post_logout_redirect_uri=http%3A%2F%2Flocalhost%3A8000%2Fredirect"
+    /// );
+    /// ```
+    pub fn v2_logout_url(&self) -> OAuthReq<Url> {
+        let mut url = self.logout_url_base()?;
+        let redirect_uri = match self.get(OAuthCredential::PostLogoutRedirectURI) {
+            Some(redirect_uri) => redirect_uri,
+            None => self.get_or_else(OAuthCredential::RedirectURI)?,
+        };
+        url.query_pairs_mut()
+            .append_pair("post_logout_redirect_uri", &redirect_uri);
+        Ok(url)
+    }
+
+    /// Sign the user out using the OneDrive v1.0 endpoint by opening
+    /// [OAuth::v1_logout_url] in the default browser.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use graph_oauth::oauth::OAuth;
+    /// let mut oauth = OAuth::new();
+    ///
+    /// oauth.v1_logout().unwrap();
+    /// ```
+    #[cfg(feature = "browser")]
+    pub fn v1_logout(&mut self) -> OAuthReq<Output> {
+        let url = self.v1_logout_url()?;
+        webbrowser::open(url.as_str()).map_err(GraphFailure::from)
+    }
+
+    /// Sign the user out using the v2.0 endpoint by opening
+    /// [OAuth::v2_logout_url] in the default browser.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use graph_oauth::oauth::OAuth;
+    /// let mut oauth = OAuth::new();
+    ///
+    /// oauth.v2_logout().unwrap();
+    /// ```
+    #[cfg(feature = "browser")]
+    pub fn v2_logout(&self) -> OAuthReq<Output> {
+        let url = self.v2_logout_url()?;
+        webbrowser::open(url.as_str()).map_err(GraphFailure::from)
+    }
+}
+
+impl OAuth {
+    // The logout url without the trailing '?' that was needed when the
+    // query was appended as a string.
+    fn logout_url_base(&self) -> OAuthReq<Url> {
+        let logout_url = self.get_or_else(OAuthCredential::LogoutURL)?;
+        let mut url = Url::parse(logout_url.as_str())?;
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+        Ok(url)
+    }
+
+    // The admin consent url with the query of the admin consent grant
+    // added to any query of the configured url.
+    pub(crate) fn encode_admin_consent_url(&mut self) -> OAuthReq<Url> {
+        let params = self
+            .params(GrantType::AdminConsent.available_credentials(GrantRequest::Authorization))?;
+        let admin_consent_url = self.get_or_else(OAuthCredential::AdminConsentURL)?;
+        let mut url = Url::parse(admin_consent_url.as_str())?;
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+        url.query_pairs_mut().extend_pairs(&params);
+        Ok(url)
+    }
+
+    pub(crate) fn token_http_client(&self) -> TokenHttpClient {
+        self.http_client.clone()
+    }
+
+    /// Returns an error if a client certificate is set but the grant
+    /// cannot authenticate the client with a client assertion. The token
+    /// request would otherwise be sent without client authentication.
+    pub(crate) fn check_certificate(
+        &self,
+        grant: GrantType,
+        request_type: GrantRequest,
+    ) -> OAuthReq<()> {
+        if self.certificate.is_some()
+            && !grant
+                .available_credentials(request_type)
+                .contains(&OAuthCredential::ClientAssertion)
+        {
+            return OAuthError::invalid_data(&format!(
+                "{:?} {:?} requests cannot use a client certificate",
+                grant, request_type
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn certificate_for(
+        &self,
+        grant: GrantType,
+        request_type: GrantRequest,
+    ) -> Option<ClientCertificateCredential> {
+        if grant
+            .available_credentials(request_type)
+            .contains(&OAuthCredential::ClientAssertion)
+        {
+            self.certificate.clone()
+        } else {
+            None
+        }
+    }
+
+    /// A copy of OAuth for the scopes. The offline_access scope is kept so
+    /// that a refresh token is returned.
+    fn scoped_oauth<T: AsRef<str>>(&self, scopes: &[T]) -> OAuth {
+        let mut oauth = self.clone();
+        oauth.scopes = scopes
+            .iter()
+            .map(|scope| scope.as_ref().to_string())
+            .collect();
+        if self.scopes.contains("offline_access") {
+            oauth.add_scope("offline_access");
+        }
+        oauth
+    }
+
+    /// The OAuth and the request used to get a token for scopes that the
+    /// tokens of OAuth do not have.
+    fn scoped_token_request<T: AsRef<str>>(
+        &self,
+        scopes: &[T],
+    ) -> OAuthReq<(OAuth, GrantRequest, Option<String>)> {
+        OAuth::check_scopes(scopes)?;
+        let mut oauth = self.scoped_oauth(scopes);
+        if self.contains(OAuthCredential::AccessCode) {
+            self.get_or_else(OAuthCredential::AccessTokenURL)?;
+            return Ok((oauth, GrantRequest::AccessToken, None));
+        }
+        self.get_or_else(OAuthCredential::RefreshTokenURL)?;
+        let refresh_token = self
+            .access_token
+            .iter()
+            .chain(self.tokens.iter().rev())
+            .find_map(|token| token.clone().refresh_token());
+        match refresh_token {
+            Some(refresh_token) => {
+                let mut access_token = AccessToken::default();
+                access_token.set_refresh_token(refresh_token.as_str());
+                oauth.access_token = Some(access_token);
+                Ok((oauth, GrantRequest::RefreshToken, Some(refresh_token)))
+            }
+            None => Err(GraphFailure::from(OAuthErrorResponse {
+                error: "consent_required".into(),
+                error_description: Some(format!(
+                    "the user must sign in to consent to the scopes: {}",
+                    oauth.join_scopes(" ")
+                )),
+                ..Default::default()
+            })),
+        }
+    }
+
+    fn check_scopes<T: AsRef<str>>(scopes: &[T]) -> OAuthReq<()> {
+        if normalize_scopes(scopes).is_empty() {
+            return OAuthError::invalid_data("at least one scope is required");
+        }
+        Ok(())
+    }
+
+    fn scoped_token_complete<T: AsRef<str>>(
+        &mut self,
+        access_token: OAuthReq<AccessToken>,
+        refresh_token: Option<String>,
+        scopes: &[T],
+    ) -> OAuthReq<AccessToken> {
+        self.remove(OAuthCredential::AccessCode);
+        let mut access_token = access_token?;
+        if let Some(refresh_token) = refresh_token {
+            if access_token.clone().refresh_token().is_none() {
+                access_token.set_refresh_token(refresh_token.as_str());
+            }
+        }
+        if access_token
+            .scopes()
+            .filter(|scope| !scope.is_empty())
+            .is_none()
+        {
+            let scopes: Vec<&str> = scopes.iter().map(|scope| scope.as_ref()).collect();
+            access_token.set_scope(&scopes.join(" "));
+        }
+        self.add_token(access_token.clone());
+        // Tokens for other resources are only held so that the access
+        // token of OAuth stays usable for its resource.
+        let same_resource = match self.access_token.as_ref().and_then(|token| token.scopes()) {
+            Some(scope) if !scope.is_empty() => {
+                scope_resources(scope.split(' '))
+                    == scope_resources(access_token.scopes().unwrap_or(&String::new()).split(' '))
+            }
+            _ => true,
+        };
+        if same_resource {
+            self.access_token(access_token.clone());
+        }
+        Ok(access_token)
+    }
+
+    /// The authority (access token url) and client id used as the
+    /// key of cached tokens.
+    fn token_cache_authority(&self) -> OAuthReq<(String, String)> {
+        Ok((
+            self.get_or_else(OAuthCredential::AccessTokenURL)?,
+            self.get_or_else(OAuthCredential::ClientId)?,
+        ))
+    }
+
+    /// A copy of OAuth with the most recent cached refresh token for the
+    /// account set to make a refresh token request.
+    fn cached_refresh_token<C: TokenCache + ?Sized>(
+        &self,
+        cache: &C,
+        authority: &str,
+        client_id: &str,
+        home_account_id: &str,
+    ) -> OAuthReq<(OAuth, String)> {
+        self.get_or_else(OAuthCredential::RefreshTokenURL)?;
+        let refresh_token = cache
+            .refresh_token(authority, client_id, home_account_id)?
+            .ok_or_else(|| {
+                OAuthError::error_kind(
+                    ErrorKind::NotFound,
+                    "No cached access token or refresh token for the account",
+                )
+            })?;
+
+        let mut token = AccessToken::default();
+        token.set_refresh_token(refresh_token.as_str());
+        let mut oauth = self.clone();
+        oauth.access_token(token);
+        Ok((oauth, refresh_token))
+    }
+
+    fn refreshed_token_key(
+        &self,
+        authority: &str,
+        client_id: &str,
+        home_account_id: &str,
+        access_token: &AccessToken,
+    ) -> TokenCacheKey {
+        match access_token.scopes() {
+            Some(scope) if !scope.trim().is_empty() => {
+                TokenCacheKey::new(authority, client_id, home_account_id, scope.split(' '))
+            }
+            _ => TokenCacheKey::new(authority, client_id, home_account_id, &self.scopes),
+        }
+    }
+
+    /// Store a token returned from a refresh token request. The previous
+    /// refresh token is kept if the response does not have a new one.
+    fn cache_refreshed_token<C: TokenCache + ?Sized>(
+        &mut self,
+        cache: &mut C,
+        key: TokenCacheKey,
+        mut access_token: AccessToken,
+        refresh_token: String,
+    ) -> OAuthReq<AccessToken> {
+        if access_token.clone().refresh_token().is_none() {
+            access_token.set_refresh_token(refresh_token.as_str());
+        }
+        cache.save(&CachedToken::new(key, access_token.clone()))?;
+        self.access_token(access_token.clone());
+        Ok(access_token)
+    }
+
+    /// Set the claims of the challenge and check the credentials needed
+    /// for the request so that building the request does not panic.
+    fn claims_challenge_request(
+        &mut self,
+        grant: GrantType,
+        challenge: &ClaimsChallenge,
+    ) -> OAuthReq<(GrantRequest, Option<String>)> {
+        self.claims(challenge.claims());
+        match grant {
+            GrantType::ClientCredentials
+            | GrantType::OnBehalfOf
+            | GrantType::ResourceOwnerPasswordCredentials
+            | GrantType::JwtBearer
+            | GrantType::Saml11Bearer
+            | GrantType::Saml2Bearer => {
+                self.get_or_else(OAuthCredential::AccessTokenURL)?;
+                Ok((GrantRequest::AccessToken, None))
+            }
+            GrantType::AuthorizationCode | GrantType::OpenId | GrantType::DeviceCode => {
+                self.get_or_else(OAuthCredential::RefreshTokenURL)?;
+                let refresh_token = self.get_refresh_token()?;
+                Ok((GrantRequest::RefreshToken, Some(refresh_token)))
+            }
+            _ => OAuthError::grant_error(
+                grant,
+                GrantRequest::RefreshToken,
+                "grant type does not support claims challenges",
+            ),
+        }
+    }
+
+    fn claims_challenge_complete(
+        &mut self,
+        mut access_token: AccessToken,
+        refresh_token: Option<String>,
+    ) -> AccessToken {
+        self.remove(OAuthCredential::Claims);
+        if let Some(refresh_token) = refresh_token {
+            if access_token.clone().refresh_token().is_none() {
+                access_token.set_refresh_token(refresh_token.as_str());
+            }
+        }
+        self.access_token(access_token.clone());
+        access_token
+    }
+
+    pub(crate) fn get_or_else(&self, c: OAuthCredential) -> OAuthReq<String> {
+        self.get(c).ok_or_else(|| OAuthError::credential_error(c))
+    }
+
+    fn form_encode_credentials(
+        &mut self,
+        pairs: Vec<OAuthCredential>,
+        encoder: &mut Serializer<String>,
+    ) {
+        pairs
+            .iter()
+            .filter(|oac| self.contains_key(oac.alias()) || oac.alias().eq("scope"))
+            .for_each(|oac| {
+                if oac.alias().eq("scope") && !self.scopes.is_empty() {
+                    encoder.append_pair("scope", self.join_scopes(" ").as_str());
+                } else if let Some(val) = self.get(*oac) {
+                    encoder.append_pair(oac.alias(), val.as_str());
+                }
+            });
+    }
+
+    pub fn params(&mut self, pairs: Vec<OAuthCredential>) -> OAuthReq<HashMap<String, String>> {
+        let mut map: HashMap<String, String> = HashMap::new();
+        for oac in pairs.iter() {
+            if oac.eq(&OAuthCredential::RefreshToken) {
+                map.insert("refresh_token".into(), self.get_refresh_token()?);
+            } else if oac.alias().eq("scope") && !self.scopes.is_empty() {
+                map.insert("scope".into(), self.join_scopes(" "));
+            } else if let Some(val) = self.get(*oac) {
+                map.insert(oac.to_string(), val);
+            }
+        }
+        Ok(map)
+    }
+
+    pub fn encode_uri(&mut self, grant: GrantType, request_type: GrantRequest) -> OAuthReq<String> {
+        let mut encoder = Serializer::new(String::new());
         match grant {
             GrantType::TokenFlow => match request_type {
                 GrantRequest::Authorization => {
@@ -1145,10 +2293,75 @@ impl OAuth {
                 );
                 Ok(encoder.finish())
             }
+            GrantType::OnBehalfOf => match request_type {
+                GrantRequest::Authorization => OAuthError::grant_error(
+                    GrantType::OnBehalfOf,
+                    GrantRequest::Authorization,
+                    "Grant type does not use request type. The on-behalf-of flow exchanges an existing access token",
+                ),
+                GrantRequest::AccessToken | GrantRequest::RefreshToken => {
+                    self.pre_request_check(GrantType::OnBehalfOf, request_type);
+                    if request_type == GrantRequest::RefreshToken {
+                        encoder.append_pair("refresh_token", &self.get_refresh_token()?);
+                    }
+                    self.form_encode_credentials(
+                        GrantType::OnBehalfOf.available_credentials(request_type),
+                        &mut encoder,
+                    );
+                    Ok(encoder.finish())
+                }
+            },
+            GrantType::DeviceCode => {
+                self.pre_request_check(GrantType::DeviceCode, request_type);
+                if request_type == GrantRequest::RefreshToken {
+                    encoder.append_pair("refresh_token", &self.get_refresh_token()?);
+                }
+                self.form_encode_credentials(
+                    GrantType::DeviceCode.available_credentials(request_type),
+                    &mut encoder,
+                );
+                Ok(encoder.finish())
+            }
+            GrantType::AdminConsent => match request_type {
+                GrantRequest::Authorization => Ok(self.encode_admin_consent_url()?.to_string()),
+                GrantRequest::AccessToken | GrantRequest::RefreshToken => OAuthError::grant_error(
+                    GrantType::AdminConsent,
+                    request_type,
+                    "Grant type does not use request type. Admin consent only grants permissions for the tenant",
+                ),
+            },
+            GrantType::JwtBearer | GrantType::Saml11Bearer | GrantType::Saml2Bearer => {
+                match request_type {
+                    GrantRequest::Authorization => OAuthError::grant_error(
+                        grant,
+                        GrantRequest::Authorization,
+                        "Grant type does not use request type. Bearer assertion grants exchange an existing assertion",
+                    ),
+                    GrantRequest::AccessToken => {
+                        let assertion = self.get_or_else(OAuthCredential::Assertion)?;
+                        check_assertion(grant, assertion.as_str())?;
+                        self.pre_request_check(grant, request_type);
+                        self.form_encode_credentials(
+                            grant.available_credentials(request_type),
+                            &mut encoder,
+                        );
+                        Ok(encoder.finish())
+                    }
+                    GrantRequest::RefreshToken => {
+                        self.pre_request_check(grant, request_type);
+                        encoder.append_pair("refresh_token", &self.get_refresh_token()?);
+                        self.form_encode_credentials(
+                            grant.available_credentials(request_type),
+                            &mut encoder,
+                        );
+                        Ok(encoder.finish())
+                    }
+                }
+            }
         }
     }
 
-    fn pre_request_check(&mut self, grant: GrantType, request_type: GrantRequest) {
+    pub(crate) fn pre_request_check(&mut self, grant: GrantType, request_type: GrantRequest) {
         match grant {
             GrantType::TokenFlow => {
                 if request_type.eq(&GrantRequest::Authorization) {
@@ -1207,6 +2420,47 @@ impl OAuth {
                     let _ = self.entry(OAuthCredential::GrantType, "password");
                 }
             }
+            GrantType::OnBehalfOf => {
+                if self.contains(OAuthCredential::ClientAssertion) {
+                    let _ = self.entry(OAuthCredential::ClientAssertionType, CLIENT_ASSERTION_TYPE);
+                }
+                match request_type {
+                    GrantRequest::Authorization => {}
+                    GrantRequest::AccessToken => {
+                        self.insert(OAuthCredential::GrantType, JWT_BEARER_GRANT_TYPE);
+                        let _ = self.entry(OAuthCredential::RequestedTokenUse, "on_behalf_of");
+                    }
+                    GrantRequest::RefreshToken => {
+                        self.insert(OAuthCredential::GrantType, "refresh_token");
+                    }
+                }
+            }
+            GrantType::DeviceCode => match request_type {
+                GrantRequest::Authorization => {}
+                GrantRequest::AccessToken => {
+                    self.insert(OAuthCredential::GrantType, DEVICE_CODE_GRANT_TYPE);
+                }
+                GrantRequest::RefreshToken => {
+                    self.insert(OAuthCredential::GrantType, "refresh_token");
+                }
+            },
+            GrantType::AdminConsent => {}
+            GrantType::JwtBearer | GrantType::Saml11Bearer | GrantType::Saml2Bearer => {
+                if self.contains(OAuthCredential::ClientAssertion) {
+                    let _ = self.entry(OAuthCredential::ClientAssertionType, CLIENT_ASSERTION_TYPE);
+                }
+                match request_type {
+                    GrantRequest::Authorization => {}
+                    GrantRequest::AccessToken => {
+                        if let Some(grant_type) = grant.assertion_grant_type() {
+                            self.insert(OAuthCredential::GrantType, grant_type);
+                        }
+                    }
+                    GrantRequest::RefreshToken => {
+                        self.insert(OAuthCredential::GrantType, "refresh_token");
+                    }
+                }
+            }
         }
     }
 }
@@ -1365,6 +2619,116 @@ impl GrantSelector<AccessTokenGrant> {
             grant: GrantType::ResourceOwnerPasswordCredentials,
         }
     }
+
+    /// Create a new instance for the device code flow.
+    ///
+    /// # See
+    /// [Microsoft Device Code Flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-oauth2-device-code)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// let device_code = oauth.build().device_code();
+    /// ```
+    pub fn device_code(self) -> DeviceCodeGrant {
+        DeviceCodeGrant::new(self.oauth)
+    }
+
+    /// Create a new instance for the admin consent flow. An administrator
+    /// signs in to grant the permissions of the application for their
+    /// tenant, such as when a customer tenant signs up for a multi-tenant
+    /// application.
+    ///
+    /// # See
+    /// [Microsoft Admin Consent](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-admin-consent)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// let admin_consent = oauth.build().admin_consent();
+    /// ```
+    pub fn admin_consent(self) -> AdminConsentGrant {
+        AdminConsentGrant::new(self.oauth)
+    }
+
+    /// Create a new instance for the JWT bearer grant. A JWT issued by a
+    /// trusted identity provider is exchanged for an access token.
+    ///
+    /// # See
+    /// [RFC 7523](https://tools.ietf.org/html/rfc7523)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// oauth.assertion("<JWT>");
+    /// let jwt_bearer = oauth.build().jwt_bearer();
+    /// ```
+    pub fn jwt_bearer(self) -> BearerAssertionGrant {
+        BearerAssertionGrant::new(self.oauth, GrantType::JwtBearer)
+    }
+
+    /// Create a new instance for the SAML 1.1 bearer grant. A SAML
+    /// assertion from a federated identity provider, such as AD FS, is
+    /// exchanged for an access token.
+    ///
+    /// # See
+    /// [Microsoft SAML Bearer Assertion Flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-saml-bearer-assertion)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// oauth.saml_assertion("<saml:Assertion>...</saml:Assertion>");
+    /// let saml_bearer = oauth.build().saml1_1_bearer();
+    /// ```
+    pub fn saml1_1_bearer(self) -> BearerAssertionGrant {
+        BearerAssertionGrant::new(self.oauth, GrantType::Saml11Bearer)
+    }
+
+    /// Create a new instance for the SAML 2.0 bearer grant. A SAML
+    /// assertion from a federated identity provider is exchanged for an
+    /// access token.
+    ///
+    /// # See
+    /// [Microsoft SAML Bearer Assertion Flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-saml-bearer-assertion)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// oauth.saml_assertion("<saml2:Assertion>...</saml2:Assertion>");
+    /// let saml_bearer = oauth.build().saml2_bearer();
+    /// ```
+    pub fn saml2_bearer(self) -> BearerAssertionGrant {
+        BearerAssertionGrant::new(self.oauth, GrantType::Saml2Bearer)
+    }
+
+    /// Create a new instance for the on-behalf-of flow. The access token
+    /// sent to a middle-tier API is exchanged for an access token to call
+    /// Graph as the same user.
+    ///
+    /// The client authenticates with either a client secret or a client
+    /// assertion signed with a certificate.
+    ///
+    /// # See
+    /// [Microsoft On-Behalf-Of Flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-oauth2-on-behalf-of-flow)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// oauth.assertion("<ACCESS TOKEN SENT TO THE API>");
+    /// let on_behalf_of = oauth.build().on_behalf_of();
+    /// ```
+    pub fn on_behalf_of(self) -> AccessTokenGrant {
+        AccessTokenGrant {
+            oauth: self.oauth,
+            grant: GrantType::OnBehalfOf,
+        }
+    }
 }
 
 impl GrantSelector<AsyncAccessTokenGrant> {
@@ -1493,6 +2857,116 @@ impl GrantSelector<AsyncAccessTokenGrant> {
             grant: GrantType::ResourceOwnerPasswordCredentials,
         }
     }
+
+    /// Create a new instance for the device code flow.
+    ///
+    /// # See
+    /// [Microsoft Device Code Flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-oauth2-device-code)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// let device_code = oauth.build_async().device_code();
+    /// ```
+    pub fn device_code(self) -> AsyncDeviceCodeGrant {
+        AsyncDeviceCodeGrant::new(self.oauth)
+    }
+
+    /// Create a new instance for the admin consent flow. An administrator
+    /// signs in to grant the permissions of the application for their
+    /// tenant, such as when a customer tenant signs up for a multi-tenant
+    /// application.
+    ///
+    /// # See
+    /// [Microsoft Admin Consent](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-admin-consent)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// let admin_consent = oauth.build_async().admin_consent();
+    /// ```
+    pub fn admin_consent(self) -> AdminConsentGrant {
+        AdminConsentGrant::new(self.oauth)
+    }
+
+    /// Create a new instance for the JWT bearer grant. A JWT issued by a
+    /// trusted identity provider is exchanged for an access token.
+    ///
+    /// # See
+    /// [RFC 7523](https://tools.ietf.org/html/rfc7523)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// oauth.assertion("<JWT>");
+    /// let jwt_bearer = oauth.build_async().jwt_bearer();
+    /// ```
+    pub fn jwt_bearer(self) -> AsyncBearerAssertionGrant {
+        AsyncBearerAssertionGrant::new(self.oauth, GrantType::JwtBearer)
+    }
+
+    /// Create a new instance for the SAML 1.1 bearer grant. A SAML
+    /// assertion from a federated identity provider, such as AD FS, is
+    /// exchanged for an access token.
+    ///
+    /// # See
+    /// [Microsoft SAML Bearer Assertion Flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-saml-bearer-assertion)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// oauth.saml_assertion("<saml:Assertion>...</saml:Assertion>");
+    /// let saml_bearer = oauth.build_async().saml1_1_bearer();
+    /// ```
+    pub fn saml1_1_bearer(self) -> AsyncBearerAssertionGrant {
+        AsyncBearerAssertionGrant::new(self.oauth, GrantType::Saml11Bearer)
+    }
+
+    /// Create a new instance for the SAML 2.0 bearer grant. A SAML
+    /// assertion from a federated identity provider is exchanged for an
+    /// access token.
+    ///
+    /// # See
+    /// [Microsoft SAML Bearer Assertion Flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-saml-bearer-assertion)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// oauth.saml_assertion("<saml2:Assertion>...</saml2:Assertion>");
+    /// let saml_bearer = oauth.build_async().saml2_bearer();
+    /// ```
+    pub fn saml2_bearer(self) -> AsyncBearerAssertionGrant {
+        AsyncBearerAssertionGrant::new(self.oauth, GrantType::Saml2Bearer)
+    }
+
+    /// Create a new instance for the on-behalf-of flow. The access token
+    /// sent to a middle-tier API is exchanged for an access token to call
+    /// Graph as the same user.
+    ///
+    /// The client authenticates with either a client secret or a client
+    /// assertion signed with a certificate.
+    ///
+    /// # See
+    /// [Microsoft On-Behalf-Of Flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-oauth2-on-behalf-of-flow)
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::OAuth;
+    /// # let mut oauth = OAuth::new();
+    /// oauth.assertion("<ACCESS TOKEN SENT TO THE API>");
+    /// let on_behalf_of = oauth.build_async().on_behalf_of();
+    /// ```
+    pub fn on_behalf_of(self) -> AsyncAccessTokenGrant {
+        AsyncAccessTokenGrant {
+            oauth: self.oauth,
+            grant: GrantType::OnBehalfOf,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, AsFile, FromFile)]
@@ -1501,6 +2975,23 @@ pub struct AuthorizationRequest {
 }
 
 impl AuthorizationRequest {
+    pub(crate) fn new(uri: String) -> AuthorizationRequest {
+        AuthorizationRequest { uri }
+    }
+
+    /// The authorization URL that the user is sent to.
+    pub fn uri(&self) -> &str {
+        self.uri.as_str()
+    }
+
+    /// The authorization URL that the user is sent to. Web apps and
+    /// servers redirect the user to this url.
+    pub fn url(&self) -> OAuthReq<Url> {
+        Ok(Url::parse(self.uri.as_str())?)
+    }
+
+    /// Open the authorization URL in the default browser.
+    #[cfg(feature = "browser")]
     pub fn open(&self) -> OAuthReq<Output> {
         webbrowser::open(self.uri.as_str()).map_err(GraphFailure::from)
     }
@@ -1510,14 +3001,52 @@ impl AuthorizationRequest {
 pub struct AccessTokenRequest {
     uri: String,
     params: HashMap<String, String>,
+    #[serde(skip)]
+    certificate: Option<ClientCertificateCredential>,
+    #[serde(skip)]
+    http_client: TokenHttpClient,
 }
 
 impl AccessTokenRequest {
+    pub(crate) fn new(uri: String, params: HashMap<String, String>) -> AccessTokenRequest {
+        AccessTokenRequest {
+            uri,
+            params,
+            certificate: None,
+            http_client: TokenHttpClient::default(),
+        }
+    }
+
+    pub(crate) fn with_certificate(
+        mut self,
+        certificate: Option<ClientCertificateCredential>,
+    ) -> AccessTokenRequest {
+        self.certificate = certificate;
+        self
+    }
+
+    pub(crate) fn with_http_client(mut self, http_client: TokenHttpClient) -> AccessTokenRequest {
+        self.http_client = http_client;
+        self
+    }
+
+    /// The form parameters of the request. A new client assertion is
+    /// signed for each request when using a client certificate.
+    #[cfg(feature = "blocking")]
+    fn form(&self) -> OAuthReq<HashMap<String, String>> {
+        let mut params = self.params.clone();
+        if let Some(certificate) = self.certificate.as_ref() {
+            certificate.sign_params(self.uri.as_str(), &mut params)?;
+        }
+        Ok(params)
+    }
+
     /// Send the request for an access token. The response body
     /// be will converted to an access token and returned.
+    #[cfg(feature = "blocking")]
     pub fn send(&mut self) -> OAuthReq<AccessToken> {
-        let client = reqwest::blocking::Client::new();
-        let builder = client.post(self.uri.as_str()).form(&self.params);
+        let client = self.http_client.blocking_client();
+        let builder = client.post(self.uri.as_str()).form(&self.form()?);
         AccessToken::try_from(builder)
     }
 
@@ -1525,12 +3054,13 @@ impl AccessTokenRequest {
     /// can be used to convert response bodies to custom
     /// objects using the serde crate. The object must implement
     /// serde deserialize.
+    #[cfg(feature = "blocking")]
     pub fn json<T>(&mut self) -> OAuthReq<T>
     where
         for<'de> T: serde::Deserialize<'de>,
     {
-        let client = reqwest::blocking::Client::new();
-        let builder = client.post(self.uri.as_str()).form(&self.params);
+        let client = self.http_client.blocking_client();
+        let builder = client.post(self.uri.as_str()).form(&self.form()?);
         let response = builder.send()?;
         Ok(response.json()?)
     }
@@ -1540,14 +3070,53 @@ impl AccessTokenRequest {
 pub struct AsyncAccessTokenRequest {
     uri: String,
     params: HashMap<String, String>,
+    #[serde(skip)]
+    certificate: Option<ClientCertificateCredential>,
+    #[serde(skip)]
+    http_client: TokenHttpClient,
 }
 
 impl AsyncAccessTokenRequest {
+    pub(crate) fn new(uri: String, params: HashMap<String, String>) -> AsyncAccessTokenRequest {
+        AsyncAccessTokenRequest {
+            uri,
+            params,
+            certificate: None,
+            http_client: TokenHttpClient::default(),
+        }
+    }
+
+    pub(crate) fn with_certificate(
+        mut self,
+        certificate: Option<ClientCertificateCredential>,
+    ) -> AsyncAccessTokenRequest {
+        self.certificate = certificate;
+        self
+    }
+
+    pub(crate) fn with_http_client(
+        mut self,
+        http_client: TokenHttpClient,
+    ) -> AsyncAccessTokenRequest {
+        self.http_client = http_client;
+        self
+    }
+
+    /// The form parameters of the request. A new client assertion is
+    /// signed for each request when using a client certificate.
+    fn form(&self) -> OAuthReq<HashMap<String, String>> {
+        let mut params = self.params.clone();
+        if let Some(certificate) = self.certificate.as_ref() {
+            certificate.sign_params(self.uri.as_str(), &mut params)?;
+        }
+        Ok(params)
+    }
+
     /// Send the request for an access token. The response body
     /// be will converted to an access token and returned.
     pub async fn send(&mut self) -> OAuthReq<AccessToken> {
-        let client = reqwest::Client::new();
-        let builder = client.post(self.uri.as_str()).form(&self.params);
+        let client = self.http_client.client();
+        let builder = client.post(self.uri.as_str()).form(&self.form()?);
         AccessToken::try_from_async(builder).await
     }
 
@@ -1559,8 +3128,8 @@ impl AsyncAccessTokenRequest {
     where
         for<'de> T: serde::Deserialize<'de>,
     {
-        let client = reqwest::Client::new();
-        let builder = client.post(self.uri.as_str()).form(&self.params);
+        let client = self.http_client.client();
+        let builder = client.post(self.uri.as_str()).form(&self.form()?);
         let response = builder.send().await?;
         Ok(response.json().await?)
     }
@@ -1573,31 +3142,31 @@ pub struct ImplicitGrant {
 }
 
 impl ImplicitGrant {
-    pub fn url(&mut self) -> Url {
+    pub fn url(&mut self) -> OAuthReq<Url> {
         self.oauth
             .pre_request_check(self.grant, GrantRequest::Authorization);
-        Url::parse(
+        Ok(Url::parse(
             self.oauth
-                .get_or_else(OAuthCredential::AuthorizeURL)
-                .unwrap()
+                .get_or_else(OAuthCredential::AuthorizeURL)?
                 .as_str(),
-        )
-        .unwrap()
+        )?)
     }
 
-    pub fn browser_authorization(&mut self) -> AuthorizationRequest {
-        let params = self
-            .oauth
-            .params(
-                self.grant
-                    .available_credentials(GrantRequest::Authorization),
-            )
-            .unwrap();
-        let mut url = self.url();
+    /// The authorization url with the encoded query for the grant.
+    pub fn authorization_url(&mut self) -> OAuthReq<Url> {
+        let mut url = self.url()?;
+        let params = self.oauth.params(
+            self.grant
+                .available_credentials(GrantRequest::Authorization),
+        )?;
         url.query_pairs_mut().extend_pairs(&params);
-        AuthorizationRequest {
-            uri: url.to_string(),
-        }
+        Ok(url)
+    }
+
+    pub fn browser_authorization(&mut self) -> OAuthReq<AuthorizationRequest> {
+        Ok(AuthorizationRequest {
+            uri: self.authorization_url()?.to_string(),
+        })
     }
 }
 
@@ -1620,35 +3189,34 @@ pub struct AccessTokenGrant {
 }
 
 impl AccessTokenGrant {
-    pub fn authorization_url(&mut self) -> Url {
+    /// The authorization url with the encoded query for the grant. For the
+    /// client credentials grant this is the admin consent url when the
+    /// authorize url is set to the adminconsent endpoint.
+    pub fn authorization_url(&mut self) -> OAuthReq<Url> {
         self.oauth
             .pre_request_check(self.grant, GrantRequest::Authorization);
-        let params = self
-            .oauth
-            .params(
-                self.grant
-                    .available_credentials(GrantRequest::Authorization),
-            )
-            .unwrap();
+        let params = self.oauth.params(
+            self.grant
+                .available_credentials(GrantRequest::Authorization),
+        )?;
         let mut url = Url::parse(
             self.oauth
-                .get_or_else(OAuthCredential::AuthorizeURL)
-                .unwrap()
+                .get_or_else(OAuthCredential::AuthorizeURL)?
                 .as_str(),
-        )
-        .unwrap();
+        )?;
         url.query_pairs_mut().extend_pairs(&params);
-        url
+        Ok(url)
     }
 
-    /// Make a request for authorization. The default browser for a user
-    /// will be opened to the sign in page where the user will need to
-    /// sign in and agree to any permissions that were set by the provided
-    /// scopes.
-    pub fn browser_authorization(&mut self) -> AuthorizationRequest {
-        AuthorizationRequest {
-            uri: self.authorization_url().to_string(),
-        }
+    /// Make a request for authorization. The user signs in and agrees
+    /// to any permissions that were set by the provided scopes at the
+    /// url of the request, either by redirecting the user to
+    /// [AuthorizationRequest::url] or by opening the default browser
+    /// with [AuthorizationRequest::open].
+    pub fn browser_authorization(&mut self) -> OAuthReq<AuthorizationRequest> {
+        Ok(AuthorizationRequest {
+            uri: self.authorization_url()?.to_string(),
+        })
     }
 
     /// Make a request for an access token. The token is stored in OAuth and
@@ -1705,6 +3273,10 @@ impl AccessTokenGrant {
                 .oauth
                 .params(self.grant.available_credentials(GrantRequest::AccessToken))
                 .unwrap(),
+            certificate: self
+                .oauth
+                .certificate_for(self.grant, GrantRequest::AccessToken),
+            http_client: self.oauth.token_http_client(),
         }
     }
 
@@ -1722,6 +3294,10 @@ impl AccessTokenGrant {
                 .oauth
                 .params(self.grant.available_credentials(GrantRequest::RefreshToken))
                 .unwrap(),
+            certificate: self
+                .oauth
+                .certificate_for(self.grant, GrantRequest::RefreshToken),
+            http_client: self.oauth.token_http_client(),
         }
     }
 }
@@ -1733,35 +3309,34 @@ pub struct AsyncAccessTokenGrant {
 }
 
 impl AsyncAccessTokenGrant {
-    pub fn authorization_url(&mut self) -> Url {
+    /// The authorization url with the encoded query for the grant. For the
+    /// client credentials grant this is the admin consent url when the
+    /// authorize url is set to the adminconsent endpoint.
+    pub fn authorization_url(&mut self) -> OAuthReq<Url> {
         self.oauth
             .pre_request_check(self.grant, GrantRequest::Authorization);
-        let params = self
-            .oauth
-            .params(
-                self.grant
-                    .available_credentials(GrantRequest::Authorization),
-            )
-            .unwrap();
+        let params = self.oauth.params(
+            self.grant
+                .available_credentials(GrantRequest::Authorization),
+        )?;
         let mut url = Url::parse(
             self.oauth
-                .get_or_else(OAuthCredential::AuthorizeURL)
-                .unwrap()
+                .get_or_else(OAuthCredential::AuthorizeURL)?
                 .as_str(),
-        )
-        .unwrap();
+        )?;
         url.query_pairs_mut().extend_pairs(&params);
-        url
+        Ok(url)
     }
 
-    /// Make a request for authorization. The default browser for a user
-    /// will be opened to the sign in page where the user will need to
-    /// sign in and agree to any permissions that were set by the provided
-    /// scopes.
-    pub fn browser_authorization(&mut self) -> AuthorizationRequest {
-        AuthorizationRequest {
-            uri: self.authorization_url().to_string(),
-        }
+    /// Make a request for authorization. The user signs in and agrees
+    /// to any permissions that were set by the provided scopes at the
+    /// url of the request, either by redirecting the user to
+    /// [AuthorizationRequest::url] or by opening the default browser
+    /// with [AuthorizationRequest::open].
+    pub fn browser_authorization(&mut self) -> OAuthReq<AuthorizationRequest> {
+        Ok(AuthorizationRequest {
+            uri: self.authorization_url()?.to_string(),
+        })
     }
 
     /// Make a request for an access token. The token is stored in OAuth and
@@ -1818,6 +3393,10 @@ impl AsyncAccessTokenGrant {
                 .oauth
                 .params(self.grant.available_credentials(GrantRequest::AccessToken))
                 .unwrap(),
+            certificate: self
+                .oauth
+                .certificate_for(self.grant, GrantRequest::AccessToken),
+            http_client: self.oauth.token_http_client(),
         }
     }
 
@@ -1835,6 +3414,10 @@ impl AsyncAccessTokenGrant {
                 .oauth
                 .params(self.grant.available_credentials(GrantRequest::RefreshToken))
                 .unwrap(),
+            certificate: self
+                .oauth
+                .certificate_for(self.grant, GrantRequest::RefreshToken),
+            http_client: self.oauth.token_http_client(),
         }
     }
 }
@@ -1851,12 +3434,22 @@ impl AsRef<OAuth> for AccessTokenGrant {
     }
 }
 
+impl Drop for OAuth {
+    fn drop(&mut self) {
+        for (key, value) in self.credentials.iter_mut() {
+            if is_sensitive_key(key) {
+                value.zeroize();
+            }
+        }
+    }
+}
+
 impl fmt::Debug for OAuth {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut map_debug: BTreeMap<&str, &str> = BTreeMap::new();
         for (key, value) in self.credentials.iter() {
             if let Some(oac) = OAuthCredential::iter()
-                .find(|oac| oac.alias().eq(key.as_str()) && oac.is_debug_redacted())
+                .find(|oac| oac.alias().eq(key.as_str()) && oac.is_sensitive())
             {
                 map_debug.insert(oac.alias(), "[REDACTED]");
             } else {
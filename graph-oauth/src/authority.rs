@@ -0,0 +1,258 @@
+use crate::discovery::graphdiscovery::GraphDiscovery;
+use std::fmt;
+
+static MICROSOFT_ONLINE_HOST: &str = "https://login.microsoftonline.com";
+static OPEN_ID_PATH: &str = ".well-known/openid-configuration";
+
+/// The tenant id of personal Microsoft accounts. Tokens for the consumers
+/// authority are issued by this tenant.
+pub const CONSUMERS_TENANT_ID: &str = "9188040d-6c67-4c5b-b112-36a304b66dad";
+
+/// The authority that signs users in and issues tokens. The authority
+/// determines the authorization, token and logout urls and the issuer
+/// of id tokens.
+///
+/// # Example
+/// ```
+/// # use graph_oauth::oauth::{Authority, OAuth, OAuthCredential};
+/// let mut oauth = OAuth::new();
+/// oauth.authority(&Authority::Organizations);
+///
+/// assert_eq!(
+///     oauth.get(OAuthCredential::AccessTokenURL).unwrap(),
+///     "https://login.microsoftonline.com/organizations/oauth2/v2.0/token"
+/// );
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Authority {
+    /// Users with both a personal Microsoft account and a work or school
+    /// account.
+    Common,
+    /// Users with work or school accounts only.
+    Organizations,
+    /// Users with a personal Microsoft account only.
+    Consumers,
+    /// Users of a single tenant given by its tenant id or a domain name
+    /// such as contoso.onmicrosoft.com.
+    Tenant(String),
+    /// An Azure AD B2C tenant and user flow or custom policy, such as
+    /// `Authority::AzureAdB2C { tenant: "contoso".into(), policy: "B2C_1_signupsignin".into() }`.
+    /// The tenant is the tenant name, with or without `.onmicrosoft.com`.
+    AzureAdB2C { tenant: String, policy: String },
+}
+
+impl Authority {
+    /// The authority for a tenant. The names common, organizations and
+    /// consumers are mapped to their own authority.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::Authority;
+    /// assert_eq!(Authority::tenant("consumers"), Authority::Consumers);
+    /// assert_eq!(
+    ///     Authority::tenant("contoso.onmicrosoft.com"),
+    ///     Authority::Tenant("contoso.onmicrosoft.com".into())
+    /// );
+    /// ```
+    pub fn tenant(tenant: &str) -> Authority {
+        match tenant.to_lowercase().as_str() {
+            "common" => Authority::Common,
+            "organizations" => Authority::Organizations,
+            "consumers" => Authority::Consumers,
+            _ => Authority::Tenant(tenant.to_string()),
+        }
+    }
+
+    /// The authority for an Azure AD B2C user flow or custom policy.
+    pub fn b2c(tenant: &str, policy: &str) -> Authority {
+        Authority::AzureAdB2C {
+            tenant: tenant.to_string(),
+            policy: policy.to_string(),
+        }
+    }
+
+    /// The tenant segment of the authority url.
+    pub fn tenant_segment(&self) -> &str {
+        match self {
+            Authority::Common => "common",
+            Authority::Organizations => "organizations",
+            Authority::Consumers => "consumers",
+            Authority::Tenant(tenant) => tenant.as_str(),
+            Authority::AzureAdB2C { tenant, .. } => tenant.as_str(),
+        }
+    }
+
+    pub fn is_b2c(&self) -> bool {
+        matches!(self, Authority::AzureAdB2C { .. })
+    }
+
+    /// The base url of the authority, such as
+    /// `https://login.microsoftonline.com/common` or
+    /// `https://contoso.b2clogin.com/contoso.onmicrosoft.com/B2C_1_signupsignin`.
+    pub fn url(&self) -> String {
+        match self {
+            Authority::AzureAdB2C { tenant, policy } => format!(
+                "{}/{}/{}",
+                Authority::b2c_host(tenant),
+                Authority::b2c_tenant_path(tenant),
+                policy
+            ),
+            _ => format!("{}/{}", MICROSOFT_ONLINE_HOST, self.tenant_segment()),
+        }
+    }
+
+    pub fn authorize_url(&self) -> String {
+        format!("{}/oauth2/v2.0/authorize", self.url())
+    }
+
+    pub fn token_url(&self) -> String {
+        format!("{}/oauth2/v2.0/token", self.url())
+    }
+
+    pub fn logout_url(&self) -> String {
+        format!("{}/oauth2/v2.0/logout", self.url())
+    }
+
+    /// The url of the admin consent endpoint. Admin consent is given by
+    /// the administrator of an organization, so it is only available for
+    /// [Authority::Organizations] and [Authority::Tenant] authorities of
+    /// work or school tenants.
+    pub fn admin_consent_url(&self) -> String {
+        format!("{}/v2.0/adminconsent", self.url())
+    }
+
+    /// The url of the OpenID Connect configuration of the authority.
+    pub fn discovery_url(&self) -> String {
+        format!("{}/v2.0/{}", self.url(), OPEN_ID_PATH)
+    }
+
+    /// The discovery for the authority. Returns None for Azure AD B2C
+    /// which is not a Microsoft Graph authority; use
+    /// [Authority::discovery_url] or
+    /// [JwksCache::from_authority](crate::oauth::jwkscache::JwksCache::from_authority)
+    /// to get its configuration and signing keys.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::Authority;
+    /// let discovery = Authority::Organizations.graph_discovery().unwrap();
+    /// assert_eq!(discovery.url(), Authority::Organizations.discovery_url());
+    /// ```
+    pub fn graph_discovery(&self) -> Option<GraphDiscovery> {
+        if self.is_b2c() {
+            None
+        } else {
+            Some(GraphDiscovery::Tenant(self.tenant_segment().to_string()))
+        }
+    }
+
+    /// The expected issuer of v2.0 id tokens. Issuers for authorities that
+    /// do not name a single tenant id contain the `{tenantid}` placeholder
+    /// used by [JwtValidator::issuer](crate::jwt::JwtValidator::issuer).
+    ///
+    /// The tenant id of a domain name tenant, such as `contoso.com`, is not
+    /// known so its issuer also contains the placeholder and matches tokens
+    /// from any tenant. Use [Authority::Tenant] with the tenant id instead
+    /// when validating tokens.
+    pub fn issuer(&self) -> String {
+        match self {
+            Authority::Consumers => {
+                format!("{}/{}/v2.0", MICROSOFT_ONLINE_HOST, CONSUMERS_TENANT_ID)
+            }
+            Authority::Tenant(tenant) if is_tenant_id(tenant) => {
+                format!("{}/{}/v2.0", MICROSOFT_ONLINE_HOST, tenant.to_lowercase())
+            }
+            Authority::AzureAdB2C { tenant, .. } => {
+                format!("{}/{{tenantid}}/v2.0/", Authority::b2c_host(tenant))
+            }
+            _ => format!("{}/{{tenantid}}/v2.0", MICROSOFT_ONLINE_HOST),
+        }
+    }
+
+    /// Returns true if a token with the given issuer (iss) and tenant id
+    /// (tid) claims can be issued by the authority.
+    ///
+    /// Tokens from personal Microsoft accounts are rejected for the
+    /// organizations authority, and only accepted for the consumers
+    /// authority if issued by the consumers tenant. The tenant of a
+    /// domain name authority is not known, so no issuer is valid. The
+    /// [JwtValidator](crate::jwt::JwtValidator) accepts tokens for a domain
+    /// name authority when
+    /// [JwtValidator::tenant_id](crate::jwt::JwtValidator::tenant_id) is set
+    /// and checks the issuer of that tenant instead.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::Authority;
+    /// let tid = "72f988bf-86f1-41af-91ab-2d7cd011db47";
+    /// let iss = format!("https://login.microsoftonline.com/{}/v2.0", tid);
+    /// assert!(Authority::Organizations.is_valid_issuer(&iss, Some(tid)));
+    /// assert!(!Authority::Consumers.is_valid_issuer(&iss, Some(tid)));
+    /// ```
+    pub fn is_valid_issuer(&self, issuer: &str, tenant_id: Option<&str>) -> bool {
+        if self.is_domain_tenant() {
+            return false;
+        }
+        let expected = self.issuer();
+        if !expected.contains("{tenantid}") {
+            return expected.eq_ignore_ascii_case(issuer);
+        }
+        let tid = match tenant_id {
+            Some(tid) => tid,
+            None => return false,
+        };
+        if matches!(self, Authority::Organizations) && tid.eq_ignore_ascii_case(CONSUMERS_TENANT_ID)
+        {
+            return false;
+        }
+        expected.replace("{tenantid}", tid).eq(issuer)
+    }
+
+    pub(crate) fn has_admin_consent(&self) -> bool {
+        match self {
+            Authority::Organizations => true,
+            Authority::Tenant(tenant) => !tenant.eq_ignore_ascii_case(CONSUMERS_TENANT_ID),
+            _ => false,
+        }
+    }
+
+    // Returns true for tenants given by a domain name instead of a tenant id.
+    pub(crate) fn is_domain_tenant(&self) -> bool {
+        matches!(self, Authority::Tenant(tenant) if !is_tenant_id(tenant))
+    }
+
+    // The host of a B2C tenant is the tenant name, such as
+    // contoso.b2clogin.com for contoso.onmicrosoft.com.
+    fn b2c_host(tenant: &str) -> String {
+        let name = tenant.split('.').next().unwrap_or(tenant);
+        format!("https://{}.b2clogin.com", name.to_lowercase())
+    }
+
+    fn b2c_tenant_path(tenant: &str) -> String {
+        if tenant.contains('.') {
+            tenant.to_string()
+        } else {
+            format!("{}.onmicrosoft.com", tenant)
+        }
+    }
+}
+
+impl fmt::Display for Authority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.url())
+    }
+}
+
+impl From<&str> for Authority {
+    fn from(tenant: &str) -> Self {
+        Authority::tenant(tenant)
+    }
+}
+
+fn is_tenant_id(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
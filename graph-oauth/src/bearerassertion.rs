@@ -0,0 +1,182 @@
+use crate::auth::{AccessTokenRequest, AsyncAccessTokenRequest, OAuth, OAuthCredential, OAuthReq};
+use crate::grants::{GrantRequest, GrantType};
+use crate::oautherror::OAuthError;
+use std::collections::HashMap;
+
+/// Check that the assertion has the format of the grant: a JWT for the
+/// JWT bearer grant or a base64url encoded SAML assertion for the SAML
+/// bearer grants.
+pub(crate) fn check_assertion(grant: GrantType, assertion: &str) -> OAuthReq<()> {
+    match grant {
+        GrantType::JwtBearer => {
+            let parts: Vec<&str> = assertion.split('.').collect();
+            if parts.len() != 3 || parts[0].is_empty() || parts[1].is_empty() {
+                return OAuthError::grant_error(
+                    grant,
+                    GrantRequest::AccessToken,
+                    "assertion is not a JWT",
+                );
+            }
+            let header = base64::decode_config(parts[0], base64::URL_SAFE_NO_PAD)?;
+            serde_json::from_slice::<serde_json::Value>(&header)?;
+            Ok(())
+        }
+        GrantType::Saml11Bearer | GrantType::Saml2Bearer => {
+            let saml =
+                base64::decode_config(assertion.trim_end_matches('='), base64::URL_SAFE_NO_PAD)
+                    .map_err(|_| {
+                        OAuthError::invalid(
+                            "SAML assertion must be base64url encoded. Use OAuth::saml_assertion to set the assertion from XML",
+                        )
+                    })?;
+            let saml = String::from_utf8_lossy(&saml);
+            if !saml.trim_start().starts_with('<') || !saml.contains("Assertion") {
+                return OAuthError::grant_error(
+                    grant,
+                    GrantRequest::AccessToken,
+                    "assertion is not a SAML assertion",
+                );
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn token_request(
+    oauth: &mut OAuth,
+    grant: GrantType,
+    grant_request: GrantRequest,
+) -> OAuthReq<(String, HashMap<String, String>)> {
+    let uri = match grant_request {
+        GrantRequest::RefreshToken => oauth.get_or_else(OAuthCredential::RefreshTokenURL)?,
+        _ => {
+            let assertion = oauth.get_or_else(OAuthCredential::Assertion)?;
+            check_assertion(grant, assertion.as_str())?;
+            oauth.get_or_else(OAuthCredential::AccessTokenURL)?
+        }
+    };
+    oauth.get_or_else(OAuthCredential::ClientId)?;
+    oauth.pre_request_check(grant, grant_request);
+    let params = oauth.params(grant.available_credentials(grant_request))?;
+    Ok((uri, params))
+}
+
+/// The JWT bearer and SAML bearer assertion grants. An assertion issued
+/// by a trusted or federated identity provider is exchanged for an access
+/// token. The client authenticates with either a client secret or a
+/// client certificate.
+///
+/// # See
+/// [Microsoft SAML Bearer Assertion Flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-saml-bearer-assertion)
+///
+/// # Example
+/// ```rust,ignore
+/// # use graph_oauth::oauth::OAuth;
+/// let mut oauth = OAuth::new();
+/// oauth
+///     .client_id("<YOUR_CLIENT_ID>")
+///     .client_secret("<YOUR_CLIENT_SECRET>")
+///     .add_scope("https://graph.microsoft.com/.default")
+///     .saml_assertion("<saml:Assertion>...</saml:Assertion>")
+///     .access_token_url("https://login.microsoftonline.com/common/oauth2/v2.0/token");
+///
+/// let access_token = oauth
+///     .build()
+///     .saml1_1_bearer()
+///     .access_token()
+///     .unwrap()
+///     .send()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BearerAssertionGrant {
+    oauth: OAuth,
+    grant: GrantType,
+}
+
+impl BearerAssertionGrant {
+    pub(crate) fn new(oauth: OAuth, grant: GrantType) -> BearerAssertionGrant {
+        BearerAssertionGrant { oauth, grant }
+    }
+
+    /// Request an access token using the assertion.
+    pub fn access_token(&mut self) -> OAuthReq<AccessTokenRequest> {
+        let (uri, params) = token_request(&mut self.oauth, self.grant, GrantRequest::AccessToken)?;
+        Ok(AccessTokenRequest::new(uri, params)
+            .with_certificate(
+                self.oauth
+                    .certificate_for(self.grant, GrantRequest::AccessToken),
+            )
+            .with_http_client(self.oauth.token_http_client()))
+    }
+
+    /// Request a new access token using a refresh token.
+    pub fn refresh_token(&mut self) -> OAuthReq<AccessTokenRequest> {
+        let (uri, params) = token_request(&mut self.oauth, self.grant, GrantRequest::RefreshToken)?;
+        Ok(AccessTokenRequest::new(uri, params)
+            .with_certificate(
+                self.oauth
+                    .certificate_for(self.grant, GrantRequest::RefreshToken),
+            )
+            .with_http_client(self.oauth.token_http_client()))
+    }
+}
+
+impl From<BearerAssertionGrant> for OAuth {
+    fn from(grant: BearerAssertionGrant) -> Self {
+        grant.oauth
+    }
+}
+
+impl AsRef<OAuth> for BearerAssertionGrant {
+    fn as_ref(&self) -> &OAuth {
+        &self.oauth
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AsyncBearerAssertionGrant {
+    oauth: OAuth,
+    grant: GrantType,
+}
+
+impl AsyncBearerAssertionGrant {
+    pub(crate) fn new(oauth: OAuth, grant: GrantType) -> AsyncBearerAssertionGrant {
+        AsyncBearerAssertionGrant { oauth, grant }
+    }
+
+    /// Request an access token using the assertion.
+    pub fn access_token(&mut self) -> OAuthReq<AsyncAccessTokenRequest> {
+        let (uri, params) = token_request(&mut self.oauth, self.grant, GrantRequest::AccessToken)?;
+        Ok(AsyncAccessTokenRequest::new(uri, params)
+            .with_certificate(
+                self.oauth
+                    .certificate_for(self.grant, GrantRequest::AccessToken),
+            )
+            .with_http_client(self.oauth.token_http_client()))
+    }
+
+    /// Request a new access token using a refresh token.
+    pub fn refresh_token(&mut self) -> OAuthReq<AsyncAccessTokenRequest> {
+        let (uri, params) = token_request(&mut self.oauth, self.grant, GrantRequest::RefreshToken)?;
+        Ok(AsyncAccessTokenRequest::new(uri, params)
+            .with_certificate(
+                self.oauth
+                    .certificate_for(self.grant, GrantRequest::RefreshToken),
+            )
+            .with_http_client(self.oauth.token_http_client()))
+    }
+}
+
+impl From<AsyncBearerAssertionGrant> for OAuth {
+    fn from(grant: AsyncBearerAssertionGrant) -> Self {
+        grant.oauth
+    }
+}
+
+impl AsRef<OAuth> for AsyncBearerAssertionGrant {
+    fn as_ref(&self) -> &OAuth {
+        &self.oauth
+    }
+}
@@ -0,0 +1,244 @@
+use crate::auth::{OAuthReq, CLIENT_ASSERTION_TYPE};
+use crate::jwt::Algorithm;
+use crate::oautherror::OAuthError;
+use chrono::{Duration, Utc};
+use ring::digest;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{self, RsaKeyPair};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// The number of minutes a client assertion is valid for.
+const ASSERTION_LIFETIME: i64 = 10;
+
+/// Decode the first PEM block with the given label.
+fn decode_pem(pem: &[u8], label: &str) -> Option<Vec<u8>> {
+    let pem = std::str::from_utf8(pem).ok()?;
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+    let start = pem.find(begin.as_str())? + begin.len();
+    let stop = start + pem[start..].find(end.as_str())?;
+    let body: String = pem[start..stop]
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    base64::decode(body).ok()
+}
+
+/// Client credential that authenticates with a certificate instead of
+/// a client secret.
+///
+/// A client assertion is a JWT signed with the private key of a certificate
+/// that has been uploaded to the application registration. A new assertion
+/// is generated for each token request.
+///
+/// # See
+/// [Microsoft identity platform application authentication certificate credentials](https://docs.microsoft.com/en-us/azure/active-directory/develop/active-directory-certificate-credentials)
+///
+/// # Example
+/// ```rust,ignore
+/// # use graph_oauth::oauth::{ClientCertificateCredential, OAuth};
+/// let credential = ClientCertificateCredential::from_pem(
+///     "<CLIENT_ID>",
+///     &std::fs::read("key.pem").unwrap(),
+///     &std::fs::read("cert.pem").unwrap(),
+/// )
+/// .unwrap();
+///
+/// let mut oauth = OAuth::new();
+/// oauth
+///     .client_id("<CLIENT_ID>")
+///     .add_scope("https://graph.microsoft.com/.default")
+///     .access_token_url("https://login.microsoftonline.com/<TENANT_ID>/oauth2/v2.0/token")
+///     .client_certificate(credential);
+///
+/// let access_token = oauth.build().client_credentials().access_token().send().unwrap();
+/// ```
+#[derive(Clone)]
+pub struct ClientCertificateCredential {
+    client_id: String,
+    key_pair: Arc<RsaKeyPair>,
+    thumbprint: String,
+    algorithm: Algorithm,
+}
+
+impl ClientCertificateCredential {
+    /// Create a credential from a PEM encoded RSA private key and
+    /// X.509 certificate. The private key can be PKCS#8 (BEGIN PRIVATE KEY)
+    /// or PKCS#1 (BEGIN RSA PRIVATE KEY).
+    pub fn from_pem(
+        client_id: &str,
+        private_key: &[u8],
+        certificate: &[u8],
+    ) -> OAuthReq<ClientCertificateCredential> {
+        let certificate = decode_pem(certificate, "CERTIFICATE")
+            .ok_or_else(|| OAuthError::invalid("PEM encoded certificate"))?;
+
+        if let Some(der) = decode_pem(private_key, "PRIVATE KEY") {
+            ClientCertificateCredential::from_der(client_id, &der, &certificate)
+        } else if let Some(der) = decode_pem(private_key, "RSA PRIVATE KEY") {
+            let key_pair = RsaKeyPair::from_der(&der)
+                .map_err(|_| OAuthError::invalid("PKCS#1 RSA private key"))?;
+            Ok(ClientCertificateCredential::new(
+                client_id,
+                key_pair,
+                &certificate,
+            ))
+        } else {
+            Err(OAuthError::invalid("PEM encoded RSA private key"))
+        }
+    }
+
+    /// Create a credential from a PKCS#8 DER encoded RSA private key and
+    /// DER encoded X.509 certificate.
+    pub fn from_der(
+        client_id: &str,
+        private_key: &[u8],
+        certificate: &[u8],
+    ) -> OAuthReq<ClientCertificateCredential> {
+        let key_pair = RsaKeyPair::from_pkcs8(private_key)
+            .map_err(|_| OAuthError::invalid("PKCS#8 RSA private key"))?;
+        Ok(ClientCertificateCredential::new(
+            client_id,
+            key_pair,
+            certificate,
+        ))
+    }
+
+    fn new(
+        client_id: &str,
+        key_pair: RsaKeyPair,
+        certificate: &[u8],
+    ) -> ClientCertificateCredential {
+        let thumbprint = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, certificate);
+        ClientCertificateCredential {
+            client_id: client_id.to_string(),
+            key_pair: Arc::new(key_pair),
+            thumbprint: base64::encode_config(thumbprint.as_ref(), base64::URL_SAFE_NO_PAD),
+            algorithm: Algorithm::RS256,
+        }
+    }
+
+    /// Set the algorithm used to sign the client assertion.
+    /// Only RS256 (the default) and PS256 are supported.
+    pub fn algorithm(&mut self, algorithm: Algorithm) -> OAuthReq<&mut Self> {
+        match algorithm {
+            Algorithm::RS256 | Algorithm::PS256 => {
+                self.algorithm = algorithm;
+                Ok(self)
+            }
+            _ => OAuthError::invalid_data("client assertion algorithm: use RS256 or PS256"),
+        }
+    }
+
+    pub fn client_id(&self) -> &str {
+        self.client_id.as_str()
+    }
+
+    /// The base64 URL encoded SHA-1 thumbprint of the certificate used
+    /// as the x5t header of the client assertion.
+    pub fn thumbprint(&self) -> &str {
+        self.thumbprint.as_str()
+    }
+
+    /// The client_assertion_type sent with the client assertion.
+    pub fn client_assertion_type(&self) -> &'static str {
+        CLIENT_ASSERTION_TYPE
+    }
+
+    /// Generate a new signed client assertion. The audience is the
+    /// token endpoint the assertion is sent to.
+    pub fn client_assertion(&self, audience: &str) -> OAuthReq<String> {
+        let rng = SystemRandom::new();
+        let header = serde_json::json!({
+            "alg": self.algorithm,
+            "typ": "JWT",
+            "x5t": self.thumbprint,
+        });
+
+        let now = Utc::now();
+        let claims = serde_json::json!({
+            "aud": audience,
+            "iss": self.client_id,
+            "sub": self.client_id,
+            "jti": ClientCertificateCredential::jti(&rng)?,
+            "nbf": now.timestamp(),
+            "exp": (now + Duration::minutes(ASSERTION_LIFETIME)).timestamp(),
+        });
+
+        let message = format!(
+            "{}.{}",
+            base64::encode_config(serde_json::to_vec(&header)?, base64::URL_SAFE_NO_PAD),
+            base64::encode_config(serde_json::to_vec(&claims)?, base64::URL_SAFE_NO_PAD),
+        );
+
+        let padding: &dyn signature::RsaEncoding = match self.algorithm {
+            Algorithm::PS256 => &signature::RSA_PSS_SHA256,
+            _ => &signature::RSA_PKCS1_SHA256,
+        };
+        let mut signature = vec![0; self.key_pair.public_modulus_len()];
+        self.key_pair
+            .sign(padding, &rng, message.as_bytes(), &mut signature)?;
+
+        Ok(format!(
+            "{}.{}",
+            message,
+            base64::encode_config(signature, base64::URL_SAFE_NO_PAD)
+        ))
+    }
+
+    /// Add a new client assertion to the parameters of a token request.
+    /// Any client secret is removed.
+    pub(crate) fn sign_params(
+        &self,
+        audience: &str,
+        params: &mut HashMap<String, String>,
+    ) -> OAuthReq<()> {
+        params.remove("client_secret");
+        params.insert("client_assertion".into(), self.client_assertion(audience)?);
+        params.insert(
+            "client_assertion_type".into(),
+            CLIENT_ASSERTION_TYPE.to_string(),
+        );
+        Ok(())
+    }
+
+    /// A random UUID (version 4) used as the jti claim.
+    fn jti(rng: &SystemRandom) -> OAuthReq<String> {
+        let mut bytes = [0u8; 16];
+        rng.fill(&mut bytes)?;
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        Ok(format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        ))
+    }
+}
+
+impl PartialEq for ClientCertificateCredential {
+    fn eq(&self, other: &Self) -> bool {
+        self.client_id == other.client_id
+            && self.thumbprint == other.thumbprint
+            && self.algorithm == other.algorithm
+    }
+}
+
+impl Eq for ClientCertificateCredential {}
+
+impl fmt::Debug for ClientCertificateCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ClientCertificateCredential")
+            .field("client_id", &"[REDACTED]")
+            .field("key_pair", &"[REDACTED]")
+            .field("thumbprint", &self.thumbprint)
+            .field("algorithm", &self.algorithm)
+            .finish()
+    }
+}
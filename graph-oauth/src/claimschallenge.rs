@@ -0,0 +1,188 @@
+use graph_error::GraphFailure;
+use reqwest::header::{HeaderMap, WWW_AUTHENTICATE};
+
+/// Claims challenge returned in the `WWW-Authenticate` header of a 401
+/// response when a resource such as Microsoft Graph requires claims that
+/// the access token does not have. Continuous Access Evaluation uses this
+/// to revoke tokens early, for instance after the user's password is reset
+/// or the user moves to a network that is not allowed.
+///
+/// Pass the challenge to
+/// [OAuth::acquire_token_for_claims_challenge](crate::oauth::OAuth::acquire_token_for_claims_challenge)
+/// to get a new access token that satisfies the claims.
+///
+/// # See
+/// [Claims challenges, claims requests, and client capabilities](https://docs.microsoft.com/en-us/azure/active-directory/develop/claims-challenge)
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ClaimsChallenge {
+    /// The error of the challenge, usually insufficient_claims.
+    pub error: Option<String>,
+    /// The decoded claims JSON to pass in the claims parameter.
+    pub claims: String,
+    pub authorization_uri: Option<String>,
+    pub realm: Option<String>,
+    pub client_id: Option<String>,
+}
+
+impl ClaimsChallenge {
+    /// Parse the value of a `WWW-Authenticate` header. Returns None if the
+    /// header is not a Bearer challenge with a claims parameter.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::ClaimsChallenge;
+    /// let challenge = ClaimsChallenge::parse(
+    ///     r#"Bearer realm="", authorization_uri="https://login.microsoftonline.com/common/oauth2/authorize", error="insufficient_claims", claims="eyJhY2Nlc3NfdG9rZW4iOnsibmJmIjp7ImVzc2VudGlhbCI6dHJ1ZSwidmFsdWUiOiIxNjA0MTA2NjUxIn19fQ==""#,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert!(challenge.is_insufficient_claims());
+    /// assert_eq!(
+    ///     challenge.claims(),
+    ///     r#"{"access_token":{"nbf":{"essential":true,"value":"1604106651"}}}"#
+    /// );
+    /// ```
+    pub fn parse(www_authenticate: &str) -> Option<ClaimsChallenge> {
+        let value = www_authenticate.trim();
+        let scheme_end = value.find(char::is_whitespace).unwrap_or(value.len());
+        if !value[..scheme_end].eq_ignore_ascii_case("bearer") {
+            return None;
+        }
+
+        let mut challenge = ClaimsChallenge {
+            error: None,
+            claims: String::new(),
+            authorization_uri: None,
+            realm: None,
+            client_id: None,
+        };
+        for (key, value) in ClaimsChallenge::auth_params(&value[scheme_end..]) {
+            match key.to_lowercase().as_str() {
+                "error" => challenge.error = Some(value),
+                "claims" => challenge.claims = ClaimsChallenge::decode_claims(&value)?,
+                "authorization_uri" => challenge.authorization_uri = Some(value),
+                "realm" => challenge.realm = Some(value),
+                "client_id" => challenge.client_id = Some(value),
+                _ => {}
+            }
+        }
+
+        if challenge.claims.is_empty() {
+            None
+        } else {
+            Some(challenge)
+        }
+    }
+
+    /// Find a claims challenge in the `WWW-Authenticate` headers of a response.
+    pub fn from_headers(headers: &HeaderMap) -> Option<ClaimsChallenge> {
+        headers
+            .get_all(WWW_AUTHENTICATE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .find_map(ClaimsChallenge::parse)
+    }
+
+    /// Get the claims challenge of a failed request. This is either the
+    /// `WWW-Authenticate` header of a 401 response from a resource or the
+    /// claims of an error response from the token endpoint.
+    pub fn from_failure(failure: &GraphFailure) -> Option<ClaimsChallenge> {
+        match failure {
+            GraphFailure::GraphError(e) => {
+                ClaimsChallenge::from_headers(e.headers.as_ref()?.header_map())
+            }
+            GraphFailure::OAuthError(e) => {
+                let claims = e.claims.as_ref().filter(|claims| !claims.is_empty())?;
+                Some(ClaimsChallenge {
+                    error: Some(e.error.clone()),
+                    claims: claims.clone(),
+                    authorization_uri: None,
+                    realm: None,
+                    client_id: None,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn claims(&self) -> &str {
+        self.claims.as_str()
+    }
+
+    pub fn is_insufficient_claims(&self) -> bool {
+        self.error.as_deref() == Some("insufficient_claims")
+    }
+
+    // Split the auth-params of a challenge. Values may be quoted strings
+    // with backslash escapes or plain tokens.
+    fn auth_params(input: &str) -> Vec<(String, String)> {
+        let mut params = Vec::new();
+        let mut chars = input.chars().peekable();
+        loop {
+            while matches!(chars.peek(), Some(c) if c.is_whitespace() || *c == ',') {
+                chars.next();
+            }
+            let mut key = String::new();
+            while let Some(c) = chars.peek() {
+                if *c == '=' || *c == ',' || c.is_whitespace() {
+                    break;
+                }
+                key.push(*c);
+                chars.next();
+            }
+            while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+                chars.next();
+            }
+            if chars.peek() != Some(&'=') {
+                if chars.peek().is_none() {
+                    return params;
+                }
+                continue;
+            }
+            chars.next();
+            while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+                chars.next();
+            }
+
+            let mut value = String::new();
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => value.extend(chars.next()),
+                        _ => value.push(c),
+                    }
+                }
+            } else {
+                while let Some(c) = chars.peek() {
+                    if *c == ',' || c.is_whitespace() {
+                        break;
+                    }
+                    value.push(*c);
+                    chars.next();
+                }
+            }
+            if !key.is_empty() {
+                params.push((key, value));
+            }
+        }
+    }
+
+    // Resources send the claims base64 encoded while token endpoints
+    // send plain JSON.
+    fn decode_claims(value: &str) -> Option<String> {
+        let value = value.trim();
+        if value.starts_with('{') {
+            return Some(value.to_string());
+        }
+        let bytes = base64::decode(value)
+            .or_else(|_| {
+                base64::decode_config(value.trim_end_matches('='), base64::URL_SAFE_NO_PAD)
+            })
+            .ok()?;
+        let claims = String::from_utf8(bytes).ok()?;
+        serde_json::from_str::<serde_json::Value>(&claims).ok()?;
+        Some(claims)
+    }
+}
@@ -0,0 +1,425 @@
+use crate::accesstoken::AccessToken;
+use crate::auth::{AccessTokenRequest, AsyncAccessTokenRequest, OAuth, OAuthCredential, OAuthReq};
+use crate::grants::{GrantRequest, GrantType};
+use crate::httpclient::TokenHttpClient;
+#[cfg(feature = "blocking")]
+use graph_error::WithGraphError;
+use graph_error::{GraphFailure, OAuthErrorResponse, WithGraphErrorAsync};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+pub(crate) const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// The default number of seconds to wait between polling requests
+/// when the device authorization response does not include an interval.
+const DEFAULT_INTERVAL: u64 = 5;
+
+/// The number of seconds the polling interval is increased by when the
+/// authorization server responds with slow_down.
+const SLOW_DOWN_INTERVAL: u64 = 5;
+
+fn default_interval() -> u64 {
+    DEFAULT_INTERVAL
+}
+
+/// The response of a device authorization request.
+///
+/// Show the message, or the user code and verification uri, to the user
+/// and then poll for an access token while the user signs in on another device.
+///
+/// # See
+/// [Microsoft identity platform and the OAuth 2.0 device authorization grant flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-oauth2-device-code)
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+    #[serde(default = "default_interval")]
+    interval: u64,
+    message: Option<String>,
+}
+
+impl DeviceCode {
+    /// The code that is used to poll for an access token.
+    pub fn device_code(&self) -> &str {
+        self.device_code.as_str()
+    }
+
+    /// The code the user enters at the verification uri.
+    pub fn user_code(&self) -> &str {
+        self.user_code.as_str()
+    }
+
+    /// The uri the user should go to with the user code in order to sign in.
+    pub fn verification_uri(&self) -> &str {
+        self.verification_uri.as_str()
+    }
+
+    /// A human readable message with instructions for the user.
+    pub fn message(&self) -> Option<&String> {
+        self.message.as_ref()
+    }
+
+    /// The duration before the device code expires.
+    pub fn expires_in(&self) -> Duration {
+        Duration::from_secs(self.expires_in)
+    }
+
+    /// The duration to wait between polling requests.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval)
+    }
+}
+
+impl fmt::Debug for DeviceCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeviceCode")
+            .field("device_code", &"[REDACTED]")
+            .field("user_code", &self.user_code)
+            .field("verification_uri", &self.verification_uri)
+            .field("expires_in", &self.expires_in)
+            .field("interval", &self.interval)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+/// Errors returned by the token endpoint while polling for an
+/// access token in the device code flow.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DeviceCodeError {
+    /// The user has not finished signing in. Continue polling.
+    AuthorizationPending,
+    /// Polling too fast. Continue polling with a longer interval.
+    SlowDown,
+    /// The user declined the authorization request.
+    AuthorizationDeclined,
+    /// The device code was not recognized.
+    BadVerificationCode,
+    /// The device code expired before the user signed in.
+    ExpiredToken,
+}
+
+impl DeviceCodeError {
+    pub fn from_error(error: &str) -> Option<DeviceCodeError> {
+        match error {
+            "authorization_pending" => Some(DeviceCodeError::AuthorizationPending),
+            "slow_down" => Some(DeviceCodeError::SlowDown),
+            "authorization_declined" | "access_denied" => {
+                Some(DeviceCodeError::AuthorizationDeclined)
+            }
+            "bad_verification_code" => Some(DeviceCodeError::BadVerificationCode),
+            "expired_token" => Some(DeviceCodeError::ExpiredToken),
+            _ => None,
+        }
+    }
+
+    /// Get the device code error from the response body of a failed
+    /// token request.
+    pub fn from_failure(failure: &GraphFailure) -> Option<DeviceCodeError> {
+        if let Some(error) = failure.oauth_error() {
+            return DeviceCodeError::from_error(&error.error);
+        }
+        if let GraphFailure::GraphError(error) = failure {
+            let body: serde_json::Value = serde_json::from_str(error.response_body()?).ok()?;
+            return DeviceCodeError::from_error(body["error"].as_str()?);
+        }
+        None
+    }
+
+    /// Returns true if polling should continue.
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self,
+            DeviceCodeError::AuthorizationPending | DeviceCodeError::SlowDown
+        )
+    }
+}
+
+impl AsRef<str> for DeviceCodeError {
+    fn as_ref(&self) -> &str {
+        match self {
+            DeviceCodeError::AuthorizationPending => "authorization_pending",
+            DeviceCodeError::SlowDown => "slow_down",
+            DeviceCodeError::AuthorizationDeclined => "authorization_declined",
+            DeviceCodeError::BadVerificationCode => "bad_verification_code",
+            DeviceCodeError::ExpiredToken => "expired_token",
+        }
+    }
+}
+
+/// Tracks the interval and expiration of the device code while polling.
+struct DevicePolling {
+    interval: Duration,
+    expires_at: Instant,
+}
+
+impl DevicePolling {
+    fn new(device_code: &DeviceCode) -> DevicePolling {
+        DevicePolling {
+            interval: device_code.interval(),
+            expires_at: Instant::now() + device_code.expires_in(),
+        }
+    }
+
+    /// Returns the error if polling should stop.
+    fn next(&mut self, failure: GraphFailure) -> Result<(), GraphFailure> {
+        match DeviceCodeError::from_failure(&failure) {
+            Some(DeviceCodeError::AuthorizationPending) => {}
+            Some(DeviceCodeError::SlowDown) => {
+                self.interval += Duration::from_secs(SLOW_DOWN_INTERVAL);
+            }
+            _ => return Err(failure),
+        }
+
+        // Report the expiration the same way as the token endpoint so that
+        // callers can match DeviceCodeError::ExpiredToken in both cases.
+        if Instant::now() + self.interval >= self.expires_at {
+            return Err(GraphFailure::OAuthError(OAuthErrorResponse {
+                error: DeviceCodeError::ExpiredToken.as_ref().to_string(),
+                error_description: Some(
+                    "The device code expired before the user signed in.".into(),
+                ),
+                ..Default::default()
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeviceCodeRequest {
+    uri: String,
+    params: HashMap<String, String>,
+    #[serde(skip)]
+    http_client: TokenHttpClient,
+}
+
+impl DeviceCodeRequest {
+    /// Send the device authorization request.
+    #[cfg(feature = "blocking")]
+    pub fn send(&mut self) -> OAuthReq<DeviceCode> {
+        let client = self.http_client.blocking_client();
+        let response = client
+            .post(self.uri.as_str())
+            .form(&self.params)
+            .send()?
+            .with_graph_error()
+            .map_err(GraphFailure::from_oauth_response)?;
+        Ok(response.json()?)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AsyncDeviceCodeRequest {
+    uri: String,
+    params: HashMap<String, String>,
+    #[serde(skip)]
+    http_client: TokenHttpClient,
+}
+
+impl AsyncDeviceCodeRequest {
+    /// Send the device authorization request.
+    pub async fn send(&mut self) -> OAuthReq<DeviceCode> {
+        let client = self.http_client.client();
+        let response = client
+            .post(self.uri.as_str())
+            .form(&self.params)
+            .send()
+            .await?
+            .with_graph_error()
+            .await
+            .map_err(GraphFailure::from_oauth_response)?;
+        Ok(response.json().await?)
+    }
+}
+
+fn device_code_request(oauth: &mut OAuth) -> OAuthReq<(String, HashMap<String, String>)> {
+    oauth.check_certificate(GrantType::DeviceCode, GrantRequest::Authorization)?;
+    oauth.pre_request_check(GrantType::DeviceCode, GrantRequest::Authorization);
+    let uri = oauth.get_or_else(OAuthCredential::DeviceCodeURL)?;
+    let params =
+        oauth.params(GrantType::DeviceCode.available_credentials(GrantRequest::Authorization))?;
+    Ok((uri, params))
+}
+
+fn token_request(
+    oauth: &mut OAuth,
+    grant_request: GrantRequest,
+) -> OAuthReq<(String, HashMap<String, String>)> {
+    oauth.check_certificate(GrantType::DeviceCode, grant_request)?;
+    oauth.pre_request_check(GrantType::DeviceCode, grant_request);
+    let uri = match grant_request {
+        GrantRequest::RefreshToken => oauth.get_or_else(OAuthCredential::RefreshTokenURL)?,
+        _ => oauth.get_or_else(OAuthCredential::AccessTokenURL)?,
+    };
+    let params = oauth.params(GrantType::DeviceCode.available_credentials(grant_request))?;
+    Ok((uri, params))
+}
+
+/// The OAuth 2.0 device authorization grant, used to sign in on devices
+/// and applications without a browser such as command line tools.
+///
+/// # See
+/// [Microsoft identity platform and the OAuth 2.0 device authorization grant flow](https://docs.microsoft.com/en-us/azure/active-directory/develop/v2-oauth2-device-code)
+///
+/// # Example
+/// ```rust,ignore
+/// # use graph_oauth::oauth::OAuth;
+/// let mut oauth = OAuth::new();
+/// oauth
+///     .client_id("<YOUR_CLIENT_ID>")
+///     .add_scope("User.Read")
+///     .add_scope("offline_access")
+///     .device_code_url("https://login.microsoftonline.com/common/oauth2/v2.0/devicecode")
+///     .access_token_url("https://login.microsoftonline.com/common/oauth2/v2.0/token");
+///
+/// let mut grant = oauth.build().device_code();
+/// let device_code = grant.device_code().send().unwrap();
+/// println!("{}", device_code.message().unwrap());
+///
+/// let access_token = grant.poll(&device_code).unwrap();
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DeviceCodeGrant {
+    oauth: OAuth,
+}
+
+impl DeviceCodeGrant {
+    pub(crate) fn new(oauth: OAuth) -> DeviceCodeGrant {
+        DeviceCodeGrant { oauth }
+    }
+
+    /// Request a device code, user code and verification uri.
+    pub fn device_code(&mut self) -> OAuthReq<DeviceCodeRequest> {
+        let (uri, params) = device_code_request(&mut self.oauth)?;
+        Ok(DeviceCodeRequest {
+            uri,
+            params,
+            http_client: self.oauth.token_http_client(),
+        })
+    }
+
+    /// A single request for an access token using the device code.
+    pub fn access_token(&mut self, device_code: &DeviceCode) -> OAuthReq<AccessTokenRequest> {
+        self.oauth.device_code(device_code.device_code());
+        let (uri, params) = token_request(&mut self.oauth, GrantRequest::AccessToken)?;
+        Ok(AccessTokenRequest::new(uri, params).with_http_client(self.oauth.token_http_client()))
+    }
+
+    /// Request a new access token using a refresh token.
+    pub fn refresh_token(&mut self) -> OAuthReq<AccessTokenRequest> {
+        let (uri, params) = token_request(&mut self.oauth, GrantRequest::RefreshToken)?;
+        Ok(AccessTokenRequest::new(uri, params).with_http_client(self.oauth.token_http_client()))
+    }
+
+    /// Poll for an access token until the user signs in, declines
+    /// the request or the device code expires.
+    ///
+    /// Polling continues while the token endpoint responds with
+    /// authorization_pending and the interval is increased when
+    /// it responds with slow_down. An expired device code is returned as
+    /// [DeviceCodeError::ExpiredToken], whether the token endpoint or the
+    /// local expiration time reports it.
+    #[cfg(feature = "blocking")]
+    pub fn poll(&mut self, device_code: &DeviceCode) -> OAuthReq<AccessToken> {
+        let mut request = self.access_token(device_code)?;
+        let mut polling = DevicePolling::new(device_code);
+        loop {
+            std::thread::sleep(polling.interval);
+            match request.send() {
+                Ok(access_token) => {
+                    self.oauth.access_token(access_token.clone());
+                    return Ok(access_token);
+                }
+                Err(err) => polling.next(err)?,
+            }
+        }
+    }
+}
+
+impl From<DeviceCodeGrant> for OAuth {
+    fn from(grant: DeviceCodeGrant) -> Self {
+        grant.oauth
+    }
+}
+
+impl AsRef<OAuth> for DeviceCodeGrant {
+    fn as_ref(&self) -> &OAuth {
+        &self.oauth
+    }
+}
+
+/// The async version of the [DeviceCodeGrant].
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AsyncDeviceCodeGrant {
+    oauth: OAuth,
+}
+
+impl AsyncDeviceCodeGrant {
+    pub(crate) fn new(oauth: OAuth) -> AsyncDeviceCodeGrant {
+        AsyncDeviceCodeGrant { oauth }
+    }
+
+    /// Request a device code, user code and verification uri.
+    pub fn device_code(&mut self) -> OAuthReq<AsyncDeviceCodeRequest> {
+        let (uri, params) = device_code_request(&mut self.oauth)?;
+        Ok(AsyncDeviceCodeRequest {
+            uri,
+            params,
+            http_client: self.oauth.token_http_client(),
+        })
+    }
+
+    /// A single request for an access token using the device code.
+    pub fn access_token(&mut self, device_code: &DeviceCode) -> OAuthReq<AsyncAccessTokenRequest> {
+        self.oauth.device_code(device_code.device_code());
+        let (uri, params) = token_request(&mut self.oauth, GrantRequest::AccessToken)?;
+        Ok(AsyncAccessTokenRequest::new(uri, params)
+            .with_http_client(self.oauth.token_http_client()))
+    }
+
+    /// Request a new access token using a refresh token.
+    pub fn refresh_token(&mut self) -> OAuthReq<AsyncAccessTokenRequest> {
+        let (uri, params) = token_request(&mut self.oauth, GrantRequest::RefreshToken)?;
+        Ok(AsyncAccessTokenRequest::new(uri, params)
+            .with_http_client(self.oauth.token_http_client()))
+    }
+
+    /// Poll for an access token until the user signs in, declines
+    /// the request or the device code expires.
+    ///
+    /// Polling continues while the token endpoint responds with
+    /// authorization_pending and the interval is increased when
+    /// it responds with slow_down. An expired device code is returned as
+    /// [DeviceCodeError::ExpiredToken], whether the token endpoint or the
+    /// local expiration time reports it.
+    pub async fn poll(&mut self, device_code: &DeviceCode) -> OAuthReq<AccessToken> {
+        let mut request = self.access_token(device_code)?;
+        let mut polling = DevicePolling::new(device_code);
+        loop {
+            tokio::time::sleep(polling.interval).await;
+            match request.send().await {
+                Ok(access_token) => {
+                    self.oauth.access_token(access_token.clone());
+                    return Ok(access_token);
+                }
+                Err(err) => polling.next(err)?,
+            }
+        }
+    }
+}
+
+impl From<AsyncDeviceCodeGrant> for OAuth {
+    fn from(grant: AsyncDeviceCodeGrant) -> Self {
+        grant.oauth
+    }
+}
+
+impl AsRef<OAuth> for AsyncDeviceCodeGrant {
+    fn as_ref(&self) -> &OAuth {
+        &self.oauth
+    }
+}
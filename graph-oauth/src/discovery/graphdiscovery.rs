@@ -1,5 +1,7 @@
+use crate::auth::OAuthReq;
+use crate::httpclient::TokenHttpClient;
 use crate::oauth::wellknown::WellKnown;
-use crate::oauth::{OAuth, OAuthError};
+use crate::oauth::{OAuth, OAuthError, UserInfo};
 use from_as::*;
 use std::convert::TryFrom;
 use std::io::{Read, Write};
@@ -53,6 +55,55 @@ pub struct MicrosoftSigningKeysV2 {
     pub cloud_graph_host_name: String,
     pub msgraph_host: String,
     pub rbac_url: String,
+    #[serde(skip)]
+    http_client: TokenHttpClient,
+}
+
+impl MicrosoftSigningKeysV2 {
+    /// Send the userinfo requests with a [TokenHttpClient] shared with
+    /// other requests, such as the token requests of
+    /// [OAuth](crate::oauth::OAuth).
+    pub fn share_http_client(&mut self, http_client: &TokenHttpClient) -> &mut Self {
+        self.http_client = http_client.clone();
+        self
+    }
+
+    /// Get the claims of the signed in user from the userinfo endpoint.
+    /// The access token must be the token returned for the openid scope.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// # use graph_oauth::oauth::graphdiscovery::{GraphDiscovery, MicrosoftSigningKeysV2};
+    /// let keys: MicrosoftSigningKeysV2 = GraphDiscovery::V2.signing_keys().unwrap();
+    /// let user_info = keys.user_info("<ACCESS_TOKEN>").unwrap();
+    /// println!("{:#?}", user_info.email);
+    /// ```
+    #[cfg(feature = "blocking")]
+    pub fn user_info(&self, access_token: &str) -> OAuthReq<UserInfo> {
+        UserInfo::request_with(
+            &self.http_client,
+            self.userinfo_endpoint.as_str(),
+            access_token,
+        )
+    }
+
+    /// Get the claims of the signed in user from the userinfo endpoint.
+    /// See [MicrosoftSigningKeysV2::user_info].
+    pub async fn async_user_info(&self, access_token: &str) -> OAuthReq<UserInfo> {
+        UserInfo::async_request_with(
+            &self.http_client,
+            self.userinfo_endpoint.as_str(),
+            access_token,
+        )
+        .await
+    }
+
+    /// The v1.0 Microsoft Graph url of the cloud, such as
+    /// `https://graph.microsoft.com/v1.0`, for use with
+    /// [IdTokenClaims::resolve_groups](crate::oauth::IdTokenClaims::resolve_groups).
+    pub fn graph_url(&self) -> String {
+        format!("https://{}/v1.0", self.msgraph_host)
+    }
 }
 
 pub enum GraphDiscovery {
@@ -91,6 +142,7 @@ impl GraphDiscovery {
     /// let keys: serde_json::Value = GraphDiscovery::V1.signing_keys().unwrap();
     /// println!("{:#?}", keys);
     /// ```
+    #[cfg(feature = "blocking")]
     pub fn signing_keys<T>(self) -> Result<T, OAuthError>
     where
         for<'de> T: serde::Deserialize<'de>,
@@ -126,6 +178,7 @@ impl GraphDiscovery {
     /// let oauth = GraphDiscovery::V1.oauth().unwrap();
     /// println!("{:#?}", oauth);
     /// ```
+    #[cfg(feature = "blocking")]
     pub fn oauth(self) -> Result<OAuth, OAuthError> {
         let mut oauth = OAuth::new();
         match self {
@@ -0,0 +1,368 @@
+use crate::authority::Authority;
+use crate::discovery::graphdiscovery::GraphDiscovery;
+use crate::discovery::jwtkeys::JWTKeys;
+use crate::httpclient::TokenHttpClient;
+use crate::oauth::OAuth;
+use graph_error::GraphResult;
+use reqwest::header::{HeaderMap, CACHE_CONTROL};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// The time to live of cached values when the response does
+/// not have a Cache-Control max-age.
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The minimum time between requests for the same document.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// The OpenID Connect configuration of an authority.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct OpenIdConfiguration {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_session_endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub userinfo_endpoint: Option<String>,
+    #[serde(default)]
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    #[serde(flatten)]
+    pub additional: HashMap<String, Value>,
+}
+
+struct Cached<T> {
+    value: Arc<T>,
+    fetched_at: Instant,
+    expires_at: Instant,
+}
+
+type Slot<T> = Arc<Mutex<Option<Cached<T>>>>;
+
+/// Caches the OpenID configuration and signing keys (JWKS) of an authority.
+///
+/// Cached values are kept for the max-age of the Cache-Control header of the
+/// response, or 24 hours when there is no max-age. When a token is signed
+/// with a key id that is not in the cache the keys are fetched again, which
+/// handles key rotation, but no more than once per refresh interval
+/// (5 minutes by default). If a refresh fails the previous values are used
+/// until the next refresh interval.
+///
+/// Clones share the same cache.
+///
+/// # Example
+/// ```rust,ignore
+/// # use graph_oauth::jwt::{JwtClaims, JwtValidator};
+/// # use graph_oauth::oauth::graphdiscovery::GraphDiscovery;
+/// # use graph_oauth::oauth::jwkscache::JwksCache;
+/// # use graph_oauth::oauth::jwtkeys::JWTKeys;
+/// let cache = JwksCache::new(GraphDiscovery::Tenant("<TENANT_ID>".into()));
+/// let mut validator = JwtValidator::new(JWTKeys::default());
+/// validator.issuer(&cache.configuration().unwrap().issuer).audience("<CLIENT_ID>");
+///
+/// let keys = cache.keys_for_token("<TOKEN>").unwrap();
+/// let claims: JwtClaims = validator.validate_with(&keys, "<TOKEN>").unwrap();
+/// ```
+#[derive(Clone)]
+pub struct JwksCache {
+    url: String,
+    default_ttl: Duration,
+    min_refresh_interval: Duration,
+    http_client: TokenHttpClient,
+    configuration: Slot<OpenIdConfiguration>,
+    keys: Slot<JWTKeys>,
+}
+
+impl JwksCache {
+    pub fn new(discovery: GraphDiscovery) -> JwksCache {
+        JwksCache::from_url(discovery.url().as_str())
+    }
+
+    /// Create a cache for the OpenID configuration of an authority,
+    /// including Azure AD B2C authorities.
+    pub fn from_authority(authority: &Authority) -> JwksCache {
+        JwksCache::from_url(authority.discovery_url().as_str())
+    }
+
+    /// Create a cache for the OpenID configuration document at the given url.
+    pub fn from_url(url: &str) -> JwksCache {
+        JwksCache {
+            url: url.to_string(),
+            default_ttl: DEFAULT_TTL,
+            min_refresh_interval: MIN_REFRESH_INTERVAL,
+            http_client: TokenHttpClient::default(),
+            configuration: Arc::new(Mutex::new(None)),
+            keys: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// The time to live of cached values when the response does
+    /// not have a Cache-Control max-age.
+    pub fn default_ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.default_ttl = ttl;
+        self
+    }
+
+    /// The minimum time between requests for the same document. This
+    /// is also the minimum time to live of cached values.
+    pub fn min_refresh_interval(&mut self, interval: Duration) -> &mut Self {
+        self.min_refresh_interval = interval;
+        self
+    }
+
+    /// Send the discovery requests with a [TokenHttpClient] shared with
+    /// other requests, such as the token requests of
+    /// [OAuth](crate::oauth::OAuth).
+    pub fn share_http_client(&mut self, http_client: &TokenHttpClient) -> &mut Self {
+        self.http_client = http_client.clone();
+        self
+    }
+
+    /// Remove all cached values.
+    pub fn clear(&self) {
+        JwksCache::lock(&self.configuration).take();
+        JwksCache::lock(&self.keys).take();
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn configuration(&self) -> GraphResult<Arc<OpenIdConfiguration>> {
+        if let Some(configuration) = self.cached(&self.configuration) {
+            return Ok(configuration);
+        }
+        let response = self.fetch(self.url.as_str());
+        self.store(&self.configuration, response)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub fn keys(&self) -> GraphResult<Arc<JWTKeys>> {
+        match self.cached(&self.keys) {
+            Some(keys) => Ok(keys),
+            None => self.fetch_keys(),
+        }
+    }
+
+    /// Get the signing keys for a token. If the key id (kid) of the token
+    /// is not in the cached keys the keys are fetched again unless they were
+    /// fetched within the refresh interval.
+    #[cfg(feature = "blocking")]
+    pub fn keys_for_token(&self, token: &str) -> GraphResult<Arc<JWTKeys>> {
+        let keys = self.keys()?;
+        if contains_key(&keys, token) || !self.can_refresh(&self.keys) {
+            return Ok(keys);
+        }
+        self.fetch_keys()
+    }
+
+    /// Fetch the signing keys ignoring the cache.
+    #[cfg(feature = "blocking")]
+    pub fn refresh(&self) -> GraphResult<Arc<JWTKeys>> {
+        self.fetch_keys()
+    }
+
+    /// Create an OAuth instance with the authorization, token and
+    /// logout urls from the cached configuration.
+    #[cfg(feature = "blocking")]
+    pub fn oauth(&self) -> GraphResult<OAuth> {
+        let configuration = self.configuration()?;
+        Ok(JwksCache::to_oauth(&configuration))
+    }
+
+    pub async fn async_configuration(&self) -> GraphResult<Arc<OpenIdConfiguration>> {
+        if let Some(configuration) = self.cached(&self.configuration) {
+            return Ok(configuration);
+        }
+        let response = self.async_fetch(self.url.as_str()).await;
+        self.store(&self.configuration, response)
+    }
+
+    pub async fn async_keys(&self) -> GraphResult<Arc<JWTKeys>> {
+        match self.cached(&self.keys) {
+            Some(keys) => Ok(keys),
+            None => self.async_fetch_keys().await,
+        }
+    }
+
+    /// Get the signing keys for a token. If the key id (kid) of the token
+    /// is not in the cached keys the keys are fetched again unless they were
+    /// fetched within the refresh interval.
+    pub async fn async_keys_for_token(&self, token: &str) -> GraphResult<Arc<JWTKeys>> {
+        let keys = self.async_keys().await?;
+        if contains_key(&keys, token) || !self.can_refresh(&self.keys) {
+            return Ok(keys);
+        }
+        self.async_fetch_keys().await
+    }
+
+    /// Fetch the signing keys ignoring the cache.
+    pub async fn async_refresh(&self) -> GraphResult<Arc<JWTKeys>> {
+        self.async_fetch_keys().await
+    }
+
+    /// Create an OAuth instance with the authorization, token and
+    /// logout urls from the cached configuration.
+    pub async fn async_oauth(&self) -> GraphResult<OAuth> {
+        let configuration = self.async_configuration().await?;
+        Ok(JwksCache::to_oauth(&configuration))
+    }
+
+    #[cfg(feature = "blocking")]
+    fn fetch_keys(&self) -> GraphResult<Arc<JWTKeys>> {
+        let configuration = self.configuration()?;
+        let response = self.fetch(configuration.jwks_uri.as_str());
+        self.store(&self.keys, response)
+    }
+
+    async fn async_fetch_keys(&self) -> GraphResult<Arc<JWTKeys>> {
+        let configuration = self.async_configuration().await?;
+        let response = self.async_fetch(configuration.jwks_uri.as_str()).await;
+        self.store(&self.keys, response)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn fetch<T>(&self, url: &str) -> GraphResult<(T, Option<Duration>)>
+    where
+        for<'de> T: serde::Deserialize<'de>,
+    {
+        let response = self
+            .http_client
+            .blocking_client()
+            .get(url)
+            .send()?
+            .error_for_status()?;
+        let max_age = max_age(response.headers());
+        Ok((response.json()?, max_age))
+    }
+
+    async fn async_fetch<T>(&self, url: &str) -> GraphResult<(T, Option<Duration>)>
+    where
+        for<'de> T: serde::Deserialize<'de>,
+    {
+        let response = self
+            .http_client
+            .client()
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+        let max_age = max_age(response.headers());
+        Ok((response.json().await?, max_age))
+    }
+
+    fn cached<T>(&self, slot: &Slot<T>) -> Option<Arc<T>> {
+        JwksCache::lock(slot)
+            .as_ref()
+            .filter(|cached| Instant::now() < cached.expires_at)
+            .map(|cached| cached.value.clone())
+    }
+
+    fn can_refresh<T>(&self, slot: &Slot<T>) -> bool {
+        match JwksCache::lock(slot).as_ref() {
+            Some(cached) => cached.fetched_at.elapsed() >= self.min_refresh_interval,
+            None => true,
+        }
+    }
+
+    /// Cache the response. If the request failed the previous value, if any,
+    /// is kept for the refresh interval and returned instead of the error.
+    fn store<T>(
+        &self,
+        slot: &Slot<T>,
+        response: GraphResult<(T, Option<Duration>)>,
+    ) -> GraphResult<Arc<T>> {
+        let now = Instant::now();
+        let mut guard = JwksCache::lock(slot);
+        match response {
+            Ok((value, max_age)) => {
+                let ttl = max_age
+                    .unwrap_or(self.default_ttl)
+                    .max(self.min_refresh_interval);
+                let value = Arc::new(value);
+                *guard = Some(Cached {
+                    value: value.clone(),
+                    fetched_at: now,
+                    expires_at: now + ttl,
+                });
+                Ok(value)
+            }
+            Err(err) => match guard.as_mut() {
+                Some(cached) => {
+                    cached.fetched_at = now;
+                    cached.expires_at = now + self.min_refresh_interval;
+                    Ok(cached.value.clone())
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    fn lock<T>(slot: &Slot<T>) -> std::sync::MutexGuard<'_, Option<Cached<T>>> {
+        slot.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn to_oauth(configuration: &OpenIdConfiguration) -> OAuth {
+        let mut oauth = OAuth::new();
+        oauth
+            .authorize_url(configuration.authorization_endpoint.as_str())
+            .access_token_url(configuration.token_endpoint.as_str())
+            .refresh_token_url(configuration.token_endpoint.as_str());
+        if let Some(end_session_endpoint) = configuration.end_session_endpoint.as_ref() {
+            oauth.logout_url(end_session_endpoint.as_str());
+        }
+        oauth
+    }
+}
+
+impl std::fmt::Debug for JwksCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwksCache")
+            .field("url", &self.url)
+            .field("default_ttl", &self.default_ttl)
+            .field("min_refresh_interval", &self.min_refresh_interval)
+            .finish()
+    }
+}
+
+impl From<GraphDiscovery> for JwksCache {
+    fn from(discovery: GraphDiscovery) -> Self {
+        JwksCache::new(discovery)
+    }
+}
+
+/// Returns true if the key for the kid or x5t header of the token is
+/// in the keys. Tokens that cannot be decoded do not trigger a refresh.
+fn contains_key(keys: &JWTKeys, token: &str) -> bool {
+    let header: Option<Value> = token
+        .split('.')
+        .next()
+        .and_then(|header| base64::decode_config(header, base64::URL_SAFE_NO_PAD).ok())
+        .and_then(|header| serde_json::from_slice(&header).ok());
+    let header = match header {
+        Some(header) => header,
+        None => return true,
+    };
+    match (header["kid"].as_str(), header["x5t"].as_str()) {
+        (Some(kid), _) => keys.find(kid).is_some(),
+        (None, Some(x5t)) => keys.find_thumbprint(x5t).is_some(),
+        (None, None) => true,
+    }
+}
+
+/// The max-age of a Cache-Control header. no-cache and no-store
+/// are treated as a max-age of 0.
+fn max_age(headers: &HeaderMap) -> Option<Duration> {
+    let cache_control = headers.get(CACHE_CONTROL)?.to_str().ok()?;
+    cache_control.split(',').find_map(|directive| {
+        let directive = directive.trim();
+        if directive.eq_ignore_ascii_case("no-cache") || directive.eq_ignore_ascii_case("no-store")
+        {
+            return Some(Duration::from_secs(0));
+        }
+        directive
+            .strip_prefix("max-age=")
+            .and_then(|seconds| seconds.trim_matches('"').parse().ok())
+            .map(Duration::from_secs)
+    })
+}
@@ -18,6 +18,12 @@ pub struct Keys {
     pub e: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub x5c: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
 }
 
 impl Keys {
@@ -42,6 +48,7 @@ pub struct JWTKeys {
 }
 
 impl JWTKeys {
+    #[cfg(feature = "blocking")]
     pub fn discovery() -> GraphResult<JWTKeys> {
         let client = reqwest::blocking::Client::new();
         let response = client
@@ -61,6 +68,16 @@ impl JWTKeys {
         Ok(keys)
     }
 
+    /// Find a key by its key id (kid).
+    pub fn find(&self, kid: &str) -> Option<&Keys> {
+        self.keys.iter().find(|key| key.kid.as_deref() == Some(kid))
+    }
+
+    /// Find a key by its X.509 certificate thumbprint (x5t).
+    pub fn find_thumbprint(&self, x5t: &str) -> Option<&Keys> {
+        self.keys.iter().find(|key| key.x5t.as_deref() == Some(x5t))
+    }
+
     pub fn keys(&self) -> Vec<Keys> {
         self.keys.to_vec()
     }
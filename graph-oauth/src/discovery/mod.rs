@@ -1,3 +1,4 @@
 pub mod graphdiscovery;
+pub mod jwkscache;
 pub mod jwtkeys;
 pub mod wellknown;
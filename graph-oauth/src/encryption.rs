@@ -0,0 +1,495 @@
+use crate::accesstoken::AccessToken;
+use crate::auth::{OAuth, OAuthReq};
+use crate::oautherror::OAuthError;
+use from_as::*;
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{Read, Write};
+use std::num::NonZeroU32;
+use zeroize::{Zeroize, Zeroizing};
+
+/// The current version of the encrypted storage format.
+pub const ENCRYPTION_VERSION: u32 = 1;
+
+/// The default number of PBKDF2 iterations used to derive a key
+/// from a passphrase.
+pub const DEFAULT_PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// The maximum number of PBKDF2 iterations. The iterations are read from
+/// the encrypted data before it is authenticated so they are limited to
+/// stop tampered data from making key derivation take arbitrarily long.
+pub const MAX_PBKDF2_ITERATIONS: u32 = 10 * DEFAULT_PBKDF2_ITERATIONS;
+
+const CIPHER: &str = "A256GCM";
+const KDF_ALGORITHM: &str = "PBKDF2-HMAC-SHA256";
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+
+enum Secret {
+    Key([u8; KEY_LEN]),
+    Passphrase { passphrase: String, iterations: u32 },
+}
+
+/// Key used to encrypt credentials and tokens at rest.
+///
+/// The key is either a caller supplied 256 bit key or a passphrase. Keys
+/// for a passphrase are derived using PBKDF2-HMAC-SHA256 with a random salt
+/// that is stored, along with the iteration count, in the encrypted data.
+/// The key material is zeroed when the key is dropped.
+///
+/// # Example
+/// ```
+/// # use graph_oauth::oauth::EncryptionKey;
+/// let key = EncryptionKey::generate().unwrap();
+/// assert_eq!(key.as_bytes().unwrap().len(), 32);
+///
+/// let key = EncryptionKey::from_passphrase("correct horse battery staple");
+/// assert!(key.as_bytes().is_none());
+/// ```
+pub struct EncryptionKey {
+    secret: Secret,
+}
+
+impl EncryptionKey {
+    /// Create a key from 32 bytes of key material.
+    pub fn new(key: &[u8]) -> OAuthReq<EncryptionKey> {
+        if key.len() != KEY_LEN {
+            return OAuthError::invalid_data("encryption key must be 32 bytes");
+        }
+        let mut bytes = [0; KEY_LEN];
+        bytes.copy_from_slice(key);
+        Ok(EncryptionKey {
+            secret: Secret::Key(bytes),
+        })
+    }
+
+    /// Generate a random 256 bit key.
+    pub fn generate() -> OAuthReq<EncryptionKey> {
+        let mut bytes = [0; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| OAuthError::invalid("unable to generate encryption key"))?;
+        Ok(EncryptionKey {
+            secret: Secret::Key(bytes),
+        })
+    }
+
+    /// Create a key that is derived from a passphrase.
+    pub fn from_passphrase(passphrase: &str) -> EncryptionKey {
+        EncryptionKey {
+            secret: Secret::Passphrase {
+                passphrase: passphrase.to_string(),
+                iterations: DEFAULT_PBKDF2_ITERATIONS,
+            },
+        }
+    }
+
+    /// Set the number of PBKDF2 iterations used when encrypting with a
+    /// passphrase. Decryption always uses the iterations stored with the
+    /// encrypted data. Has no effect for keys that are not passphrases.
+    ///
+    /// Encryption fails if the iterations are more than
+    /// [MAX_PBKDF2_ITERATIONS].
+    pub fn iterations(&mut self, value: u32) -> &mut EncryptionKey {
+        if let Secret::Passphrase { iterations, .. } = &mut self.secret {
+            *iterations = value;
+        }
+        self
+    }
+
+    /// The key material, or None if the key is derived from a passphrase.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match &self.secret {
+            Secret::Key(bytes) => Some(bytes),
+            Secret::Passphrase { .. } => None,
+        }
+    }
+
+    // Returns the key used to encrypt new data and the key derivation
+    // parameters that must be stored with it.
+    fn encryption_key(&self) -> OAuthReq<(Zeroizing<[u8; KEY_LEN]>, Option<KeyDerivation>)> {
+        match &self.secret {
+            Secret::Key(bytes) => Ok((Zeroizing::new(*bytes), None)),
+            Secret::Passphrase { iterations, .. } => {
+                let mut salt = [0; SALT_LEN];
+                SystemRandom::new()
+                    .fill(&mut salt)
+                    .map_err(|_| OAuthError::invalid("unable to generate salt"))?;
+                let kdf = KeyDerivation {
+                    algorithm: KDF_ALGORITHM.into(),
+                    iterations: *iterations,
+                    salt: base64::encode(salt),
+                };
+                Ok((self.decryption_key(&Some(kdf.clone()))?, Some(kdf)))
+            }
+        }
+    }
+
+    fn decryption_key(&self, kdf: &Option<KeyDerivation>) -> OAuthReq<Zeroizing<[u8; KEY_LEN]>> {
+        match (&self.secret, kdf) {
+            (Secret::Key(bytes), None) => Ok(Zeroizing::new(*bytes)),
+            (Secret::Passphrase { passphrase, .. }, Some(kdf)) => {
+                if kdf.algorithm != KDF_ALGORITHM {
+                    return OAuthError::invalid_data(&format!(
+                        "unsupported key derivation algorithm: {}",
+                        kdf.algorithm
+                    ));
+                }
+                let iterations = NonZeroU32::new(kdf.iterations).ok_or_else(|| {
+                    OAuthError::invalid("key derivation iterations must not be 0")
+                })?;
+                if iterations.get() > MAX_PBKDF2_ITERATIONS {
+                    return OAuthError::invalid_data(&format!(
+                        "key derivation iterations must not be more than {}",
+                        MAX_PBKDF2_ITERATIONS
+                    ));
+                }
+                let salt = base64::decode(&kdf.salt)
+                    .map_err(|_| OAuthError::invalid("key derivation salt"))?;
+                let mut key = Zeroizing::new([0; KEY_LEN]);
+                pbkdf2::derive(
+                    pbkdf2::PBKDF2_HMAC_SHA256,
+                    iterations,
+                    &salt,
+                    passphrase.as_bytes(),
+                    &mut key[..],
+                );
+                Ok(key)
+            }
+            (Secret::Key(_), Some(_)) => {
+                OAuthError::invalid_data("data was encrypted with a passphrase")
+            }
+            (Secret::Passphrase { .. }, None) => {
+                OAuthError::invalid_data("data was encrypted with a key and not a passphrase")
+            }
+        }
+    }
+}
+
+impl Clone for EncryptionKey {
+    fn clone(&self) -> Self {
+        let secret = match &self.secret {
+            Secret::Key(bytes) => Secret::Key(*bytes),
+            Secret::Passphrase {
+                passphrase,
+                iterations,
+            } => Secret::Passphrase {
+                passphrase: passphrase.clone(),
+                iterations: *iterations,
+            },
+        };
+        EncryptionKey { secret }
+    }
+}
+
+impl Drop for EncryptionKey {
+    fn drop(&mut self) {
+        match &mut self.secret {
+            Secret::Key(bytes) => bytes.zeroize(),
+            Secret::Passphrase { passphrase, .. } => passphrase.zeroize(),
+        }
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.secret {
+            Secret::Key(_) => "key",
+            Secret::Passphrase { .. } => "passphrase",
+        };
+        f.debug_struct("EncryptionKey")
+            .field("kind", &kind)
+            .field("secret", &"[REDACTED]")
+            .finish()
+    }
+}
+
+/// Parameters used to derive a key from a passphrase.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KeyDerivation {
+    pub algorithm: String,
+    pub iterations: u32,
+    pub salt: String,
+}
+
+// Authenticated with the ciphertext so that the unencrypted fields
+// cannot be changed without failing decryption.
+#[derive(Serialize)]
+struct Header<'a> {
+    version: u32,
+    cipher: &'a str,
+    kdf: &'a Option<KeyDerivation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scopes: Option<&'a BTreeSet<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    credentials: Option<&'a BTreeMap<String, String>>,
+}
+
+impl<'a> Header<'a> {
+    fn check(&self) -> OAuthReq<()> {
+        if self.version != ENCRYPTION_VERSION {
+            return OAuthError::invalid_data(&format!(
+                "unsupported encryption version: {}",
+                self.version
+            ));
+        }
+        if self.cipher != CIPHER {
+            return OAuthError::invalid_data(&format!("unsupported cipher: {}", self.cipher));
+        }
+        Ok(())
+    }
+
+    fn aad(&self) -> OAuthReq<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+fn aead_key(key: &[u8; KEY_LEN]) -> OAuthReq<LessSafeKey> {
+    let key = UnboundKey::new(&aead::AES_256_GCM, key)
+        .map_err(|_| OAuthError::invalid("encryption key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+// Encrypts the plaintext and returns the nonce and ciphertext.
+fn seal(
+    key: &[u8; KEY_LEN],
+    header: &Header<'_>,
+    mut plaintext: Zeroizing<Vec<u8>>,
+) -> OAuthReq<(String, String)> {
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| OAuthError::invalid("unable to generate nonce"))?;
+    aead_key(key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(header.aad()?),
+            &mut *plaintext,
+        )
+        .map_err(|_| OAuthError::invalid("unable to encrypt data"))?;
+    Ok((base64::encode(nonce), base64::encode(&*plaintext)))
+}
+
+fn open(
+    key: &[u8; KEY_LEN],
+    header: &Header<'_>,
+    nonce: &str,
+    ciphertext: &str,
+) -> OAuthReq<Zeroizing<Vec<u8>>> {
+    let nonce = base64::decode(nonce)
+        .ok()
+        .and_then(|nonce| Nonce::try_assume_unique_for_key(&nonce).ok())
+        .ok_or_else(|| OAuthError::invalid("encryption nonce"))?;
+    let mut in_out = Zeroizing::new(
+        base64::decode(ciphertext).map_err(|_| OAuthError::invalid("encrypted data"))?,
+    );
+    let len = aead_key(key)?
+        .open_in_place(nonce, Aad::from(header.aad()?), &mut in_out)
+        .map_err(|_| {
+            OAuthError::invalid(
+                "unable to decrypt data: the key is incorrect or the data was modified",
+            )
+        })?
+        .len();
+    in_out.truncate(len);
+    Ok(in_out)
+}
+
+/// Versioned envelope for a value that is encrypted with AES-256-GCM.
+///
+/// # Example
+/// ```
+/// # use graph_oauth::oauth::{AccessToken, EncryptionKey};
+/// let key = EncryptionKey::generate().unwrap();
+/// let access_token = AccessToken::new("Bearer", 3600, "User.Read", "ASODFIUJ34KJ;LADSK");
+///
+/// let encrypted = access_token.encrypt(&key).unwrap();
+/// let decrypted = AccessToken::decrypt(&encrypted, &key).unwrap();
+/// assert_eq!(decrypted.bearer_token(), "ASODFIUJ34KJ;LADSK");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, AsFile, FromFile)]
+pub struct EncryptedData {
+    version: u32,
+    cipher: String,
+    kdf: Option<KeyDerivation>,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl EncryptedData {
+    /// Serialize and encrypt a value.
+    pub fn encrypt<T: Serialize>(value: &T, key: &EncryptionKey) -> OAuthReq<EncryptedData> {
+        let (key, kdf) = key.encryption_key()?;
+        let plaintext = Zeroizing::new(serde_json::to_vec(value)?);
+        let (nonce, ciphertext) = seal(&key, &EncryptedData::header(&kdf), plaintext)?;
+        Ok(EncryptedData {
+            version: ENCRYPTION_VERSION,
+            cipher: CIPHER.into(),
+            kdf,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Decrypt and deserialize a value.
+    pub fn decrypt<T: DeserializeOwned>(&self, key: &EncryptionKey) -> OAuthReq<T> {
+        let header = Header {
+            version: self.version,
+            cipher: self.cipher.as_str(),
+            ..EncryptedData::header(&self.kdf)
+        };
+        header.check()?;
+        let key = key.decryption_key(&self.kdf)?;
+        let plaintext = open(&key, &header, &self.nonce, &self.ciphertext)?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn kdf(&self) -> Option<&KeyDerivation> {
+        self.kdf.as_ref()
+    }
+
+    fn header(kdf: &Option<KeyDerivation>) -> Header<'_> {
+        Header {
+            version: ENCRYPTION_VERSION,
+            cipher: CIPHER,
+            kdf,
+            scopes: None,
+            credentials: None,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct OAuthSecrets {
+    pub(crate) credentials: BTreeMap<String, String>,
+    pub(crate) access_token: Option<AccessToken>,
+    #[serde(default)]
+    pub(crate) tokens: Vec<AccessToken>,
+}
+
+impl Drop for OAuthSecrets {
+    fn drop(&mut self) {
+        for value in self.credentials.values_mut() {
+            value.zeroize();
+        }
+    }
+}
+
+/// Encrypted storage format for [OAuth].
+///
+/// Credentials that are sensitive, as given by
+/// [OAuthCredential::is_sensitive](crate::oauth::OAuthCredential::is_sensitive),
+/// and the access token are encrypted with AES-256-GCM. The scopes and the
+/// remaining credentials are stored in plain text so that they can be read
+/// without the key, but they are authenticated and any change to them
+/// fails decryption.
+///
+/// # Example
+/// ```
+/// # use graph_oauth::oauth::{EncryptionKey, OAuth, OAuthCredential};
+/// let mut oauth = OAuth::new();
+/// oauth
+///     .client_id("<CLIENT_ID>")
+///     .client_secret("<CLIENT_SECRET>")
+///     .redirect_uri("http://localhost:8000/redirect");
+///
+/// let mut key = EncryptionKey::from_passphrase("correct horse battery staple");
+/// # key.iterations(1000);
+/// let encrypted = oauth.encrypt(&key).unwrap();
+/// assert!(encrypted.credentials().contains_key("redirect_uri"));
+/// assert!(!encrypted.credentials().contains_key("client_secret"));
+///
+/// let decrypted = encrypted.decrypt(&key).unwrap();
+/// assert_eq!(decrypted.get(OAuthCredential::ClientSecret).unwrap(), "<CLIENT_SECRET>");
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, AsFile, FromFile)]
+pub struct EncryptedOAuth {
+    version: u32,
+    cipher: String,
+    kdf: Option<KeyDerivation>,
+    scopes: BTreeSet<String>,
+    credentials: BTreeMap<String, String>,
+    nonce: String,
+    secrets: String,
+}
+
+impl EncryptedOAuth {
+    pub(crate) fn seal(
+        key: &EncryptionKey,
+        scopes: BTreeSet<String>,
+        credentials: BTreeMap<String, String>,
+        secrets: &OAuthSecrets,
+    ) -> OAuthReq<EncryptedOAuth> {
+        let (key, kdf) = key.encryption_key()?;
+        let header = Header {
+            version: ENCRYPTION_VERSION,
+            cipher: CIPHER,
+            kdf: &kdf,
+            scopes: Some(&scopes),
+            credentials: Some(&credentials),
+        };
+        let plaintext = Zeroizing::new(serde_json::to_vec(secrets)?);
+        let (nonce, secrets) = seal(&key, &header, plaintext)?;
+        Ok(EncryptedOAuth {
+            version: ENCRYPTION_VERSION,
+            cipher: CIPHER.into(),
+            kdf,
+            scopes,
+            credentials,
+            nonce,
+            secrets,
+        })
+    }
+
+    /// Decrypt the sensitive credentials and access token.
+    pub fn decrypt(&self, key: &EncryptionKey) -> OAuthReq<OAuth> {
+        let header = Header {
+            version: self.version,
+            cipher: self.cipher.as_str(),
+            kdf: &self.kdf,
+            scopes: Some(&self.scopes),
+            credentials: Some(&self.credentials),
+        };
+        header.check()?;
+        let key = key.decryption_key(&self.kdf)?;
+        let plaintext = open(&key, &header, &self.nonce, &self.secrets)?;
+        let mut secrets: OAuthSecrets = serde_json::from_slice(&plaintext)?;
+
+        let mut credentials = self.credentials.clone();
+        credentials.append(&mut secrets.credentials);
+        Ok(OAuth::from_parts(
+            self.scopes.clone(),
+            credentials,
+            secrets.access_token.take(),
+            std::mem::take(&mut secrets.tokens),
+        ))
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn kdf(&self) -> Option<&KeyDerivation> {
+        self.kdf.as_ref()
+    }
+
+    /// The scopes, which are stored unencrypted.
+    pub fn scopes(&self) -> &BTreeSet<String> {
+        &self.scopes
+    }
+
+    /// The credentials that are not sensitive and are stored unencrypted.
+    pub fn credentials(&self) -> &BTreeMap<String, String> {
+        &self.credentials
+    }
+}
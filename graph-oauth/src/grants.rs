@@ -1,5 +1,10 @@
 use crate::auth::OAuthCredential;
 
+pub(crate) const JWT_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+pub(crate) const SAML1_1_BEARER_GRANT_TYPE: &str =
+    "urn:ietf:params:oauth:grant-type:saml1_1-bearer";
+pub(crate) const SAML2_BEARER_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:saml2-bearer";
+
 #[derive(
     Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Serialize, Deserialize, EnumIter,
 )]
@@ -20,6 +25,12 @@ pub enum GrantType {
     OpenId,
     ClientCredentials,
     ResourceOwnerPasswordCredentials,
+    DeviceCode,
+    OnBehalfOf,
+    AdminConsent,
+    JwtBearer,
+    Saml11Bearer,
+    Saml2Bearer,
 }
 
 impl GrantType {
@@ -46,6 +57,8 @@ impl GrantType {
                 GrantRequest::AccessToken => vec![
                     OAuthCredential::ClientId,
                     OAuthCredential::ClientSecret,
+                    OAuthCredential::ClientAssertion,
+                    OAuthCredential::ClientAssertionType,
                     OAuthCredential::RedirectURI,
                     OAuthCredential::ResponseType,
                     OAuthCredential::GrantType,
@@ -54,6 +67,8 @@ impl GrantType {
                 GrantRequest::RefreshToken => vec![
                     OAuthCredential::ClientId,
                     OAuthCredential::ClientSecret,
+                    OAuthCredential::ClientAssertion,
+                    OAuthCredential::ClientAssertionType,
                     OAuthCredential::RedirectURI,
                     OAuthCredential::GrantType,
                     OAuthCredential::AccessCode,
@@ -73,22 +88,29 @@ impl GrantType {
                     OAuthCredential::LoginHint,
                     OAuthCredential::CodeChallenge,
                     OAuthCredential::CodeChallengeMethod,
+                    OAuthCredential::Claims,
                 ],
                 GrantRequest::AccessToken => vec![
                     OAuthCredential::ClientId,
                     OAuthCredential::ClientSecret,
+                    OAuthCredential::ClientAssertion,
+                    OAuthCredential::ClientAssertionType,
                     OAuthCredential::RedirectURI,
                     OAuthCredential::AccessCode,
                     OAuthCredential::Scopes,
                     OAuthCredential::GrantType,
                     OAuthCredential::CodeVerifier,
+                    OAuthCredential::Claims,
                 ],
                 GrantRequest::RefreshToken => vec![
                     OAuthCredential::ClientId,
                     OAuthCredential::ClientSecret,
+                    OAuthCredential::ClientAssertion,
+                    OAuthCredential::ClientAssertionType,
                     OAuthCredential::RefreshToken,
                     OAuthCredential::GrantType,
                     OAuthCredential::Scopes,
+                    OAuthCredential::Claims,
                 ],
             },
             GrantType::Implicit => match grant_request {
@@ -120,22 +142,29 @@ impl GrantType {
                     OAuthCredential::LoginHint,
                     OAuthCredential::DomainHint,
                     OAuthCredential::Resource,
+                    OAuthCredential::Claims,
                 ],
                 GrantRequest::AccessToken => vec![
                     OAuthCredential::ClientId,
                     OAuthCredential::ClientSecret,
+                    OAuthCredential::ClientAssertion,
+                    OAuthCredential::ClientAssertionType,
                     OAuthCredential::RedirectURI,
                     OAuthCredential::GrantType,
                     OAuthCredential::Scopes,
                     OAuthCredential::AccessCode,
                     OAuthCredential::CodeVerifier,
+                    OAuthCredential::Claims,
                 ],
                 GrantRequest::RefreshToken => vec![
                     OAuthCredential::ClientId,
                     OAuthCredential::ClientSecret,
+                    OAuthCredential::ClientAssertion,
+                    OAuthCredential::ClientAssertionType,
                     OAuthCredential::RefreshToken,
                     OAuthCredential::GrantType,
                     OAuthCredential::Scopes,
+                    OAuthCredential::Claims,
                 ],
             },
             GrantType::ClientCredentials => match grant_request {
@@ -151,6 +180,7 @@ impl GrantType {
                     OAuthCredential::Scopes,
                     OAuthCredential::ClientAssertion,
                     OAuthCredential::ClientAssertionType,
+                    OAuthCredential::Claims,
                 ],
             },
             GrantType::ResourceOwnerPasswordCredentials => match grant_request {
@@ -165,8 +195,95 @@ impl GrantType {
                     OAuthCredential::Scopes,
                     OAuthCredential::RedirectURI,
                     OAuthCredential::ClientAssertion,
+                    OAuthCredential::ClientAssertionType,
+                    OAuthCredential::Claims,
+                ],
+            },
+            GrantType::OnBehalfOf => match grant_request {
+                GrantRequest::Authorization => vec![OAuthCredential::ClientId],
+                GrantRequest::AccessToken => vec![
+                    OAuthCredential::ClientId,
+                    OAuthCredential::ClientSecret,
+                    OAuthCredential::ClientAssertion,
+                    OAuthCredential::ClientAssertionType,
+                    OAuthCredential::GrantType,
+                    OAuthCredential::Assertion,
+                    OAuthCredential::RequestedTokenUse,
+                    OAuthCredential::Scopes,
+                    OAuthCredential::Claims,
+                ],
+                GrantRequest::RefreshToken => vec![
+                    OAuthCredential::ClientId,
+                    OAuthCredential::ClientSecret,
+                    OAuthCredential::ClientAssertion,
+                    OAuthCredential::ClientAssertionType,
+                    OAuthCredential::RefreshToken,
+                    OAuthCredential::GrantType,
+                    OAuthCredential::Scopes,
+                    OAuthCredential::Claims,
                 ],
             },
+            GrantType::DeviceCode => match grant_request {
+                GrantRequest::Authorization => {
+                    vec![OAuthCredential::ClientId, OAuthCredential::Scopes]
+                }
+                GrantRequest::AccessToken => vec![
+                    OAuthCredential::ClientId,
+                    OAuthCredential::GrantType,
+                    OAuthCredential::DeviceCode,
+                ],
+                GrantRequest::RefreshToken => vec![
+                    OAuthCredential::ClientId,
+                    OAuthCredential::RefreshToken,
+                    OAuthCredential::GrantType,
+                    OAuthCredential::Scopes,
+                    OAuthCredential::Claims,
+                ],
+            },
+            GrantType::AdminConsent => match grant_request {
+                GrantRequest::Authorization => vec![
+                    OAuthCredential::ClientId,
+                    OAuthCredential::RedirectURI,
+                    OAuthCredential::State,
+                    OAuthCredential::Scopes,
+                ],
+                GrantRequest::AccessToken | GrantRequest::RefreshToken => vec![],
+            },
+            GrantType::JwtBearer | GrantType::Saml11Bearer | GrantType::Saml2Bearer => {
+                match grant_request {
+                    GrantRequest::Authorization => vec![OAuthCredential::ClientId],
+                    GrantRequest::AccessToken => vec![
+                        OAuthCredential::ClientId,
+                        OAuthCredential::ClientSecret,
+                        OAuthCredential::ClientAssertion,
+                        OAuthCredential::ClientAssertionType,
+                        OAuthCredential::GrantType,
+                        OAuthCredential::Assertion,
+                        OAuthCredential::Scopes,
+                        OAuthCredential::Claims,
+                    ],
+                    GrantRequest::RefreshToken => vec![
+                        OAuthCredential::ClientId,
+                        OAuthCredential::ClientSecret,
+                        OAuthCredential::ClientAssertion,
+                        OAuthCredential::ClientAssertionType,
+                        OAuthCredential::RefreshToken,
+                        OAuthCredential::GrantType,
+                        OAuthCredential::Scopes,
+                        OAuthCredential::Claims,
+                    ],
+                }
+            }
+        }
+    }
+
+    /// The grant_type of token requests for the bearer assertion grants.
+    pub(crate) fn assertion_grant_type(self) -> Option<&'static str> {
+        match self {
+            GrantType::JwtBearer => Some(JWT_BEARER_GRANT_TYPE),
+            GrantType::Saml11Bearer => Some(SAML1_1_BEARER_GRANT_TYPE),
+            GrantType::Saml2Bearer => Some(SAML2_BEARER_GRANT_TYPE),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,133 @@
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+type ClientSlot<T> = Arc<Mutex<Option<T>>>;
+
+/// The HTTP clients that send the requests of [OAuth](crate::oauth::OAuth),
+/// the token credentials and the discovery, userinfo and group requests.
+/// When a client is not set a default client is created on first use and
+/// shared by clones.
+///
+/// Share one instance to send every request through the same transport,
+/// such as the client used for Graph requests. Clients set after the
+/// instance is shared are not seen by the other clones.
+///
+/// # Example
+/// ```
+/// # use graph_oauth::oauth::{ManagedIdentityCredential, OAuth, TokenHttpClient};
+/// let mut http_client = TokenHttpClient::new();
+/// http_client.http_client(reqwest::Client::new());
+///
+/// let mut oauth = OAuth::new();
+/// oauth.share_http_client(&http_client);
+/// let mut credential = ManagedIdentityCredential::new();
+/// credential.share_http_client(&http_client);
+/// ```
+#[derive(Default, Clone)]
+pub struct TokenHttpClient {
+    client: ClientSlot<reqwest::Client>,
+    #[cfg(feature = "blocking")]
+    blocking_client: ClientSlot<reqwest::blocking::Client>,
+    connect_timeout: Option<Duration>,
+    // Set when a client is given instead of creating a default client.
+    configured: bool,
+}
+
+impl TokenHttpClient {
+    pub fn new() -> TokenHttpClient {
+        TokenHttpClient::default()
+    }
+
+    // Default clients created by this instance use the connect timeout.
+    pub(crate) fn with_connect_timeout(connect_timeout: Duration) -> TokenHttpClient {
+        TokenHttpClient {
+            connect_timeout: Some(connect_timeout),
+            ..Default::default()
+        }
+    }
+
+    /// Set the client of async requests.
+    pub fn http_client(&mut self, client: reqwest::Client) -> &mut Self {
+        self.client = Arc::new(Mutex::new(Some(client)));
+        self.configured = true;
+        self
+    }
+
+    /// Set the client of blocking requests.
+    #[cfg(feature = "blocking")]
+    pub fn blocking_http_client(&mut self, client: reqwest::blocking::Client) -> &mut Self {
+        self.blocking_client = Arc::new(Mutex::new(Some(client)));
+        self.configured = true;
+        self
+    }
+
+    pub(crate) fn client(&self) -> reqwest::Client {
+        TokenHttpClient::lock(&self.client)
+            .get_or_insert_with(|| {
+                let mut builder = reqwest::Client::builder();
+                if let Some(connect_timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                builder.build().expect("failed to create the HTTP client")
+            })
+            .clone()
+    }
+
+    // The blocking client can't be created in an async runtime so it
+    // is not created until the first blocking request.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn blocking_client(&self) -> reqwest::blocking::Client {
+        TokenHttpClient::lock(&self.blocking_client)
+            .get_or_insert_with(|| {
+                let mut builder = reqwest::blocking::Client::builder();
+                if let Some(connect_timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                builder.build().expect("failed to create the HTTP client")
+            })
+            .clone()
+    }
+
+    fn lock<T>(slot: &ClientSlot<T>) -> MutexGuard<'_, Option<T>> {
+        slot.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+// Clients can't be compared so instances are equal when both create
+// default clients with the same connect timeout or when they share the
+// same configured clients.
+impl PartialEq for TokenHttpClient {
+    fn eq(&self, other: &Self) -> bool {
+        if self.connect_timeout != other.connect_timeout {
+            return false;
+        }
+        if !self.configured && !other.configured {
+            return true;
+        }
+        #[cfg(feature = "blocking")]
+        {
+            if !Arc::ptr_eq(&self.blocking_client, &other.blocking_client) {
+                return false;
+            }
+        }
+        Arc::ptr_eq(&self.client, &other.client)
+    }
+}
+
+impl Eq for TokenHttpClient {}
+
+impl fmt::Debug for TokenHttpClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("TokenHttpClient");
+        debug.field("client", &TokenHttpClient::lock(&self.client).is_some());
+        #[cfg(feature = "blocking")]
+        debug.field(
+            "blocking_client",
+            &TokenHttpClient::lock(&self.blocking_client).is_some(),
+        );
+        debug
+            .field("connect_timeout", &self.connect_timeout)
+            .finish()
+    }
+}
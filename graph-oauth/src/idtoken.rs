@@ -1,7 +1,14 @@
+use crate::auth::OAuthReq;
+use crate::httpclient::TokenHttpClient;
+use crate::jwt::{Audience, JwtValidationError, JwtValidator};
 use crate::oautherror::OAuthError;
 use from_as::*;
-use graph_error::GraphFailure;
+#[cfg(feature = "blocking")]
+use graph_error::WithGraphError;
+use graph_error::{GraphFailure, WithGraphErrorAsync};
+use serde_json::Value;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::io::{Read, Write};
 use std::str::FromStr;
@@ -56,6 +63,12 @@ impl IdToken {
     pub fn get_session_state(&self) -> Option<String> {
         self.session_state.clone()
     }
+
+    /// Validate the signature and claims of the id token and get the
+    /// claims. See [JwtValidator::validate_id_token].
+    pub fn validate(&self, validator: &JwtValidator) -> Result<IdTokenClaims, JwtValidationError> {
+        validator.validate_id_token(self.id_token.as_str())
+    }
 }
 
 impl TryFrom<String> for IdToken {
@@ -97,3 +110,280 @@ impl FromStr for IdToken {
         Ok(id_token)
     }
 }
+
+/// A source of distributed claims given by the `_claim_sources` claim.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ClaimSource {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+}
+
+/// The claims of an ID token issued by the Microsoft identity platform.
+/// Get the claims of a validated ID token using [IdToken::validate] or
+/// [JwtValidator::validate_id_token]. Claims that do not have a field are
+/// kept in `additional`.
+///
+/// When a user is a member of more groups than fit in a token the groups
+/// claim is left out. Instead the token has `hasgroups` or a groups entry
+/// in `_claim_names`. Use [IdTokenClaims::has_group_overage] to check for
+/// this and [IdTokenClaims::resolve_groups] to get the groups from
+/// Microsoft Graph.
+///
+/// # See
+/// [ID token claims reference](https://docs.microsoft.com/en-us/azure/active-directory/develop/id-tokens)
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<Audience>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// The object id of the user in the tenant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oid: Option<String>,
+    /// The tenant id of the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tid: Option<String>,
+    /// The subject, which is unique to the user and the application.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// The app roles assigned to the user.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+    /// The object ids of the groups of the user.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    /// Set when the groups of the user did not fit in the token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hasgroups: Option<bool>,
+    #[serde(
+        rename = "_claim_names",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub claim_names: HashMap<String, String>,
+    #[serde(
+        rename = "_claim_sources",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub claim_sources: HashMap<String, ClaimSource>,
+    #[serde(flatten)]
+    pub additional: HashMap<String, Value>,
+    #[serde(skip)]
+    http_client: TokenHttpClient,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MemberObjectsRequest {
+    security_enabled_only: bool,
+}
+
+#[derive(Deserialize)]
+struct MemberObjects {
+    value: Vec<String>,
+}
+
+impl IdTokenClaims {
+    /// Returns true if the groups of the user did not fit in the token
+    /// and must be requested from Microsoft Graph.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::IdTokenClaims;
+    /// let claims: IdTokenClaims = serde_json::from_str(
+    ///     r#"{"oid":"a0ca4d4c-b80a-4d26-8a21-9a8a8d1b1f4b","hasgroups":true}"#,
+    /// )
+    /// .unwrap();
+    /// assert!(claims.has_group_overage());
+    /// ```
+    pub fn has_group_overage(&self) -> bool {
+        self.hasgroups.unwrap_or(false) || self.claim_names.contains_key("groups")
+    }
+
+    /// The endpoint of the groups given by the `_claim_sources` claim.
+    /// This is the Azure AD Graph endpoint for v1.0 tokens; use
+    /// [IdTokenClaims::resolve_groups] to get the groups from Microsoft
+    /// Graph instead.
+    pub fn group_overage_endpoint(&self) -> Option<&str> {
+        let source = self.claim_names.get("groups")?;
+        self.claim_sources.get(source)?.endpoint.as_deref()
+    }
+
+    /// Send the group requests with a [TokenHttpClient] shared with
+    /// other requests, such as the token requests of
+    /// [OAuth](crate::oauth::OAuth).
+    pub fn share_http_client(&mut self, http_client: &TokenHttpClient) -> &mut Self {
+        self.http_client = http_client.clone();
+        self
+    }
+
+    /// Get the groups of the user. If the token has a group overage the
+    /// security groups of the user are requested from Microsoft Graph
+    /// using [getMemberObjects](https://docs.microsoft.com/en-us/graph/api/directoryobject-getmemberobjects)
+    /// and set as the groups of the claims.
+    ///
+    /// The graph url is the Microsoft Graph url with the version, such as
+    /// `https://graph.microsoft.com/v1.0`, and the access token must be
+    /// a Microsoft Graph token with the GroupMember.Read.All or
+    /// Directory.Read.All scope.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// # use graph_oauth::oauth::IdTokenClaims;
+    /// # let mut claims = IdTokenClaims::default();
+    /// let groups = claims
+    ///     .resolve_groups("https://graph.microsoft.com/v1.0", "<ACCESS_TOKEN>")
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "blocking")]
+    pub fn resolve_groups(&mut self, graph_url: &str, access_token: &str) -> OAuthReq<&[String]> {
+        if let Some(url) = self.member_objects_url(graph_url)? {
+            let member_objects: MemberObjects = self
+                .http_client
+                .blocking_client()
+                .post(url.as_str())
+                .bearer_auth(access_token)
+                .json(&MemberObjectsRequest {
+                    security_enabled_only: true,
+                })
+                .send()?
+                .with_graph_error()?
+                .json()?;
+            self.set_resolved_groups(member_objects.value);
+        }
+        Ok(self.groups.as_slice())
+    }
+
+    /// Get the groups of the user. See [IdTokenClaims::resolve_groups].
+    pub async fn async_resolve_groups(
+        &mut self,
+        graph_url: &str,
+        access_token: &str,
+    ) -> OAuthReq<&[String]> {
+        if let Some(url) = self.member_objects_url(graph_url)? {
+            let member_objects: MemberObjects = self
+                .http_client
+                .client()
+                .post(url.as_str())
+                .bearer_auth(access_token)
+                .json(&MemberObjectsRequest {
+                    security_enabled_only: true,
+                })
+                .send()
+                .await?
+                .with_graph_error()
+                .await?
+                .json()
+                .await?;
+            self.set_resolved_groups(member_objects.value);
+        }
+        Ok(self.groups.as_slice())
+    }
+
+    // The getMemberObjects url of the user, or None if there is no overage.
+    fn member_objects_url(&self, graph_url: &str) -> OAuthReq<Option<String>> {
+        if !self.has_group_overage() {
+            return Ok(None);
+        }
+        let oid = self.oid.as_ref().ok_or_else(|| {
+            OAuthError::invalid("The oid claim is required to resolve the groups of the user")
+        })?;
+        Ok(Some(format!(
+            "{}/users/{}/getMemberObjects",
+            graph_url.trim_end_matches('/'),
+            oid
+        )))
+    }
+
+    fn set_resolved_groups(&mut self, groups: Vec<String>) {
+        self.groups = groups;
+        self.hasgroups = None;
+        if let Some(source) = self.claim_names.remove("groups") {
+            self.claim_sources.remove(&source);
+        }
+    }
+}
+
+/// The claims returned by the OpenID Connect userinfo endpoint. Claims
+/// that do not have a field are kept in `additional`.
+///
+/// # See
+/// [Microsoft identity platform UserInfo endpoint](https://docs.microsoft.com/en-us/azure/active-directory/develop/userinfo)
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserInfo {
+    pub sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub picture: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(flatten)]
+    pub additional: HashMap<String, Value>,
+}
+
+impl UserInfo {
+    /// Get the claims of the user from a userinfo endpoint using an
+    /// access token for the openid scope.
+    #[cfg(feature = "blocking")]
+    pub fn request(userinfo_endpoint: &str, access_token: &str) -> OAuthReq<UserInfo> {
+        UserInfo::request_with(&TokenHttpClient::default(), userinfo_endpoint, access_token)
+    }
+
+    #[cfg(feature = "blocking")]
+    pub(crate) fn request_with(
+        http_client: &TokenHttpClient,
+        userinfo_endpoint: &str,
+        access_token: &str,
+    ) -> OAuthReq<UserInfo> {
+        let response = http_client
+            .blocking_client()
+            .get(userinfo_endpoint)
+            .bearer_auth(access_token)
+            .send()?
+            .with_graph_error()?;
+        Ok(response.json()?)
+    }
+
+    /// Get the claims of the user from a userinfo endpoint using an
+    /// access token for the openid scope.
+    pub async fn async_request(userinfo_endpoint: &str, access_token: &str) -> OAuthReq<UserInfo> {
+        UserInfo::async_request_with(&TokenHttpClient::default(), userinfo_endpoint, access_token)
+            .await
+    }
+
+    pub(crate) async fn async_request_with(
+        http_client: &TokenHttpClient,
+        userinfo_endpoint: &str,
+        access_token: &str,
+    ) -> OAuthReq<UserInfo> {
+        let response = http_client
+            .client()
+            .get(userinfo_endpoint)
+            .bearer_auth(access_token)
+            .send()
+            .await?
+            .with_graph_error()
+            .await?;
+        Ok(response.json().await?)
+    }
+}
@@ -1,6 +1,13 @@
 use crate::auth::OAuthReq;
+use crate::authority::Authority;
+use crate::discovery::jwtkeys::{JWTKeys, Keys};
+use crate::idtoken::IdTokenClaims;
 use crate::oautherror::OAuthError;
+use chrono::{Duration, Utc};
 use graph_error::GraphFailure;
+pub use graph_error::JwtValidationError;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::de::DeserializeOwned;
 use serde_json::Map;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -146,15 +153,14 @@ impl JsonWebToken {
     }
 }
 
-/// TODO(#4): JWT Validation - https://github.com/sreeise/rust-onedrive/issues/4
+/// JSON web token (JWT) parsing for RFC 7619
 ///
-/// JSON web token (JWT) verification for RFC 7619
+/// The parser decodes the header and claims of a JWT without verifying
+/// the signature. The parsing here is best effort to follow section 7.2 of
+/// RFC 7519: https://tools.ietf.org/html/rfc7519#section-7.2
 ///
-/// The JWT implementation does not implement full JWT verification.
-/// The validation here is best effort to follow section 7.2 of RFC 7519 for
-/// JWT validation: https://tools.ietf.org/html/rfc7519#section-7.2
-///
-/// Callers should not rely on this alone to verify JWTs
+/// Callers should not rely on this to verify JWTs. Use [JwtValidator]
+/// to verify the signature and claims of a token.
 pub struct JwtParser;
 
 impl JwtParser {
@@ -208,7 +214,8 @@ impl JwtParser {
         let payload = key_vec.get(1);
 
         if let Some(p) = payload {
-            let t = base64::decode(&**p)?;
+            let t = base64::decode_config(p.trim_end_matches('='), base64::URL_SAFE_NO_PAD)
+                .or_else(|_| base64::decode(&**p))?;
             let v_utf8 = std::str::from_utf8(&t)?;
             let v_owned = v_utf8.to_owned();
 
@@ -256,3 +263,439 @@ impl JwtParser {
         Ok(())
     }
 }
+
+/// Audience (aud) claim of a JWT which may be a single value or a list.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Audience {
+    pub fn contains(&self, audience: &str) -> bool {
+        match self {
+            Audience::Single(aud) => aud.eq(audience),
+            Audience::Multiple(aud) => aud.iter().any(|aud| aud.eq(audience)),
+        }
+    }
+}
+
+/// Registered and Microsoft identity platform claims of a validated JWT.
+/// Claims that do not have a field are kept in `additional`.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JwtClaims {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<Audience>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub oid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub azp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub appid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ver: Option<String>,
+    #[serde(flatten)]
+    pub additional: HashMap<String, Value>,
+}
+
+impl JwtClaims {
+    /// The app roles in the roles claim.
+    pub fn roles(&self) -> &[String] {
+        self.roles.as_deref().unwrap_or_default()
+    }
+
+    /// The application id of the client the token was issued to. This is the
+    /// azp claim of v2.0 tokens or the appid claim of v1.0 tokens.
+    pub fn app_id(&self) -> Option<&str> {
+        self.azp.as_deref().or(self.appid.as_deref())
+    }
+
+    /// Returns true if the token was issued to an application without a
+    /// signed in user. The idtyp optional claim is used when present,
+    /// otherwise a token without a scp claim is app-only.
+    pub fn is_app_only(&self) -> bool {
+        match self.additional.get("idtyp").and_then(Value::as_str) {
+            Some(idtyp) => idtyp.eq_ignore_ascii_case("app"),
+            None => self.scp.is_none(),
+        }
+    }
+}
+
+/// Validates the signature and claims of a JWT using the signing keys
+/// from [JWTKeys::discovery] or the jwks_uri of an OpenID configuration.
+///
+/// The signing key is picked using the kid (or x5t) header of the token.
+/// RS256, PS256 and ES256 signatures are supported. After the signature
+/// is verified the exp and nbf claims are checked, allowing for clock skew,
+/// followed by the issuer, audience and any tenant id or nonce set on the
+/// validator.
+///
+/// At least one issuer or authority and at least one audience must be set.
+/// Tokens are rejected with [JwtValidationError::NotConfigured] otherwise
+/// so that a token issued by any tenant or for any application is not
+/// accepted by mistake.
+///
+/// Issuers can contain the `{tenantid}` placeholder used by the multi-tenant
+/// issuer of the Microsoft identity platform. The placeholder is replaced
+/// by the tid claim of the token.
+///
+/// # Example
+/// ```rust,ignore
+/// # use graph_oauth::jwt::JwtValidator;
+/// # use graph_oauth::oauth::jwtkeys::JWTKeys;
+/// let keys = JWTKeys::discovery().unwrap();
+/// let mut validator = JwtValidator::new(keys);
+/// validator
+///     .issuer("https://login.microsoftonline.com/{tenantid}/v2.0")
+///     .audience("<CLIENT_ID>")
+///     .nonce("<NONCE>");
+///
+/// let claims = validator.validate("<ID_TOKEN>").unwrap();
+/// println!("{:#?}", claims.preferred_username);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct JwtValidator {
+    keys: JWTKeys,
+    algorithms: Vec<Algorithm>,
+    issuers: Vec<String>,
+    authorities: Vec<Authority>,
+    audiences: Vec<String>,
+    tenant_id: Option<String>,
+    nonce: Option<String>,
+    clock_skew: Duration,
+}
+
+impl JwtValidator {
+    /// The default clock skew allowed when checking exp and nbf.
+    pub const DEFAULT_CLOCK_SKEW: i64 = 300;
+
+    pub fn new(keys: JWTKeys) -> JwtValidator {
+        JwtValidator {
+            keys,
+            algorithms: vec![Algorithm::RS256, Algorithm::PS256, Algorithm::ES256],
+            issuers: Vec::new(),
+            authorities: Vec::new(),
+            audiences: Vec::new(),
+            tenant_id: None,
+            nonce: None,
+            clock_skew: Duration::seconds(JwtValidator::DEFAULT_CLOCK_SKEW),
+        }
+    }
+
+    /// Add an expected issuer. The token issuer must match one of the
+    /// expected issuers or authorities.
+    pub fn issuer(&mut self, issuer: &str) -> &mut Self {
+        self.issuers.push(issuer.to_string());
+        self
+    }
+
+    /// Add an expected authority. The token issuer must be valid for one
+    /// of the expected issuers or authorities. See
+    /// [Authority::is_valid_issuer] for the issuers of each authority.
+    ///
+    /// Tokens for a domain name tenant, such as `contoso.com`, are only
+    /// accepted if the [tenant id](JwtValidator::tenant_id) is also set.
+    pub fn authority(&mut self, authority: &Authority) -> &mut Self {
+        self.authorities.push(authority.clone());
+        self
+    }
+
+    /// Add an expected audience, usually the client id of the application.
+    /// The token audience must contain one of the expected audiences.
+    pub fn audience(&mut self, audience: &str) -> &mut Self {
+        self.audiences.push(audience.to_string());
+        self
+    }
+
+    /// The tenant id (tid) the token must be issued for.
+    pub fn tenant_id(&mut self, tenant_id: &str) -> &mut Self {
+        self.tenant_id = Some(tenant_id.to_string());
+        self
+    }
+
+    /// The nonce sent in the authorization request. The nonce of an
+    /// ID token must match.
+    pub fn nonce(&mut self, nonce: &str) -> &mut Self {
+        self.nonce = Some(nonce.to_string());
+        self
+    }
+
+    /// The clock skew allowed when checking the exp and nbf claims.
+    /// Defaults to 5 minutes.
+    pub fn clock_skew(&mut self, clock_skew: std::time::Duration) -> &mut Self {
+        self.clock_skew = Duration::seconds(clock_skew.as_secs() as i64);
+        self
+    }
+
+    /// Set the algorithms that are allowed. Only RS256, PS256 and ES256
+    /// are supported and other algorithms are ignored.
+    pub fn algorithms(&mut self, algorithms: &[Algorithm]) -> &mut Self {
+        self.algorithms = algorithms
+            .iter()
+            .filter(|alg| matches!(alg, Algorithm::RS256 | Algorithm::PS256 | Algorithm::ES256))
+            .copied()
+            .collect();
+        self
+    }
+
+    /// Validate the token and return the claims.
+    pub fn validate(&self, token: &str) -> Result<JwtClaims, JwtValidationError> {
+        self.validate_as(token)
+    }
+
+    /// Validate an ID token and return its claims. The audience must be
+    /// set to the client id so that tokens issued to other applications
+    /// are rejected.
+    ///
+    /// ID tokens have a nonce claim when the authorization request sent a
+    /// nonce. The nonce of the request must then be set on the validator,
+    /// otherwise the token is rejected with
+    /// [JwtValidationError::NotConfigured], so that a token from another
+    /// sign in cannot be replayed.
+    pub fn validate_id_token(&self, token: &str) -> Result<IdTokenClaims, JwtValidationError> {
+        let claims = self.verify(&self.keys, token)?;
+        if self.nonce.is_none() && claims.contains_key("nonce") {
+            return Err(JwtValidationError::NotConfigured("nonce"));
+        }
+        serde_json::from_value(Value::Object(claims))
+            .map_err(|err| JwtValidationError::InvalidClaims(err.to_string()))
+    }
+
+    /// Validate the token and deserialize the claims into `T`.
+    pub fn validate_as<T: DeserializeOwned>(&self, token: &str) -> Result<T, JwtValidationError> {
+        self.validate_with(&self.keys, token)
+    }
+
+    /// Validate the token using the given signing keys instead of the keys
+    /// of the validator and deserialize the claims into `T`. Use this with
+    /// keys from a [JwksCache](crate::oauth::jwkscache::JwksCache).
+    pub fn validate_with<T: DeserializeOwned>(
+        &self,
+        keys: &JWTKeys,
+        token: &str,
+    ) -> Result<T, JwtValidationError> {
+        let claims = self.verify(keys, token)?;
+        serde_json::from_value(Value::Object(claims))
+            .map_err(|err| JwtValidationError::InvalidClaims(err.to_string()))
+    }
+
+    fn verify(
+        &self,
+        keys: &JWTKeys,
+        token: &str,
+    ) -> Result<Map<String, Value>, JwtValidationError> {
+        let parts: Vec<&str> = token.split('.').collect();
+        if parts.len() != 3 {
+            return Err(JwtValidationError::Malformed(format!(
+                "expected 3 parts but found {}",
+                parts.len()
+            )));
+        }
+
+        let header = JwtValidator::decode(parts[0], "header")?;
+        let alg = header
+            .get("alg")
+            .and_then(Value::as_str)
+            .ok_or_else(|| JwtValidationError::Malformed("missing alg header".into()))?;
+        let algorithm = Algorithm::from_str(alg)
+            .ok()
+            .filter(|algorithm| self.algorithms.contains(algorithm))
+            .ok_or_else(|| JwtValidationError::UnsupportedAlgorithm(alg.to_string()))?;
+
+        let key = match (
+            header.get("kid").and_then(Value::as_str),
+            header.get("x5t").and_then(Value::as_str),
+        ) {
+            (Some(kid), _) => keys
+                .find(kid)
+                .ok_or_else(|| JwtValidationError::KeyNotFound(Some(kid.to_string())))?,
+            (None, Some(x5t)) => keys
+                .find_thumbprint(x5t)
+                .ok_or_else(|| JwtValidationError::KeyNotFound(Some(x5t.to_string())))?,
+            (None, None) => return Err(JwtValidationError::KeyNotFound(None)),
+        };
+
+        let signature = base64::decode_config(parts[2], base64::URL_SAFE_NO_PAD)
+            .map_err(|_| JwtValidationError::Malformed("signature is not base64url".into()))?;
+        let message_len = parts[0].len() + parts[1].len() + 1;
+        JwtValidator::verify_signature(
+            key,
+            algorithm,
+            &token.as_bytes()[..message_len],
+            &signature,
+        )?;
+
+        let claims = JwtValidator::decode(parts[1], "payload")?;
+        self.validate_claims(&claims)?;
+        Ok(claims)
+    }
+
+    fn decode(part: &str, name: &str) -> Result<Map<String, Value>, JwtValidationError> {
+        let bytes = base64::decode_config(part, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| JwtValidationError::Malformed(format!("{} is not base64url", name)))?;
+        serde_json::from_slice(&bytes)
+            .map_err(|_| JwtValidationError::Malformed(format!("{} is not a JSON object", name)))
+    }
+
+    fn verify_signature(
+        key: &Keys,
+        algorithm: Algorithm,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), JwtValidationError> {
+        let decode = |value: &Option<String>, name: &str| {
+            value
+                .as_ref()
+                .and_then(|value| base64::decode_config(value, base64::URL_SAFE_NO_PAD).ok())
+                .ok_or_else(|| {
+                    JwtValidationError::InvalidKey(format!("missing or invalid {}", name))
+                })
+        };
+
+        let verified = match algorithm {
+            Algorithm::RS256 | Algorithm::PS256 => {
+                if matches!(key.kty.as_deref(), Some(kty) if kty != "RSA") {
+                    return Err(JwtValidationError::InvalidKey(format!(
+                        "{:?} requires an RSA key",
+                        algorithm
+                    )));
+                }
+                let public_key = RsaPublicKeyComponents {
+                    n: decode(&key.n, "modulus (n)")?,
+                    e: decode(&key.e, "exponent (e)")?,
+                };
+                let params = match algorithm {
+                    Algorithm::PS256 => &signature::RSA_PSS_2048_8192_SHA256,
+                    _ => &signature::RSA_PKCS1_2048_8192_SHA256,
+                };
+                public_key.verify(params, message, signature)
+            }
+            Algorithm::ES256 => {
+                if key.kty.as_deref() != Some("EC") || key.crv.as_deref() != Some("P-256") {
+                    return Err(JwtValidationError::InvalidKey(
+                        "ES256 requires an EC key with the P-256 curve".into(),
+                    ));
+                }
+                let x = decode(&key.x, "x coordinate")?;
+                let y = decode(&key.y, "y coordinate")?;
+                if x.len() != 32 || y.len() != 32 {
+                    return Err(JwtValidationError::InvalidKey(
+                        "invalid P-256 coordinates".into(),
+                    ));
+                }
+                let mut point = vec![0x04];
+                point.extend_from_slice(&x);
+                point.extend_from_slice(&y);
+                UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point)
+                    .verify(message, signature)
+            }
+            _ => {
+                return Err(JwtValidationError::UnsupportedAlgorithm(format!(
+                    "{:?}",
+                    algorithm
+                )))
+            }
+        };
+        verified.map_err(|_| JwtValidationError::InvalidSignature)
+    }
+
+    fn validate_claims(&self, claims: &Map<String, Value>) -> Result<(), JwtValidationError> {
+        let now = Utc::now().timestamp();
+        let skew = self.clock_skew.num_seconds();
+
+        let exp = claims
+            .get("exp")
+            .and_then(Value::as_i64)
+            .ok_or(JwtValidationError::MissingClaim("exp"))?;
+        if now > exp + skew {
+            return Err(JwtValidationError::Expired(exp));
+        }
+        if let Some(nbf) = claims.get("nbf").and_then(Value::as_i64) {
+            if now + skew < nbf {
+                return Err(JwtValidationError::NotYetValid(nbf));
+            }
+        }
+
+        if self.issuers.is_empty() && self.authorities.is_empty() {
+            return Err(JwtValidationError::NotConfigured("issuer"));
+        }
+        if self.audiences.is_empty() {
+            return Err(JwtValidationError::NotConfigured("audience"));
+        }
+
+        let tid = claims.get("tid").and_then(Value::as_str);
+        let iss = claims
+            .get("iss")
+            .and_then(Value::as_str)
+            .ok_or(JwtValidationError::MissingClaim("iss"))?;
+        let valid = self.issuers.iter().any(|issuer| {
+            if issuer.contains("{tenantid}") {
+                matches!(tid, Some(tid) if issuer.replace("{tenantid}", tid).eq(iss))
+            } else {
+                issuer.eq(iss)
+            }
+        }) || self
+            .authorities
+            .iter()
+            .any(|authority| match self.tenant_id.as_ref() {
+                // The tenant id of a domain name is not known so the
+                // issuer of the expected tenant is used instead.
+                Some(tenant_id) if authority.is_domain_tenant() => {
+                    Authority::Tenant(tenant_id.clone()).is_valid_issuer(iss, tid)
+                }
+                _ => authority.is_valid_issuer(iss, tid),
+            });
+        if !valid {
+            return Err(JwtValidationError::InvalidIssuer(iss.to_string()));
+        }
+
+        let aud: Audience = claims
+            .get("aud")
+            .cloned()
+            .and_then(|aud| serde_json::from_value(aud).ok())
+            .ok_or(JwtValidationError::MissingClaim("aud"))?;
+        if !self.audiences.iter().any(|audience| aud.contains(audience)) {
+            return Err(JwtValidationError::InvalidAudience);
+        }
+
+        if let Some(tenant_id) = self.tenant_id.as_ref() {
+            let tid = tid.ok_or(JwtValidationError::MissingClaim("tid"))?;
+            if !tenant_id.eq(tid) {
+                return Err(JwtValidationError::InvalidTenant(tid.to_string()));
+            }
+        }
+
+        if let Some(expected) = self.nonce.as_ref() {
+            let nonce = claims
+                .get("nonce")
+                .and_then(Value::as_str)
+                .ok_or(JwtValidationError::MissingClaim("nonce"))?;
+            ring::constant_time::verify_slices_are_equal(expected.as_bytes(), nonce.as_bytes())
+                .map_err(|_| JwtValidationError::InvalidNonce)?;
+        }
+        Ok(())
+    }
+}
@@ -30,13 +30,34 @@
 //!     .logout_url("https://login.microsoftonline.com/common/oauth2/v2.0/logout")
 //!     .post_logout_redirect_uri("http://localhost:8000/redirect");
 //! ```
+//!
+//! The authorization, token and logout urls can also be set from an
+//! [Authority](crate::oauth::Authority):
+//! ```
+//! use graph_oauth::oauth::{Authority, OAuth};
+//! let mut oauth = OAuth::new();
+//! oauth
+//!     .client_id("<YOUR_CLIENT_ID>")
+//!     .authority(&Authority::Organizations)
+//!     .add_scope("files.read")
+//!     .redirect_uri("http://localhost:8000/redirect");
+//! ```
 //! Get the access code for the authorization code grant by sending the user to
 //! log in using their browser.
 //! ```rust,ignore
 //! # use graph_oauth::oauth::OAuth;
 //! # let mut oauth = OAuth::new();
 //! let mut request = oauth.build().authorization_code_grant();
-//! let _ = request.browser_authorization().open();
+//! let _ = request.browser_authorization()?.open();
+//! ```
+//!
+//! Opening the browser requires the `browser` feature, which is enabled
+//! by default. Web apps and servers redirect the user to the authorization
+//! url instead:
+//! ```rust,ignore
+//! # use graph_oauth::oauth::OAuth;
+//! # let mut oauth = OAuth::new();
+//! let url = oauth.build().authorization_code_grant().authorization_url()?;
 //! ```
 //!
 //! The access code will be appended to the url on redirect. Pass
@@ -56,6 +77,34 @@
 //! let access_token = request.access_token().send().unwrap();
 //! println!("{:#?}", access_token);
 //! ```
+//!
+//! # Async
+//! Every request has an async version, using `build_async()` for grants and
+//! methods prefixed with `async_` elsewhere:
+//! ```rust,ignore
+//! # use graph_oauth::oauth::OAuth;
+//! # let mut oauth = OAuth::new();
+//! let mut request = oauth.build_async().authorization_code_grant();
+//!
+//! let access_token = request.access_token().send().await.unwrap();
+//! ```
+//!
+//! The blocking requests use the blocking client of reqwest and require the
+//! `blocking` feature, which is enabled by default. Services that only use
+//! the async API can turn off the default features so that graph-oauth does
+//! not block the runtime or depend on reqwest's blocking client:
+//! ```toml
+//! graph-oauth = { version = "0.1", default-features = false }
+//! ```
+//!
+//! Token requests use a default client, created on the first request and
+//! shared by clones of the OAuth instance, unless one is set with
+//! [OAuth::http_client](crate::oauth::OAuth::http_client) or
+//! `OAuth::blocking_http_client`, such as a client with a proxy or
+//! timeouts or the client used for Graph requests. A
+//! [TokenHttpClient](crate::oauth::TokenHttpClient) shares the clients
+//! between OAuth, the token credentials and the discovery, userinfo and
+//! group requests.
 
 #[macro_use]
 extern crate serde;
@@ -63,24 +112,66 @@ extern crate serde;
 extern crate strum;
 
 mod accesstoken;
+mod adminconsent;
 mod auth;
+mod authority;
+mod bearerassertion;
+mod certificate;
+mod claimschallenge;
+mod devicecode;
 mod discovery;
+mod encryption;
 mod grants;
+mod httpclient;
 mod idtoken;
 pub mod jwt;
+mod loopback;
 mod oautherror;
+mod redirect;
+mod tokencache;
+mod tokencredential;
 
 pub mod oauth {
     pub use crate::accesstoken::AccessToken;
+    pub use crate::adminconsent::{AdminConsentGrant, AdminConsentRedirect, AdminConsentResponse};
+    pub use crate::auth::AuthorizationRequest;
     pub use crate::auth::GrantSelector;
     pub use crate::auth::OAuth;
     pub use crate::auth::OAuthCredential;
+    pub use crate::authority::{Authority, CONSUMERS_TENANT_ID};
+    pub use crate::bearerassertion::{AsyncBearerAssertionGrant, BearerAssertionGrant};
+    pub use crate::certificate::ClientCertificateCredential;
+    pub use crate::claimschallenge::ClaimsChallenge;
+    pub use crate::devicecode::{
+        AsyncDeviceCodeGrant, AsyncDeviceCodeRequest, DeviceCode, DeviceCodeError, DeviceCodeGrant,
+        DeviceCodeRequest,
+    };
     pub use crate::discovery::graphdiscovery;
+    pub use crate::discovery::jwkscache;
     pub use crate::discovery::jwtkeys;
     pub use crate::discovery::wellknown;
+    pub use crate::encryption::{
+        EncryptedData, EncryptedOAuth, EncryptionKey, KeyDerivation, DEFAULT_PBKDF2_ITERATIONS,
+        ENCRYPTION_VERSION, MAX_PBKDF2_ITERATIONS,
+    };
     pub use crate::grants::GrantRequest;
     pub use crate::grants::GrantType;
-    pub use crate::idtoken::IdToken;
+    pub use crate::httpclient::TokenHttpClient;
+    pub use crate::idtoken::{ClaimSource, IdToken, IdTokenClaims, UserInfo};
+    pub use crate::loopback::{LoopbackServer, DEFAULT_LOOPBACK_TIMEOUT};
     pub use crate::oautherror::OAuthError;
+    pub use crate::redirect::{
+        AuthorizationCodeResponse, AuthorizationErrorResponse, ImplicitResponse, RedirectResponse,
+        ResponseMode,
+    };
     pub use crate::strum::IntoEnumIterator;
+    pub use crate::tokencache::{
+        CachedToken, FileTokenCache, MemoryTokenCache, TokenCache, TokenCacheAccount, TokenCacheKey,
+    };
+    #[cfg(feature = "blocking")]
+    pub use crate::tokencredential::BlockingTokenCredential;
+    pub use crate::tokencredential::{
+        ChainedTokenCredential, EnvironmentCredential, ManagedIdentityCredential, TokenCredential,
+        WorkloadIdentityCredential, GRAPH_DEFAULT_SCOPE,
+    };
 }
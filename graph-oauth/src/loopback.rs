@@ -0,0 +1,193 @@
+use crate::auth::OAuthReq;
+use crate::oautherror::OAuthError;
+use graph_error::GraphFailure;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+use url::Url;
+
+/// The default amount of time to wait for the user to sign in.
+pub const DEFAULT_LOOPBACK_TIMEOUT: Duration = Duration::from_secs(300);
+
+const SIGN_IN_COMPLETE: &str = "Sign in complete. You can close this window.";
+const SIGN_IN_FAILED: &str = "Sign in failed. You can close this window.";
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Connections that do not send a request line in time are dropped so
+// they cannot hold up the redirect.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REQUEST_LINE: u64 = 8192;
+
+/// Local HTTP server that receives the redirect of an authorization request.
+///
+/// The server binds `127.0.0.1` on a port chosen by the operating system.
+/// Microsoft identity platform ignores the port of loopback redirect URIs,
+/// so the application only needs `http://127.0.0.1/redirect` (or the path
+/// given to [LoopbackServer::bind]) registered as a redirect URI.
+///
+/// Most callers should use
+/// [OAuth::loopback_sign_in](crate::oauth::OAuth::loopback_sign_in)
+/// which handles the whole authorization code flow.
+///
+/// # Example
+/// ```
+/// # use graph_oauth::oauth::LoopbackServer;
+/// let server = LoopbackServer::bind("/redirect").unwrap();
+/// assert!(server.redirect_uri().starts_with("http://127.0.0.1:"));
+/// ```
+#[derive(Debug)]
+pub struct LoopbackServer {
+    listener: TcpListener,
+    path: String,
+}
+
+impl LoopbackServer {
+    /// Bind the server on a free port of `127.0.0.1`.
+    pub fn bind(path: &str) -> OAuthReq<LoopbackServer> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let path = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{}", path)
+        };
+        Ok(LoopbackServer { listener, path })
+    }
+
+    /// The redirect URI to use in the authorization request.
+    pub fn redirect_uri(&self) -> String {
+        let port = self
+            .listener
+            .local_addr()
+            .map(|addr| addr.port())
+            .unwrap_or_default();
+        format!("http://127.0.0.1:{}{}", port, self.path)
+    }
+
+    /// Wait for the redirect and return its query parameters. Requests for
+    /// other paths, such as the browser asking for a favicon, are answered
+    /// with 404 and ignored.
+    ///
+    /// Returns an error of kind [ErrorKind::TimedOut] if there is no redirect
+    /// before the timeout.
+    pub fn wait_for_redirect(&self, timeout: Duration) -> OAuthReq<HashMap<String, String>> {
+        let deadline = Instant::now() + timeout;
+        self.listener.set_nonblocking(true)?;
+        loop {
+            if let Some(query) = self.poll_redirect(deadline)? {
+                return Ok(query);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Wait for the redirect and return its query parameters without
+    /// blocking the async runtime. See [LoopbackServer::wait_for_redirect].
+    pub async fn async_wait_for_redirect(
+        &self,
+        timeout: Duration,
+    ) -> OAuthReq<HashMap<String, String>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        self.listener.set_nonblocking(true)?;
+        let listener = tokio::net::TcpListener::from_std(self.listener.try_clone()?)?;
+        loop {
+            let (stream, _) = tokio::time::timeout_at(deadline, listener.accept())
+                .await
+                .map_err(|_| LoopbackServer::timed_out())??;
+            if let Some(query) = self.async_handle(stream).await {
+                return Ok(query);
+            }
+        }
+    }
+
+    // Handle a pending connection, if any. Returns None until the
+    // redirect is received.
+    fn poll_redirect(&self, deadline: Instant) -> OAuthReq<Option<HashMap<String, String>>> {
+        match self.listener.accept() {
+            Ok((stream, _)) => self.handle(stream),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                if Instant::now() >= deadline {
+                    return Err(LoopbackServer::timed_out());
+                }
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // Connections that fail or time out are dropped and do not end the
+    // wait for the redirect.
+    fn handle(&self, mut stream: TcpStream) -> OAuthReq<Option<HashMap<String, String>>> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+
+        let mut request_line = String::new();
+        if BufReader::new((&stream).take(MAX_REQUEST_LINE))
+            .read_line(&mut request_line)
+            .is_err()
+        {
+            return Ok(None);
+        }
+        let (query, response) = self.route(&request_line);
+        // The browser closing the connection early is not an error.
+        let _ = stream.write_all(response.as_bytes());
+        let _ = stream.flush();
+        Ok(query)
+    }
+
+    async fn async_handle(
+        &self,
+        mut stream: tokio::net::TcpStream,
+    ) -> Option<HashMap<String, String>> {
+        let mut request_line = String::new();
+        let read = {
+            let mut reader = tokio::io::BufReader::new((&mut stream).take(MAX_REQUEST_LINE));
+            tokio::time::timeout(CONNECTION_TIMEOUT, reader.read_line(&mut request_line)).await
+        };
+        if !matches!(read, Ok(Ok(_))) {
+            return None;
+        }
+        let (query, response) = self.route(&request_line);
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.flush().await;
+        query
+    }
+
+    // The query of a redirect to the path of the server and the response
+    // to send. Requests for other paths are answered with 404.
+    fn route(&self, request_line: &str) -> (Option<HashMap<String, String>>, String) {
+        let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+        let url = Url::parse("http://127.0.0.1")
+            .and_then(|base| base.join(target))
+            .ok();
+
+        match url {
+            Some(url) if url.path() == self.path => {
+                let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+                let body = if query.contains_key("error") {
+                    SIGN_IN_FAILED
+                } else {
+                    SIGN_IN_COMPLETE
+                };
+                (Some(query), LoopbackServer::response("200 OK", body))
+            }
+            _ => (None, LoopbackServer::response("404 Not Found", "")),
+        }
+    }
+
+    fn timed_out() -> GraphFailure {
+        OAuthError::error_kind(
+            ErrorKind::TimedOut,
+            "timed out waiting for the authorization redirect",
+        )
+    }
+
+    fn response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+}
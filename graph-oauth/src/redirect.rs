@@ -0,0 +1,237 @@
+use crate::accesstoken::AccessToken;
+use crate::auth::OAuthReq;
+use crate::idtoken::IdToken;
+use crate::oautherror::OAuthError;
+use std::collections::HashMap;
+use std::fmt;
+use url::{form_urlencoded, Url};
+
+/// How the authorization server returns the parameters of an
+/// authorization response to the redirect URI.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Hash,
+    Serialize,
+    Deserialize,
+    EnumIter,
+    EnumString,
+    AsRefStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ResponseMode {
+    /// Parameters are in the query string of the redirect URI.
+    Query,
+    /// Parameters are in the fragment of the redirect URI.
+    Fragment,
+    /// Parameters are form encoded in the body of a POST to the redirect URI.
+    FormPost,
+}
+
+/// Successful response of the authorization code flow. An id token is
+/// included when the `id_token` response type was also requested.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AuthorizationCodeResponse {
+    pub code: String,
+    pub id_token: Option<String>,
+    pub state: Option<String>,
+    pub session_state: Option<String>,
+}
+
+impl AuthorizationCodeResponse {
+    /// The id token of a hybrid flow response.
+    pub fn id_token(&self) -> Option<IdToken> {
+        let id_token = self.id_token.as_ref()?;
+        let mut token = IdToken::default();
+        token.id_token(id_token);
+        token.code(&self.code);
+        if let Some(state) = self.state.as_ref() {
+            token.state(state);
+        }
+        if let Some(session_state) = self.session_state.as_ref() {
+            token.session_state(session_state);
+        }
+        Some(token)
+    }
+}
+
+/// Tokens returned directly in the response of the implicit flow.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ImplicitResponse {
+    pub access_token: Option<String>,
+    pub token_type: Option<String>,
+    pub expires_in: Option<i64>,
+    pub scope: Option<String>,
+    pub id_token: Option<String>,
+    pub state: Option<String>,
+    pub session_state: Option<String>,
+}
+
+impl ImplicitResponse {
+    /// Convert the response to an access token. Returns None if the
+    /// response only has an id token.
+    pub fn access_token(&self) -> Option<AccessToken> {
+        let bearer = self.access_token.as_ref()?;
+        let mut access_token = AccessToken::new(
+            self.token_type.as_deref().unwrap_or("Bearer"),
+            self.expires_in.unwrap_or_default(),
+            self.scope.as_deref().unwrap_or_default(),
+            bearer,
+        );
+        if let Some(id_token) = self.id_token.as_ref() {
+            access_token.set_id_token(id_token);
+        }
+        if let Some(state) = self.state.as_ref() {
+            access_token.set_state(state);
+        }
+        Some(access_token)
+    }
+}
+
+/// Error returned to the redirect URI when authorization fails, such as
+/// when the user declines consent.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AuthorizationErrorResponse {
+    pub error: String,
+    pub error_description: Option<String>,
+    pub error_uri: Option<String>,
+    pub state: Option<String>,
+}
+
+impl fmt::Display for AuthorizationErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.error_description.as_ref() {
+            Some(description) => write!(f, "{} {}", self.error, description),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+impl std::error::Error for AuthorizationErrorResponse {}
+
+/// The parameters returned to the redirect URI after the user signs in.
+///
+/// Use [OAuth::parse_redirect](crate::oauth::OAuth::parse_redirect) to also
+/// validate the state of the response.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RedirectResponse {
+    Code(AuthorizationCodeResponse),
+    Implicit(ImplicitResponse),
+    Error(AuthorizationErrorResponse),
+}
+
+impl RedirectResponse {
+    /// Parse a redirect URL or a form_post body.
+    ///
+    /// For a URL the parameters are read from the part given by the
+    /// response mode. If no response mode is given the fragment is used
+    /// when present and the query otherwise. Anything that is not an
+    /// absolute URL is parsed as form encoded parameters.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::RedirectResponse;
+    /// let response = RedirectResponse::parse(
+    ///     "http://localhost:8000/redirect?code=M0ab92efe&state=12345",
+    ///     None,
+    /// )
+    /// .unwrap();
+    ///
+    /// match response {
+    ///     RedirectResponse::Code(response) => assert_eq!(response.code, "M0ab92efe"),
+    ///     _ => panic!(),
+    /// }
+    /// ```
+    pub fn parse(input: &str, response_mode: Option<ResponseMode>) -> OAuthReq<RedirectResponse> {
+        RedirectResponse::from_params(redirect_params(input, response_mode))
+    }
+
+    /// Create the response from the parameters of a redirect.
+    pub fn from_params(mut params: HashMap<String, String>) -> OAuthReq<RedirectResponse> {
+        let expires_in = match params.remove("expires_in") {
+            Some(expires_in) => Some(
+                expires_in
+                    .parse::<i64>()
+                    .map_err(|_| OAuthError::invalid("expires_in in authorization response"))?,
+            ),
+            None => None,
+        };
+        let state = params.remove("state");
+        let session_state = params.remove("session_state");
+        let id_token = params.remove("id_token");
+
+        if let Some(error) = params.remove("error") {
+            return Ok(RedirectResponse::Error(AuthorizationErrorResponse {
+                error,
+                error_description: params.remove("error_description"),
+                error_uri: params.remove("error_uri"),
+                state,
+            }));
+        }
+        if let Some(code) = params.remove("code") {
+            return Ok(RedirectResponse::Code(AuthorizationCodeResponse {
+                code,
+                id_token,
+                state,
+                session_state,
+            }));
+        }
+        let access_token = params.remove("access_token");
+        if access_token.is_none() && id_token.is_none() {
+            return OAuthError::invalid_data(
+                "authorization response does not have a code, token or error",
+            );
+        }
+        Ok(RedirectResponse::Implicit(ImplicitResponse {
+            access_token,
+            token_type: params.remove("token_type"),
+            expires_in,
+            scope: params.remove("scope"),
+            id_token,
+            state,
+            session_state,
+        }))
+    }
+
+    pub fn state(&self) -> Option<&str> {
+        match self {
+            RedirectResponse::Code(response) => response.state.as_deref(),
+            RedirectResponse::Implicit(response) => response.state.as_deref(),
+            RedirectResponse::Error(response) => response.state.as_deref(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, RedirectResponse::Error(_))
+    }
+}
+
+// The parameters of a redirect URL or a form_post body.
+pub(crate) fn redirect_params(
+    input: &str,
+    response_mode: Option<ResponseMode>,
+) -> HashMap<String, String> {
+    let input = input.trim();
+    let params = match Url::parse(input) {
+        Ok(url) => {
+            let query = url.query().unwrap_or_default().to_string();
+            let fragment = url.fragment().unwrap_or_default().to_string();
+            match response_mode {
+                Some(ResponseMode::Query) => query,
+                Some(ResponseMode::Fragment) => fragment,
+                _ if !fragment.is_empty() => fragment,
+                _ => query,
+            }
+        }
+        Err(_) => input.trim_start_matches(&['?', '#'][..]).to_string(),
+    };
+    form_urlencoded::parse(params.as_bytes())
+        .into_owned()
+        .collect()
+}
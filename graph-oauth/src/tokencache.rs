@@ -0,0 +1,425 @@
+use crate::accesstoken::AccessToken;
+use crate::auth::{OAuth, OAuthCredential};
+use crate::encryption::{EncryptedData, EncryptionKey};
+use crate::jwt::JwtParser;
+use graph_error::GraphResult;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Scopes that are not returned in the scope of a token response
+/// and are ignored when matching cached tokens.
+const RESERVED_SCOPES: [&str; 3] = ["offline_access", "openid", "profile"];
+
+/// The resource prefix that Microsoft Graph removes from the scopes
+/// returned in a token response.
+const GRAPH_RESOURCE: &str = "https://graph.microsoft.com/";
+
+/// Tokens that expire within this duration are not returned
+/// from the cache.
+const EXPIRATION_BUFFER: Duration = Duration::from_secs(300);
+
+pub(crate) fn normalize_scopes<T: AsRef<str>, I: IntoIterator<Item = T>>(
+    scopes: I,
+) -> BTreeSet<String> {
+    scopes
+        .into_iter()
+        .map(|scope| scope.as_ref().trim().to_lowercase())
+        .map(|scope| match scope.strip_prefix(GRAPH_RESOURCE) {
+            Some(scope) => scope.to_string(),
+            None => scope,
+        })
+        .filter(|scope| !scope.is_empty() && !RESERVED_SCOPES.contains(&scope.as_str()))
+        .collect()
+}
+
+/// The resources of the scopes, such as `https://storage.azure.com`.
+/// Scopes without a resource are for Microsoft Graph.
+pub(crate) fn scope_resources<T: AsRef<str>, I: IntoIterator<Item = T>>(
+    scopes: I,
+) -> BTreeSet<String> {
+    normalize_scopes(scopes)
+        .iter()
+        .map(|scope| match scope.rfind('/') {
+            Some(index) if scope.contains("://") => scope[..index].to_string(),
+            _ => GRAPH_RESOURCE.trim_end_matches('/').to_string(),
+        })
+        .collect()
+}
+
+/// Returns true if the access token is expired or expires in the next
+/// 5 minutes.
+pub(crate) fn expires_soon(access_token: &AccessToken) -> bool {
+    access_token.expires_within(EXPIRATION_BUFFER)
+}
+
+/// Decode the claims of a JWT without validating it. Tokens stored in the
+/// cache come directly from the token endpoint.
+fn decode_claims(token: &str) -> Option<Value> {
+    let claims: Map<String, Value> = JwtParser::parse(token)
+        .ok()?
+        .claims()?
+        .into_iter()
+        .map(|claim| (claim.key(), claim.value()))
+        .collect();
+    Some(Value::Object(claims))
+}
+
+/// The key of a cached token.
+///
+/// The authority is the token endpoint the token was requested from and the
+/// home account id is the object id (oid) and tenant id (tid) of the user
+/// joined with a `.`. Tokens that are not issued for a user, such as
+/// tokens from the client credentials grant, have an empty home account id.
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct TokenCacheKey {
+    authority: String,
+    client_id: String,
+    home_account_id: String,
+    scopes: BTreeSet<String>,
+}
+
+impl TokenCacheKey {
+    pub fn new<T: AsRef<str>, I: IntoIterator<Item = T>>(
+        authority: &str,
+        client_id: &str,
+        home_account_id: &str,
+        scopes: I,
+    ) -> TokenCacheKey {
+        TokenCacheKey {
+            authority: authority.to_string(),
+            client_id: client_id.to_string(),
+            home_account_id: home_account_id.to_string(),
+            scopes: normalize_scopes(scopes),
+        }
+    }
+
+    pub fn authority(&self) -> &str {
+        self.authority.as_str()
+    }
+
+    pub fn client_id(&self) -> &str {
+        self.client_id.as_str()
+    }
+
+    pub fn home_account_id(&self) -> &str {
+        self.home_account_id.as_str()
+    }
+
+    pub fn scopes(&self) -> &BTreeSet<String> {
+        &self.scopes
+    }
+
+    fn is_account(&self, authority: &str, client_id: &str, home_account_id: &str) -> bool {
+        self.authority.eq(authority)
+            && self.client_id.eq(client_id)
+            && self.home_account_id.eq(home_account_id)
+    }
+}
+
+/// An account that has tokens in a [TokenCache].
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct TokenCacheAccount {
+    pub home_account_id: String,
+    pub username: Option<String>,
+}
+
+/// An access token, and the refresh and ID tokens returned with it,
+/// stored in a [TokenCache].
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CachedToken {
+    key: TokenCacheKey,
+    username: Option<String>,
+    access_token: AccessToken,
+}
+
+impl CachedToken {
+    pub fn new(key: TokenCacheKey, access_token: AccessToken) -> CachedToken {
+        let username = access_token
+            .id_token()
+            .and_then(|id_token| decode_claims(&id_token))
+            .and_then(|claims| {
+                claims["preferred_username"]
+                    .as_str()
+                    .or_else(|| claims["upn"].as_str())
+                    .map(|username| username.to_string())
+            });
+        CachedToken {
+            key,
+            username,
+            access_token,
+        }
+    }
+
+    /// Create a cached token using the home account id from the claims of the
+    /// ID token, or the access token if there is no ID token, and the scope of
+    /// the token response.
+    pub fn from_token(authority: &str, client_id: &str, access_token: AccessToken) -> CachedToken {
+        let key = TokenCacheKey::new(
+            authority,
+            client_id,
+            CachedToken::home_account_id(&access_token).as_str(),
+            access_token
+                .scopes()
+                .map(|scope| scope.split(' ').map(|s| s.to_string()).collect::<Vec<_>>())
+                .unwrap_or_default(),
+        );
+        CachedToken::new(key, access_token)
+    }
+
+    /// The home account id (oid.tid) of the user a token was issued for.
+    pub fn home_account_id(access_token: &AccessToken) -> String {
+        let claims = access_token
+            .id_token()
+            .and_then(|id_token| decode_claims(&id_token))
+            .or_else(|| decode_claims(access_token.bearer_token()));
+        match claims {
+            Some(claims) => match (claims["oid"].as_str(), claims["tid"].as_str()) {
+                (Some(oid), Some(tid)) => format!("{}.{}", oid, tid),
+                (Some(oid), None) => oid.to_string(),
+                _ => String::new(),
+            },
+            None => String::new(),
+        }
+    }
+
+    pub fn key(&self) -> &TokenCacheKey {
+        &self.key
+    }
+
+    pub fn username(&self) -> Option<&String> {
+        self.username.as_ref()
+    }
+
+    pub fn access_token(&self) -> &AccessToken {
+        &self.access_token
+    }
+
+    /// Returns true if the access token is expired or expires in
+    /// the next 5 minutes.
+    pub fn is_expired(&self) -> bool {
+        expires_soon(&self.access_token)
+    }
+}
+
+/// Storage for access, refresh and ID tokens. Tokens are stored per
+/// authority, client id, account and set of scopes.
+///
+/// # Example
+/// ```rust,ignore
+/// # use graph_oauth::oauth::{GrantType, MemoryTokenCache, OAuth, TokenCache};
+/// let mut cache = MemoryTokenCache::new();
+/// let mut oauth = OAuth::new();
+/// // ... configure OAuth and sign in the user.
+/// let access_token = oauth.build().authorization_code_grant().access_token().send().unwrap();
+/// let cached = cache.insert(&oauth, access_token).unwrap();
+///
+/// // Later, get a token for the account without signing in again.
+/// let access_token = oauth
+///     .acquire_token_silent(&mut cache, GrantType::AuthorizationCode, cached.key().home_account_id())
+///     .unwrap();
+/// ```
+pub trait TokenCache {
+    /// Load all cached tokens.
+    fn load(&self) -> GraphResult<Vec<CachedToken>>;
+
+    /// Insert or replace a cached token.
+    fn save(&mut self, token: &CachedToken) -> GraphResult<()>;
+
+    /// Remove a cached token.
+    fn remove(&mut self, key: &TokenCacheKey) -> GraphResult<()>;
+
+    /// Cache a token returned from the token endpoint of OAuth. The
+    /// authority is the access token url of OAuth.
+    fn insert(&mut self, oauth: &OAuth, access_token: AccessToken) -> GraphResult<CachedToken> {
+        let authority = oauth.get_or_else(OAuthCredential::AccessTokenURL)?;
+        let client_id = oauth.get_or_else(OAuthCredential::ClientId)?;
+        let mut token = CachedToken::from_token(&authority, &client_id, access_token);
+        if token.key.scopes.is_empty() {
+            token.key.scopes = normalize_scopes(oauth.get_scopes());
+        }
+        self.save(&token)?;
+        Ok(token)
+    }
+
+    /// Find the unexpired access token for the account that has all of the
+    /// scopes and expires last.
+    fn find(
+        &self,
+        authority: &str,
+        client_id: &str,
+        home_account_id: &str,
+        scopes: &BTreeSet<String>,
+    ) -> GraphResult<Option<CachedToken>> {
+        let scopes = normalize_scopes(scopes);
+        Ok(self
+            .load()?
+            .into_iter()
+            .filter(|token| token.key.is_account(authority, client_id, home_account_id))
+            .filter(|token| scopes.is_subset(&token.key.scopes) && !token.is_expired())
+            .max_by_key(|token| token.access_token.timestamp()))
+    }
+
+    /// The most recent refresh token for the account.
+    fn refresh_token(
+        &self,
+        authority: &str,
+        client_id: &str,
+        home_account_id: &str,
+    ) -> GraphResult<Option<String>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .filter(|token| token.key.is_account(authority, client_id, home_account_id))
+            .filter_map(|token| {
+                let timestamp = token.access_token.timestamp();
+                token
+                    .access_token
+                    .refresh_token()
+                    .map(|refresh_token| (timestamp, refresh_token))
+            })
+            .max_by_key(|(timestamp, _)| *timestamp)
+            .map(|(_, refresh_token)| refresh_token))
+    }
+
+    /// The accounts that have cached tokens.
+    fn accounts(&self) -> GraphResult<Vec<TokenCacheAccount>> {
+        let accounts: BTreeSet<TokenCacheAccount> = self
+            .load()?
+            .into_iter()
+            .filter(|token| !token.key.home_account_id.is_empty())
+            .map(|token| TokenCacheAccount {
+                home_account_id: token.key.home_account_id,
+                username: token.username,
+            })
+            .collect();
+        Ok(accounts.into_iter().collect())
+    }
+
+    /// Remove all tokens for the account.
+    fn remove_account(&mut self, home_account_id: &str) -> GraphResult<()> {
+        for token in self.load()? {
+            if token.key.home_account_id.eq(home_account_id) {
+                self.remove(&token.key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Stores tokens in memory. Tokens are lost when the cache is dropped.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryTokenCache {
+    tokens: BTreeMap<TokenCacheKey, CachedToken>,
+}
+
+impl MemoryTokenCache {
+    pub fn new() -> MemoryTokenCache {
+        MemoryTokenCache::default()
+    }
+}
+
+impl TokenCache for MemoryTokenCache {
+    fn load(&self) -> GraphResult<Vec<CachedToken>> {
+        Ok(self.tokens.values().cloned().collect())
+    }
+
+    fn save(&mut self, token: &CachedToken) -> GraphResult<()> {
+        self.tokens.insert(token.key.clone(), token.clone());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &TokenCacheKey) -> GraphResult<()> {
+        self.tokens.remove(key);
+        Ok(())
+    }
+}
+
+/// Stores tokens in a file that is encrypted with an [EncryptionKey]. Only
+/// tokens are stored, client secrets and other credentials of OAuth are not
+/// written to the file. On unix the permissions of the file are set to read
+/// and write for the owner only.
+///
+/// # Example
+/// ```rust,ignore
+/// # use graph_oauth::oauth::{EncryptionKey, FileTokenCache};
+/// let key = EncryptionKey::from_passphrase("correct horse battery staple");
+/// let mut cache = FileTokenCache::new("./token_cache.json", key);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileTokenCache {
+    path: PathBuf,
+    key: EncryptionKey,
+}
+
+impl FileTokenCache {
+    pub fn new<P: AsRef<Path>>(path: P, key: EncryptionKey) -> FileTokenCache {
+        FileTokenCache {
+            path: path.as_ref().to_path_buf(),
+            key,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    fn read(&self) -> GraphResult<Vec<CachedToken>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = fs::read_to_string(&self.path)?;
+        if contents.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let encrypted: EncryptedData = serde_json::from_str(&contents)?;
+        encrypted.decrypt(&self.key)
+    }
+
+    fn write(&self, tokens: &[CachedToken]) -> GraphResult<()> {
+        let encrypted = EncryptedData::encrypt(&tokens, &self.key)?;
+        let mut options = OpenOptions::new();
+        options.create(true).write(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&self.path)?;
+        // The mode is only used when the file is created.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(serde_json::to_string_pretty(&encrypted)?.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+impl TokenCache for FileTokenCache {
+    fn load(&self) -> GraphResult<Vec<CachedToken>> {
+        self.read()
+    }
+
+    fn save(&mut self, token: &CachedToken) -> GraphResult<()> {
+        let mut tokens = self.read()?;
+        tokens.retain(|cached| cached.key.ne(&token.key));
+        tokens.push(token.clone());
+        self.write(&tokens)
+    }
+
+    fn remove(&mut self, key: &TokenCacheKey) -> GraphResult<()> {
+        let mut tokens = self.read()?;
+        let len = tokens.len();
+        tokens.retain(|cached| cached.key.ne(key));
+        if tokens.len() != len {
+            self.write(&tokens)?;
+        }
+        Ok(())
+    }
+}
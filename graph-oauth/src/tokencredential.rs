@@ -0,0 +1,710 @@
+use crate::accesstoken::AccessToken;
+use crate::auth::{OAuth, OAuthReq, CLIENT_ASSERTION_TYPE};
+use crate::authority::Authority;
+use crate::certificate::ClientCertificateCredential;
+use crate::httpclient::TokenHttpClient;
+use crate::oautherror::OAuthError;
+use async_trait::async_trait;
+#[cfg(feature = "blocking")]
+use graph_error::WithGraphError;
+use graph_error::{GraphFailure, WithGraphErrorAsync};
+use serde_aux::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// The scope of an application token for Microsoft Graph.
+pub const GRAPH_DEFAULT_SCOPE: &str = "https://graph.microsoft.com/.default";
+
+const IMDS_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+const IMDS_API_VERSION: &str = "2018-02-01";
+const APP_SERVICE_API_VERSION: &str = "2019-08-01";
+
+/// The managed identity endpoint is only reachable on Azure, so the
+/// connection fails fast elsewhere.
+const MANAGED_IDENTITY_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Tokens are requested again when they expire within this time.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+/// A source of application access tokens, for daemons and other
+/// workloads that run without a signed in user.
+#[async_trait]
+pub trait TokenCredential: Send + Sync {
+    /// Get an access token for the scopes, such as
+    /// [GRAPH_DEFAULT_SCOPE].
+    async fn async_get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken>;
+}
+
+/// A [TokenCredential] that can also get tokens with blocking requests.
+/// Requires the `blocking` feature.
+#[cfg(feature = "blocking")]
+pub trait BlockingTokenCredential: TokenCredential {
+    /// Get an access token for the scopes, such as
+    /// [GRAPH_DEFAULT_SCOPE].
+    fn get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken>;
+}
+
+fn env_var(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+fn token_url(authority_host: Option<&str>, tenant_id: &str) -> String {
+    match authority_host {
+        Some(host) => format!(
+            "{}/{}/oauth2/v2.0/token",
+            host.trim_end_matches('/'),
+            tenant_id
+        ),
+        None => Authority::tenant(tenant_id).token_url(),
+    }
+}
+
+fn client_credentials(client_id: &str, token_url: &str, scopes: &[&str]) -> OAuth {
+    let mut oauth = OAuth::new();
+    oauth.client_id(client_id).access_token_url(token_url);
+    for scope in scopes {
+        oauth.add_scope(scope);
+    }
+    oauth
+}
+
+enum ClientSecret {
+    Secret(String),
+    Certificate(ClientCertificateCredential),
+}
+
+/// Client credentials read from environment variables.
+///
+/// | Variable | Value |
+/// |---|---|
+/// | `AZURE_CLIENT_ID` | The application (client) id. |
+/// | `AZURE_TENANT_ID` | The tenant id. |
+/// | `AZURE_CLIENT_SECRET` | A client secret. |
+/// | `AZURE_CLIENT_CERTIFICATE_PATH` | A PEM file with the private key and certificate, used when there is no client secret. |
+/// | `AZURE_AUTHORITY_HOST` | Optional, defaults to `https://login.microsoftonline.com`. |
+pub struct EnvironmentCredential {
+    client_id: String,
+    tenant_id: String,
+    secret: ClientSecret,
+    authority_host: Option<String>,
+    http_client: TokenHttpClient,
+}
+
+impl EnvironmentCredential {
+    /// Read the credential from the environment. Returns None if the
+    /// client id, tenant id and a secret or certificate are not all set.
+    pub fn from_env() -> OAuthReq<Option<EnvironmentCredential>> {
+        EnvironmentCredential::from_vars(env_var)
+    }
+
+    /// Read the credential from variables given by `vars`.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_oauth::oauth::EnvironmentCredential;
+    /// let credential = EnvironmentCredential::from_vars(|key| match key {
+    ///     "AZURE_CLIENT_ID" => Some("<CLIENT_ID>".to_string()),
+    ///     "AZURE_TENANT_ID" => Some("<TENANT_ID>".to_string()),
+    ///     "AZURE_CLIENT_SECRET" => Some("<CLIENT_SECRET>".to_string()),
+    ///     _ => None,
+    /// })
+    /// .unwrap();
+    /// assert!(credential.is_some());
+    /// ```
+    pub fn from_vars<F>(vars: F) -> OAuthReq<Option<EnvironmentCredential>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let (client_id, tenant_id) = match (vars("AZURE_CLIENT_ID"), vars("AZURE_TENANT_ID")) {
+            (Some(client_id), Some(tenant_id)) => (client_id, tenant_id),
+            _ => return Ok(None),
+        };
+        let secret = if let Some(secret) = vars("AZURE_CLIENT_SECRET") {
+            ClientSecret::Secret(secret)
+        } else if let Some(path) = vars("AZURE_CLIENT_CERTIFICATE_PATH") {
+            let pem = std::fs::read(path)?;
+            ClientSecret::Certificate(ClientCertificateCredential::from_pem(
+                client_id.as_str(),
+                &pem,
+                &pem,
+            )?)
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(EnvironmentCredential {
+            client_id,
+            tenant_id,
+            secret,
+            authority_host: vars("AZURE_AUTHORITY_HOST"),
+            http_client: TokenHttpClient::default(),
+        }))
+    }
+
+    /// Send the token requests with a [TokenHttpClient] shared with
+    /// other requests, such as the Graph client.
+    pub fn share_http_client(&mut self, http_client: &TokenHttpClient) -> &mut Self {
+        self.http_client = http_client.clone();
+        self
+    }
+
+    fn oauth(&self, scopes: &[&str]) -> OAuth {
+        let token_url = token_url(self.authority_host.as_deref(), self.tenant_id.as_str());
+        let mut oauth = client_credentials(self.client_id.as_str(), token_url.as_str(), scopes);
+        oauth.share_http_client(&self.http_client);
+        match &self.secret {
+            ClientSecret::Secret(secret) => oauth.client_secret(secret.as_str()),
+            ClientSecret::Certificate(certificate) => oauth.client_certificate(certificate.clone()),
+        };
+        oauth
+    }
+}
+
+impl fmt::Debug for EnvironmentCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secret = match self.secret {
+            ClientSecret::Secret(_) => "client_secret",
+            ClientSecret::Certificate(_) => "certificate",
+        };
+        f.debug_struct("EnvironmentCredential")
+            .field("client_id", &"[REDACTED]")
+            .field("tenant_id", &self.tenant_id)
+            .field("secret", &secret)
+            .field("authority_host", &self.authority_host)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TokenCredential for EnvironmentCredential {
+    async fn async_get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        self.oauth(scopes)
+            .build_async()
+            .client_credentials()
+            .access_token()
+            .send()
+            .await
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingTokenCredential for EnvironmentCredential {
+    fn get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        self.oauth(scopes)
+            .build()
+            .client_credentials()
+            .access_token()
+            .send()
+    }
+}
+
+/// Exchanges a federated token from a file, such as the service account
+/// token of an Azure Kubernetes Service pod with workload identity, for an
+/// access token. The file is read for every request since the token is
+/// rotated.
+///
+/// [WorkloadIdentityCredential::from_env] reads the `AZURE_CLIENT_ID`,
+/// `AZURE_TENANT_ID`, `AZURE_FEDERATED_TOKEN_FILE` and optional
+/// `AZURE_AUTHORITY_HOST` environment variables.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WorkloadIdentityCredential {
+    client_id: String,
+    tenant_id: String,
+    token_file: PathBuf,
+    authority_host: Option<String>,
+    http_client: TokenHttpClient,
+}
+
+impl WorkloadIdentityCredential {
+    pub fn new<P: Into<PathBuf>>(
+        client_id: &str,
+        tenant_id: &str,
+        token_file: P,
+    ) -> WorkloadIdentityCredential {
+        WorkloadIdentityCredential {
+            client_id: client_id.to_string(),
+            tenant_id: tenant_id.to_string(),
+            token_file: token_file.into(),
+            authority_host: None,
+            http_client: TokenHttpClient::default(),
+        }
+    }
+
+    /// Read the credential from the environment. Returns None if the
+    /// variables are not set.
+    pub fn from_env() -> Option<WorkloadIdentityCredential> {
+        WorkloadIdentityCredential::from_vars(env_var)
+    }
+
+    /// Read the credential from variables given by `vars`.
+    pub fn from_vars<F>(vars: F) -> Option<WorkloadIdentityCredential>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut credential = WorkloadIdentityCredential::new(
+            vars("AZURE_CLIENT_ID")?.as_str(),
+            vars("AZURE_TENANT_ID")?.as_str(),
+            vars("AZURE_FEDERATED_TOKEN_FILE")?,
+        );
+        credential.authority_host = vars("AZURE_AUTHORITY_HOST");
+        Some(credential)
+    }
+
+    /// Set the host of the token endpoint. Defaults to
+    /// `https://login.microsoftonline.com`.
+    pub fn authority_host(&mut self, authority_host: &str) -> &mut Self {
+        self.authority_host = Some(authority_host.to_string());
+        self
+    }
+
+    /// Send the token requests with a [TokenHttpClient] shared with
+    /// other requests, such as the Graph client.
+    pub fn share_http_client(&mut self, http_client: &TokenHttpClient) -> &mut Self {
+        self.http_client = http_client.clone();
+        self
+    }
+
+    fn oauth(&self, scopes: &[&str]) -> OAuthReq<OAuth> {
+        let assertion = std::fs::read_to_string(&self.token_file)?;
+        let token_url = token_url(self.authority_host.as_deref(), self.tenant_id.as_str());
+        let mut oauth = client_credentials(self.client_id.as_str(), token_url.as_str(), scopes);
+        oauth
+            .client_assertion(assertion.trim())
+            .client_assertion_type(CLIENT_ASSERTION_TYPE)
+            .share_http_client(&self.http_client);
+        Ok(oauth)
+    }
+}
+
+#[async_trait]
+impl TokenCredential for WorkloadIdentityCredential {
+    async fn async_get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        self.oauth(scopes)?
+            .build_async()
+            .client_credentials()
+            .access_token()
+            .send()
+            .await
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingTokenCredential for WorkloadIdentityCredential {
+    fn get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        self.oauth(scopes)?
+            .build()
+            .client_credentials()
+            .access_token()
+            .send()
+    }
+}
+
+#[derive(Clone, Eq, PartialEq)]
+enum ManagedIdentityEndpoint {
+    Imds(String),
+    AppService { endpoint: String, header: String },
+}
+
+#[derive(Deserialize)]
+struct ManagedIdentityToken {
+    access_token: String,
+    #[serde(default)]
+    token_type: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    expires_in: Option<i64>,
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    expires_on: Option<i64>,
+}
+
+/// Gets tokens for the managed identity of an Azure resource from the
+/// Azure Instance Metadata Service (IMDS), or from the identity endpoint
+/// of App Service and Azure Functions when the `IDENTITY_ENDPOINT` and
+/// `IDENTITY_HEADER` environment variables are set.
+///
+/// Managed identity tokens are requested for a resource, so exactly one
+/// scope must be given, such as [GRAPH_DEFAULT_SCOPE].
+///
+/// # Example
+/// ```rust,ignore
+/// # use graph_oauth::oauth::{BlockingTokenCredential, ManagedIdentityCredential, GRAPH_DEFAULT_SCOPE};
+/// let mut credential = ManagedIdentityCredential::from_env();
+/// // Use a user-assigned managed identity.
+/// credential.client_id("<CLIENT_ID>");
+///
+/// let access_token = credential.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct ManagedIdentityCredential {
+    client_id: Option<String>,
+    endpoint: ManagedIdentityEndpoint,
+    http_client: TokenHttpClient,
+}
+
+impl ManagedIdentityCredential {
+    /// A credential for the system-assigned identity using IMDS.
+    pub fn new() -> ManagedIdentityCredential {
+        ManagedIdentityCredential {
+            client_id: None,
+            endpoint: ManagedIdentityEndpoint::Imds(IMDS_ENDPOINT.to_string()),
+            http_client: TokenHttpClient::with_connect_timeout(MANAGED_IDENTITY_CONNECT_TIMEOUT),
+        }
+    }
+
+    /// Use the App Service identity endpoint if it is set in the
+    /// environment and IMDS otherwise.
+    pub fn from_env() -> ManagedIdentityCredential {
+        ManagedIdentityCredential::from_vars(env_var)
+    }
+
+    /// Use the App Service identity endpoint if it is given by `vars`
+    /// and IMDS otherwise.
+    pub fn from_vars<F>(vars: F) -> ManagedIdentityCredential
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut credential = ManagedIdentityCredential::new();
+        if let (Some(endpoint), Some(header)) = (vars("IDENTITY_ENDPOINT"), vars("IDENTITY_HEADER"))
+        {
+            credential.endpoint = ManagedIdentityEndpoint::AppService { endpoint, header };
+        }
+        credential
+    }
+
+    /// The client id of a user-assigned managed identity.
+    pub fn client_id(&mut self, client_id: &str) -> &mut Self {
+        self.client_id = Some(client_id.to_string());
+        self
+    }
+
+    /// Use an IMDS compatible endpoint instead of the default
+    /// `http://169.254.169.254/metadata/identity/oauth2/token`.
+    pub fn imds_endpoint(&mut self, endpoint: &str) -> &mut Self {
+        self.endpoint = ManagedIdentityEndpoint::Imds(endpoint.to_string());
+        self
+    }
+
+    /// Send the token requests with a [TokenHttpClient] shared with
+    /// other requests, such as the Graph client. The default client has
+    /// a connect timeout of one second so that the unreachable endpoint
+    /// fails fast outside of Azure; a shared client should set its own
+    /// connect timeout.
+    pub fn share_http_client(&mut self, http_client: &TokenHttpClient) -> &mut Self {
+        self.http_client = http_client.clone();
+        self
+    }
+
+    /// The url and header of the token request for the resource of the scope.
+    fn request(&self, scopes: &[&str]) -> OAuthReq<(String, (&'static str, String))> {
+        let resource = match scopes {
+            [scope] => scope.trim_end_matches("/.default"),
+            _ => return OAuthError::invalid_data("managed identity requires exactly one scope"),
+        };
+        let (endpoint, api_version, header) = match &self.endpoint {
+            ManagedIdentityEndpoint::Imds(endpoint) => {
+                (endpoint, IMDS_API_VERSION, ("Metadata", "true".to_string()))
+            }
+            ManagedIdentityEndpoint::AppService { endpoint, header } => (
+                endpoint,
+                APP_SERVICE_API_VERSION,
+                ("X-IDENTITY-HEADER", header.clone()),
+            ),
+        };
+        let mut url = Url::parse(endpoint.as_str())
+            .map_err(|_| OAuthError::invalid("managed identity endpoint"))?;
+        url.query_pairs_mut()
+            .append_pair("api-version", api_version)
+            .append_pair("resource", resource);
+        if let Some(client_id) = self.client_id.as_ref() {
+            url.query_pairs_mut().append_pair("client_id", client_id);
+        }
+        Ok((url.to_string(), header))
+    }
+
+    fn access_token(token: ManagedIdentityToken, scope: &str) -> AccessToken {
+        let expires_in = token.expires_in.unwrap_or_else(|| {
+            token
+                .expires_on
+                .map(|expires_on| expires_on - chrono::Utc::now().timestamp())
+                .unwrap_or_default()
+        });
+        AccessToken::new(
+            token.token_type.as_deref().unwrap_or("Bearer"),
+            expires_in,
+            scope,
+            token.access_token.as_str(),
+        )
+    }
+}
+
+impl Default for ManagedIdentityCredential {
+    fn default() -> Self {
+        ManagedIdentityCredential::new()
+    }
+}
+
+impl fmt::Debug for ManagedIdentityCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let endpoint = match &self.endpoint {
+            ManagedIdentityEndpoint::Imds(endpoint) => endpoint,
+            ManagedIdentityEndpoint::AppService { endpoint, .. } => endpoint,
+        };
+        f.debug_struct("ManagedIdentityCredential")
+            .field("client_id", &self.client_id)
+            .field("endpoint", endpoint)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TokenCredential for ManagedIdentityCredential {
+    async fn async_get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        let (url, (name, value)) = self.request(scopes)?;
+        let token: ManagedIdentityToken = self
+            .http_client
+            .client()
+            .get(url.as_str())
+            .header(name, value)
+            .send()
+            .await?
+            .with_graph_error()
+            .await
+            .map_err(GraphFailure::from_oauth_response)?
+            .json()
+            .await?;
+        Ok(ManagedIdentityCredential::access_token(token, scopes[0]))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingTokenCredential for ManagedIdentityCredential {
+    fn get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        let (url, (name, value)) = self.request(scopes)?;
+        let token: ManagedIdentityToken = self
+            .http_client
+            .blocking_client()
+            .get(url.as_str())
+            .header(name, value)
+            .send()?
+            .with_graph_error()
+            .map_err(GraphFailure::from_oauth_response)?
+            .json()?;
+        Ok(ManagedIdentityCredential::access_token(token, scopes[0]))
+    }
+}
+
+/// Tries a list of credentials in order and returns the token of the
+/// first one that succeeds. Tokens are cached per set of scopes until
+/// five minutes before they expire.
+///
+/// [ChainedTokenCredential::from_env] tries, in order, an
+/// [EnvironmentCredential], a [ManagedIdentityCredential] and a
+/// [WorkloadIdentityCredential]. Credentials that are not configured in
+/// the environment are skipped.
+///
+/// For a different order, start from [ChainedTokenCredential::new] and add
+/// the credentials in the order they should be tried. Credentials added with
+/// `push_blocking` are used for blocking and async requests, and those added
+/// with [ChainedTokenCredential::push] only for async requests.
+///
+/// # Example
+/// ```rust,ignore
+/// # use graph_oauth::oauth::{BlockingTokenCredential, ChainedTokenCredential, GRAPH_DEFAULT_SCOPE};
+/// let credential = ChainedTokenCredential::from_env().unwrap();
+/// let access_token = credential.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+/// ```
+pub struct ChainedTokenCredential {
+    credentials: Vec<ChainedCredential>,
+    cache: Mutex<HashMap<String, (AccessToken, Instant)>>,
+}
+
+impl ChainedTokenCredential {
+    pub fn new() -> ChainedTokenCredential {
+        ChainedTokenCredential {
+            credentials: Vec::new(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The default chain for credentials from the environment.
+    pub fn from_env() -> OAuthReq<ChainedTokenCredential> {
+        ChainedTokenCredential::from_vars(env_var)
+    }
+
+    /// The default chain for credentials from variables given by `vars`.
+    pub fn from_vars<F>(vars: F) -> OAuthReq<ChainedTokenCredential>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut chain = ChainedTokenCredential::new();
+        if let Some(credential) = EnvironmentCredential::from_vars(&vars)? {
+            chain.push_default(credential);
+        }
+        chain.push_default(ManagedIdentityCredential::from_vars(&vars));
+        if let Some(credential) = WorkloadIdentityCredential::from_vars(&vars) {
+            chain.push_default(credential);
+        }
+        Ok(chain)
+    }
+
+    /// Add a credential to the end of the chain. The credential is
+    /// skipped by blocking requests.
+    pub fn push<T: TokenCredential + 'static>(&mut self, credential: T) -> &mut Self {
+        self.credentials
+            .push(ChainedCredential::Async(Box::new(credential)));
+        self
+    }
+
+    /// Add a credential to the end of the chain that is used for both
+    /// blocking and async requests.
+    #[cfg(feature = "blocking")]
+    pub fn push_blocking<T: BlockingTokenCredential + 'static>(
+        &mut self,
+        credential: T,
+    ) -> &mut Self {
+        self.credentials
+            .push(ChainedCredential::Blocking(Box::new(credential)));
+        self
+    }
+
+    #[cfg(feature = "blocking")]
+    fn push_default<T: BlockingTokenCredential + 'static>(&mut self, credential: T) {
+        self.push_blocking(credential);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    fn push_default<T: TokenCredential + 'static>(&mut self, credential: T) {
+        self.push(credential);
+    }
+
+    pub fn len(&self) -> usize {
+        self.credentials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.credentials.is_empty()
+    }
+
+    /// Remove all cached tokens.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (AccessToken, Instant)>> {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn cache_key(scopes: &[&str]) -> String {
+        let mut scopes: Vec<&str> = scopes.to_vec();
+        scopes.sort_unstable();
+        scopes.dedup();
+        scopes.join(" ")
+    }
+
+    fn cached(&self, key: &str) -> Option<AccessToken> {
+        match self.lock().get(key) {
+            Some((access_token, refresh_at)) if Instant::now() < *refresh_at => {
+                Some(access_token.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn store(&self, key: String, access_token: &AccessToken) {
+        let expires_in = Duration::from_secs(access_token.expires_in().max(0) as u64);
+        let refresh_at = Instant::now() + expires_in.checked_sub(EXPIRY_MARGIN).unwrap_or_default();
+        self.lock().insert(key, (access_token.clone(), refresh_at));
+    }
+
+    fn unavailable(errors: Vec<String>) -> GraphFailure {
+        OAuthError::error_kind(
+            ErrorKind::NotFound,
+            format!("no credential returned a token: {}", errors.join("; ")).as_str(),
+        )
+    }
+}
+
+impl Default for ChainedTokenCredential {
+    fn default() -> Self {
+        ChainedTokenCredential::new()
+    }
+}
+
+impl fmt::Debug for ChainedTokenCredential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChainedTokenCredential")
+            .field("credentials", &self.credentials.len())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TokenCredential for ChainedTokenCredential {
+    async fn async_get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        let key = ChainedTokenCredential::cache_key(scopes);
+        if let Some(access_token) = self.cached(&key) {
+            return Ok(access_token);
+        }
+        let mut errors = Vec::new();
+        for credential in self.credentials.iter() {
+            match credential.async_get_token(scopes).await {
+                Ok(access_token) => {
+                    self.store(key, &access_token);
+                    return Ok(access_token);
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        Err(ChainedTokenCredential::unavailable(errors))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingTokenCredential for ChainedTokenCredential {
+    fn get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        let key = ChainedTokenCredential::cache_key(scopes);
+        if let Some(access_token) = self.cached(&key) {
+            return Ok(access_token);
+        }
+        let mut errors = Vec::new();
+        for credential in self.credentials.iter() {
+            match credential.get_token(scopes) {
+                Ok(access_token) => {
+                    self.store(key, &access_token);
+                    return Ok(access_token);
+                }
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        Err(ChainedTokenCredential::unavailable(errors))
+    }
+}
+
+enum ChainedCredential {
+    Async(Box<dyn TokenCredential>),
+    #[cfg(feature = "blocking")]
+    Blocking(Box<dyn BlockingTokenCredential>),
+}
+
+impl ChainedCredential {
+    #[cfg(feature = "blocking")]
+    fn get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        match self {
+            ChainedCredential::Async(_) => {
+                OAuthError::invalid_data("the credential does not support blocking requests")
+            }
+            ChainedCredential::Blocking(credential) => credential.get_token(scopes),
+        }
+    }
+
+    async fn async_get_token(&self, scopes: &[&str]) -> OAuthReq<AccessToken> {
+        match self {
+            ChainedCredential::Async(credential) => credential.async_get_token(scopes).await,
+            #[cfg(feature = "blocking")]
+            ChainedCredential::Blocking(credential) => credential.async_get_token(scopes).await,
+        }
+    }
+}
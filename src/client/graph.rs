@@ -37,7 +37,10 @@ use graph_http::url::GraphUrl;
 use graph_http::{
     types::DeltaPhantom, AsyncHttpClient, BlockingHttpClient, IntoResponse, RequestClient,
 };
-use graph_oauth::oauth::{AccessToken, OAuth};
+use graph_oauth::oauth::{
+    AccessToken, BlockingTokenCredential, OAuth, TokenCredential, TokenHttpClient,
+    GRAPH_DEFAULT_SCOPE,
+};
 use reqwest::header::{HeaderValue, ACCEPT};
 use reqwest::Method;
 use std::convert::TryFrom;
@@ -159,6 +162,67 @@ impl<'a> GraphBlocking {
         Graph { request }
     }
 
+    /// Create a new client with an application token from a credential,
+    /// such as a chained credential for daemons.
+    ///
+    /// The client keeps using the token it was created with, and the token
+    /// expires after about an hour. Call [Graph::refresh_token] with the
+    /// same credential before sending requests to keep the token current.
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use graph_rs_sdk::client::Graph;
+    /// use graph_rs_sdk::oauth::ChainedTokenCredential;
+    ///
+    /// let credential = ChainedTokenCredential::from_env().unwrap();
+    /// let client = Graph::from_credential(&credential).unwrap();
+    ///
+    /// // Later, before sending more requests.
+    /// client.refresh_token(&credential).unwrap();
+    /// ```
+    pub fn from_credential<C: BlockingTokenCredential + ?Sized>(
+        credential: &C,
+    ) -> Result<GraphBlocking, GraphFailure> {
+        let access_token = credential.get_token(&[GRAPH_DEFAULT_SCOPE])?;
+        Ok(Graph::from(&access_token))
+    }
+
+    /// Set the access token used for requests to a token from the
+    /// credential. A
+    /// [ChainedTokenCredential](crate::oauth::ChainedTokenCredential) caches
+    /// tokens until five minutes before they expire, so a token is only
+    /// requested when the cached token is about to expire.
+    pub fn refresh_token<C: BlockingTokenCredential + ?Sized>(
+        &self,
+        credential: &C,
+    ) -> Result<(), GraphFailure> {
+        let access_token = credential.get_token(&[GRAPH_DEFAULT_SCOPE])?;
+        self.set_token(access_token.bearer_token());
+        Ok(())
+    }
+
+    /// The HTTP client of the Graph requests for use by the blocking
+    /// token requests, so that OAuth, the token credentials and the
+    /// discovery requests share the transport of the Graph client.
+    ///
+    /// # Example
+    /// ```
+    /// # use graph_rs_sdk::client::Graph;
+    /// # use graph_rs_sdk::oauth::{ManagedIdentityCredential, OAuth};
+    /// let client = Graph::new("ACCESS_TOKEN");
+    /// let http_client = client.token_http_client();
+    ///
+    /// let mut oauth = OAuth::new();
+    /// oauth.share_http_client(&http_client);
+    /// let mut credential = ManagedIdentityCredential::new();
+    /// credential.share_http_client(&http_client);
+    /// ```
+    pub fn token_http_client(&self) -> TokenHttpClient {
+        let mut http_client = TokenHttpClient::new();
+        http_client.blocking_http_client(self.request.inner_http_client());
+        http_client
+    }
+
     pub fn url_ref<F>(&self, f: F)
     where
         F: Fn(&GraphUrl),
@@ -236,6 +300,35 @@ impl<'a> GraphAsync {
         Graph { request }
     }
 
+    /// Create a new client with an application token from a credential.
+    /// See [Graph::from_credential]. The token is not refreshed, so call
+    /// [Graph::async_refresh_token] before sending requests.
+    pub async fn from_credential_async<C: TokenCredential + ?Sized>(
+        credential: &C,
+    ) -> Result<GraphAsync, GraphFailure> {
+        let access_token = credential.async_get_token(&[GRAPH_DEFAULT_SCOPE]).await?;
+        Ok(Graph::from(&access_token))
+    }
+
+    /// Set the access token used for requests to a token from the
+    /// credential. See [Graph::refresh_token].
+    pub async fn async_refresh_token<C: TokenCredential + ?Sized>(
+        &self,
+        credential: &C,
+    ) -> Result<(), GraphFailure> {
+        let access_token = credential.async_get_token(&[GRAPH_DEFAULT_SCOPE]).await?;
+        self.set_token(access_token.bearer_token());
+        Ok(())
+    }
+
+    /// The HTTP client of the Graph requests for use by the async token
+    /// requests. See `Graph::token_http_client` of the blocking client.
+    pub fn token_http_client(&self) -> TokenHttpClient {
+        let mut http_client = TokenHttpClient::new();
+        http_client.http_client(self.request.inner_http_client());
+        http_client
+    }
+
     pub fn url_ref<F>(&self, f: F)
     where
         F: Fn(&GraphUrl) + Sync,
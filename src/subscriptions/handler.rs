@@ -0,0 +1,365 @@
+#[cfg(feature = "notification-decryption")]
+use crate::subscriptions::DecryptionKey;
+use crate::subscriptions::{ChangeNotification, ChangeNotificationCollection};
+use graph_error::{GraphFailure, GraphResult};
+use graph_oauth::jwt::{Algorithm, JwtClaims, JwtValidator};
+use graph_oauth::oauth::jwtkeys::JWTKeys;
+#[cfg(feature = "notification-decryption")]
+use serde::de::DeserializeOwned;
+
+/// The query parameter Microsoft Graph uses when validating a
+/// notification url.
+pub const VALIDATION_TOKEN: &str = "validationToken";
+
+/// The app id of the Microsoft Graph change notification publisher. This is
+/// the authorized party (azp) of the validation tokens sent with rich notifications.
+pub const CHANGE_NOTIFICATION_PUBLISHER: &str = "0bf30f3b-4a52-48df-9a82-234910c4a086";
+
+/// Change notifications received in a single request, grouped by kind.
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
+pub struct Notifications {
+    /// Change notifications (created, updated, deleted) with a valid client state.
+    pub changes: Vec<ChangeNotification>,
+    /// Lifecycle notifications with a valid client state.
+    pub lifecycle: Vec<ChangeNotification>,
+    /// Notifications whose client state did not match the expected value.
+    /// These should not be trusted.
+    pub rejected: Vec<ChangeNotification>,
+    /// Validation tokens sent with rich notifications.
+    pub validation_tokens: Vec<String>,
+}
+
+impl Notifications {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.lifecycle.is_empty() && self.rejected.is_empty()
+    }
+}
+
+/// The result of handling a request to a notification url.
+///
+/// Use [status](NotificationResponse::status), [content_type](NotificationResponse::content_type)
+/// and [body](NotificationResponse::body) to build the http response
+/// for whichever server is receiving notifications.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum NotificationResponse {
+    /// A subscription validation request. The token must be returned
+    /// as plain text within 10 seconds.
+    Validation(String),
+    /// A notification request. Microsoft Graph expects a 202 Accepted
+    /// response within 3 seconds.
+    Notifications(Notifications),
+}
+
+impl NotificationResponse {
+    pub fn status(&self) -> u16 {
+        match self {
+            NotificationResponse::Validation(_) => 200,
+            NotificationResponse::Notifications(_) => 202,
+        }
+    }
+
+    pub fn content_type(&self) -> Option<&'static str> {
+        match self {
+            NotificationResponse::Validation(_) => Some("text/plain"),
+            NotificationResponse::Notifications(_) => None,
+        }
+    }
+
+    pub fn body(&self) -> &str {
+        match self {
+            NotificationResponse::Validation(token) => token.as_str(),
+            NotificationResponse::Notifications(_) => "",
+        }
+    }
+
+    pub fn notifications(&self) -> Option<&Notifications> {
+        match self {
+            NotificationResponse::Validation(_) => None,
+            NotificationResponse::Notifications(notifications) => Some(notifications),
+        }
+    }
+}
+
+/// Transport agnostic handler for subscription change notifications.
+///
+/// # Example
+/// ```
+/// # use graph_rs_sdk::subscriptions::NotificationHandler;
+/// let mut handler = NotificationHandler::new();
+/// handler.client_state("secretClientValue");
+///
+/// let response = handler
+///     .handle(Some("validationToken=Validation%3A%20Testing"), &[])
+///     .unwrap();
+/// assert_eq!(response.status(), 200);
+/// assert_eq!(response.body(), "Validation: Testing");
+/// ```
+#[derive(Default, Clone)]
+pub struct NotificationHandler {
+    client_state: Option<String>,
+    app_id: Option<String>,
+    tenant_id: Option<String>,
+    #[cfg(feature = "notification-decryption")]
+    decryption_key: Option<DecryptionKey>,
+}
+
+impl NotificationHandler {
+    pub fn new() -> NotificationHandler {
+        NotificationHandler::default()
+    }
+
+    /// Set the client state that was used when creating subscriptions.
+    /// Notifications with a different client state are rejected.
+    ///
+    /// The client state is required to parse notifications. Without it
+    /// every notification is rejected.
+    pub fn client_state(&mut self, client_state: &str) -> &mut Self {
+        self.client_state = Some(client_state.to_string());
+        self
+    }
+
+    /// Set the app id (client id) that created the subscriptions. This is
+    /// the expected audience of validation tokens.
+    pub fn app_id(&mut self, app_id: &str) -> &mut Self {
+        self.app_id = Some(app_id.to_string());
+        self
+    }
+
+    /// Set the tenant id that validation tokens must be issued for.
+    pub fn tenant_id(&mut self, tenant_id: &str) -> &mut Self {
+        self.tenant_id = Some(tenant_id.to_string());
+        self
+    }
+
+    /// Set the key used to decrypt resource data in rich notifications.
+    #[cfg(feature = "notification-decryption")]
+    pub fn decryption_key(&mut self, decryption_key: DecryptionKey) -> &mut Self {
+        self.decryption_key = Some(decryption_key);
+        self
+    }
+
+    /// Get the validation token from the query of a request url, if any.
+    pub fn validation_token(query: &str) -> Option<String> {
+        url::form_urlencoded::parse(query.trim_start_matches('?').as_bytes())
+            .find(|(key, _)| key == VALIDATION_TOKEN)
+            .map(|(_, value)| value.into_owned())
+    }
+
+    /// Handle a request sent to a notification url given the query
+    /// of the request url and the request body.
+    pub fn handle(&self, query: Option<&str>, body: &[u8]) -> GraphResult<NotificationResponse> {
+        if let Some(token) = query.and_then(NotificationHandler::validation_token) {
+            return Ok(NotificationResponse::Validation(token));
+        }
+        self.parse(body).map(NotificationResponse::Notifications)
+    }
+
+    /// Parse and verify the body of a notification request. Returns an
+    /// error if the client state of the handler is not set.
+    pub fn parse(&self, body: &[u8]) -> GraphResult<Notifications> {
+        if self.client_state.is_none() {
+            return Err(GraphFailure::invalid("client state"));
+        }
+        if body.is_empty() {
+            return Err(GraphFailure::invalid("notification request body"));
+        }
+        let collection: ChangeNotificationCollection = serde_json::from_slice(body)?;
+        Ok(self.verify(collection))
+    }
+
+    /// Verify the client state of each notification in a collection.
+    pub fn verify(&self, collection: ChangeNotificationCollection) -> Notifications {
+        let mut notifications = Notifications {
+            validation_tokens: collection.validation_tokens.unwrap_or_default(),
+            ..Default::default()
+        };
+
+        for notification in collection.value {
+            if !self.is_valid_client_state(&notification) {
+                notifications.rejected.push(notification);
+            } else if notification.is_lifecycle() {
+                notifications.lifecycle.push(notification);
+            } else {
+                notifications.changes.push(notification);
+            }
+        }
+        notifications
+    }
+
+    /// Decrypt the resource data of a rich notification. Returns `None` if
+    /// the notification does not include encrypted content.
+    #[cfg(feature = "notification-decryption")]
+    pub fn decrypt<T: DeserializeOwned>(
+        &self,
+        notification: &ChangeNotification,
+    ) -> GraphResult<Option<T>> {
+        let content = match notification.encrypted_content.as_ref() {
+            Some(content) => content,
+            None => return Ok(None),
+        };
+        let decryption_key = self
+            .decryption_key
+            .as_ref()
+            .ok_or_else(|| GraphFailure::invalid("decryption key"))?;
+        decryption_key.decrypt_json(content).map(Some)
+    }
+
+    /// Validate the validation tokens sent with rich notifications using
+    /// the signing keys published by Microsoft, such as the keys returned
+    /// from [JWTKeys::discovery].
+    ///
+    /// Each token must be signed with RS256 by one of the keys, have
+    /// the app id as the audience, the change notification publisher as the
+    /// authorized party (azp), a sts.windows.net issuer and must not be expired.
+    /// The issuer must be for the tenant id of the handler, if set, or
+    /// otherwise for the tenant in the tid claim of the token.
+    pub fn validate_tokens(&self, tokens: &[String], keys: &JWTKeys) -> GraphResult<()> {
+        if tokens.is_empty() {
+            return Err(GraphFailure::invalid("validation tokens"));
+        }
+        for token in tokens.iter() {
+            self.validate_token(token, keys)?;
+        }
+        Ok(())
+    }
+
+    fn validate_token(&self, token: &str, keys: &JWTKeys) -> GraphResult<()> {
+        let app_id = self
+            .app_id
+            .as_ref()
+            .ok_or_else(|| GraphFailure::invalid("app id"))?;
+
+        let mut validator = JwtValidator::new(keys.clone());
+        validator
+            .algorithms(&[Algorithm::RS256])
+            .audience(app_id)
+            .clock_skew(std::time::Duration::from_secs(0));
+        match self.tenant_id.as_ref() {
+            Some(tenant_id) => validator.issuer(&format!("https://sts.windows.net/{}/", tenant_id)),
+            None => validator.issuer("https://sts.windows.net/{tenantid}/"),
+        };
+
+        let claims: JwtClaims = validator.validate(token)?;
+        if claims.azp.as_deref() != Some(CHANGE_NOTIFICATION_PUBLISHER) {
+            return Err(GraphFailure::invalid("validation token authorized party"));
+        }
+        Ok(())
+    }
+
+    /// Returns true if the client state of the notification matches
+    /// the client state set on the handler. Returns false if the client
+    /// state of the handler is not set.
+    pub fn is_valid_client_state(&self, notification: &ChangeNotification) -> bool {
+        match (
+            self.client_state.as_ref(),
+            notification.client_state.as_ref(),
+        ) {
+            (Some(expected), Some(actual)) => {
+                ring::constant_time::verify_slices_are_equal(expected.as_bytes(), actual.as_bytes())
+                    .is_ok()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for NotificationHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("NotificationHandler");
+        debug
+            .field(
+                "client_state",
+                &self.client_state.as_ref().map(|_| "[REDACTED]"),
+            )
+            .field("app_id", &self.app_id)
+            .field("tenant_id", &self.tenant_id);
+        #[cfg(feature = "notification-decryption")]
+        debug.field("decryption_key", &self.decryption_key);
+        debug.finish()
+    }
+}
+
+#[cfg(feature = "webhook-server")]
+mod server {
+    use super::{NotificationHandler, NotificationResponse, Notifications};
+    use hyper::service::{make_service_fn, service_fn};
+    use hyper::{Body, Method, Request, Response, Server, StatusCode};
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    impl NotificationHandler {
+        /// Handle a hyper request. Notifications are passed to `on_notifications`
+        /// before the response is returned.
+        pub async fn handle_request<F>(
+            &self,
+            request: Request<Body>,
+            on_notifications: F,
+        ) -> Response<Body>
+        where
+            F: Fn(Notifications),
+        {
+            if request.method() != Method::POST {
+                return status_response(StatusCode::METHOD_NOT_ALLOWED);
+            }
+
+            let query = request.uri().query().map(|q| q.to_string());
+            let body = match hyper::body::to_bytes(request.into_body()).await {
+                Ok(body) => body,
+                Err(_) => return status_response(StatusCode::BAD_REQUEST),
+            };
+
+            match self.handle(query.as_deref(), &body) {
+                Ok(NotificationResponse::Notifications(notifications)) => {
+                    on_notifications(notifications);
+                    status_response(StatusCode::ACCEPTED)
+                }
+                Ok(response) => {
+                    let mut builder = Response::builder().status(response.status());
+                    if let Some(content_type) = response.content_type() {
+                        builder = builder.header(hyper::header::CONTENT_TYPE, content_type);
+                    }
+                    builder
+                        .body(Body::from(response.body().to_string()))
+                        .unwrap_or_else(|_| status_response(StatusCode::INTERNAL_SERVER_ERROR))
+                }
+                Err(_) => status_response(StatusCode::BAD_REQUEST),
+            }
+        }
+
+        /// Run a small hyper server that receives notifications on any path.
+        pub async fn serve<F>(self, addr: SocketAddr, on_notifications: F) -> hyper::Result<()>
+        where
+            F: Fn(Notifications) + Send + Sync + 'static,
+        {
+            let handler = Arc::new(self);
+            let on_notifications = Arc::new(on_notifications);
+
+            let make_service = make_service_fn(move |_| {
+                let handler = handler.clone();
+                let on_notifications = on_notifications.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| {
+                        let handler = handler.clone();
+                        let on_notifications = on_notifications.clone();
+                        async move {
+                            Ok::<_, Infallible>(
+                                handler
+                                    .handle_request(request, |n| on_notifications(n))
+                                    .await,
+                            )
+                        }
+                    }))
+                }
+            });
+
+            Server::bind(&addr).serve(make_service).await
+        }
+    }
+
+    fn status_response(status: StatusCode) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = status;
+        response
+    }
+}
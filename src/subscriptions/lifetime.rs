@@ -0,0 +1,108 @@
+use chrono::Duration;
+
+/// Resource types that support subscriptions, used to determine the
+/// maximum lifetime of a subscription.
+///
+/// # See
+/// [Maximum length of subscription per resource type](https://docs.microsoft.com/en-us/graph/api/resources/subscription?view=graph-rest-1.0#maximum-length-of-subscription-per-resource-type)
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SubscriptionResource {
+    /// Security alerts.
+    Alert,
+    /// Teams call records.
+    CallRecord,
+    /// Teams chat messages, channels, chats, teams and members.
+    Teams,
+    /// Group conversations.
+    GroupConversation,
+    /// OneDrive drive items.
+    DriveItem,
+    /// SharePoint lists.
+    List,
+    /// Outlook messages, events and contacts.
+    Outlook,
+    /// Users, groups and other directory resources.
+    Directory,
+    /// User presence.
+    Presence,
+    /// To do tasks.
+    TodoTask,
+    /// Print resources.
+    Print,
+    /// Any other resource.
+    Other,
+}
+
+impl SubscriptionResource {
+    /// Get the resource type from the resource path of a subscription
+    /// such as `me/mailFolders('Inbox')/messages`.
+    pub fn from_resource(resource: &str) -> SubscriptionResource {
+        let resource = resource.trim_start_matches('/').to_lowercase();
+        let segments: Vec<&str> = resource
+            .split(['/', '(', '?'])
+            .filter(|s| !s.is_empty())
+            .collect();
+        let has = |name: &str| segments.contains(&name);
+        let first = segments.first().copied().unwrap_or_default();
+
+        if first == "security" && has("alerts") {
+            SubscriptionResource::Alert
+        } else if first == "communications" && has("callrecords") {
+            SubscriptionResource::CallRecord
+        } else if has("presences") || has("presence") {
+            SubscriptionResource::Presence
+        } else if first == "print" {
+            SubscriptionResource::Print
+        } else if has("todo") {
+            SubscriptionResource::TodoTask
+        } else if has("teams")
+            || has("chats")
+            || has("channels")
+            || has("getallmessages")
+            || has("getallchannels")
+        {
+            SubscriptionResource::Teams
+        } else if first == "groups" && has("conversations") {
+            SubscriptionResource::GroupConversation
+        } else if has("messages") || has("events") || has("contacts") || has("mailfolders") {
+            SubscriptionResource::Outlook
+        } else if has("lists") {
+            SubscriptionResource::List
+        } else if has("drive") || has("drives") || has("root") {
+            SubscriptionResource::DriveItem
+        } else if first == "directory"
+            || (segments.len() == 1
+                && matches!(
+                    first,
+                    "users" | "groups" | "contacts" | "devices" | "serviceprincipals"
+                ))
+        {
+            SubscriptionResource::Directory
+        } else {
+            SubscriptionResource::Other
+        }
+    }
+
+    /// The maximum lifetime in minutes of a subscription for the resource type.
+    pub fn max_lifetime_minutes(&self) -> i64 {
+        match self {
+            SubscriptionResource::Alert => 43200,
+            SubscriptionResource::CallRecord => 4230,
+            SubscriptionResource::Teams => 60,
+            SubscriptionResource::GroupConversation => 4230,
+            SubscriptionResource::DriveItem => 42300,
+            SubscriptionResource::List => 42300,
+            SubscriptionResource::Outlook => 4230,
+            SubscriptionResource::Directory => 41760,
+            SubscriptionResource::Presence => 60,
+            SubscriptionResource::TodoTask => 4230,
+            SubscriptionResource::Print => 4230,
+            SubscriptionResource::Other => 4230,
+        }
+    }
+
+    /// The maximum lifetime of a subscription for the resource type.
+    pub fn max_lifetime(&self) -> Duration {
+        Duration::minutes(self.max_lifetime_minutes())
+    }
+}
@@ -0,0 +1,612 @@
+use crate::client::Graph;
+use crate::subscriptions::{
+    ChangeNotification, LifecycleEvent, SubscriptionResource, SubscriptionStore,
+};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use graph_error::{GraphFailure, GraphResult};
+use graph_http::{AsyncHttpClient, BlockingHttpClient, GraphResponse};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// How long [SubscriptionManager::run] waits before the first retry of
+/// subscriptions that failed to renew. The wait is doubled for each
+/// failed attempt up to [MAX_RETRY_INTERVAL].
+const RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// The longest [SubscriptionManager::run] waits between retries. This is
+/// kept below the default renewal margin so that a subscription can still
+/// be renewed before it expires once the failure clears.
+const MAX_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// The subscription to create. Mirrors the properties of a subscription
+/// resource that are set by the caller.
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SubscriptionSpec {
+    pub resource: String,
+    #[serde(rename = "changeType")]
+    pub change_type: String,
+    #[serde(rename = "notificationUrl")]
+    pub notification_url: String,
+    #[serde(rename = "lifecycleNotificationUrl")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lifecycle_notification_url: Option<String>,
+    #[serde(rename = "clientState")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_state: Option<String>,
+    #[serde(rename = "includeResourceData")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_resource_data: Option<bool>,
+    #[serde(rename = "encryptionCertificate")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption_certificate: Option<String>,
+    #[serde(rename = "encryptionCertificateId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption_certificate_id: Option<String>,
+}
+
+impl SubscriptionSpec {
+    pub fn new(resource: &str, change_type: &str, notification_url: &str) -> SubscriptionSpec {
+        SubscriptionSpec {
+            resource: resource.into(),
+            change_type: change_type.into(),
+            notification_url: notification_url.into(),
+            ..Default::default()
+        }
+    }
+
+    /// The resource type of the subscription.
+    pub fn resource_type(&self) -> SubscriptionResource {
+        SubscriptionResource::from_resource(&self.resource)
+    }
+}
+
+/// A subscription tracked by a [SubscriptionManager].
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ManagedSubscription {
+    pub id: String,
+    pub spec: SubscriptionSpec,
+    pub expiration_date_time: DateTime<Utc>,
+}
+
+impl ManagedSubscription {
+    /// Returns true if the subscription expires within the given duration.
+    pub fn expires_within(&self, duration: Duration) -> bool {
+        self.expiration_date_time - Utc::now() <= duration
+    }
+
+    /// Returns true if the subscription has expired.
+    pub fn is_expired(&self) -> bool {
+        self.expiration_date_time <= Utc::now()
+    }
+}
+
+/// Events emitted by a [SubscriptionManager].
+#[derive(Debug)]
+pub enum SubscriptionEvent {
+    /// A subscription was created.
+    Created(ManagedSubscription),
+    /// A subscription was renewed.
+    Renewed(ManagedSubscription),
+    /// A subscription no longer existed when renewing and was recreated.
+    Recreated {
+        previous_id: String,
+        subscription: ManagedSubscription,
+    },
+    /// A subscription was deleted.
+    Deleted(String),
+    /// Renewing or recreating a subscription failed.
+    RenewalFailed { id: String, error: String },
+    /// A missed lifecycle notification was received. Resources for the
+    /// subscription should be resynchronized.
+    Missed(String),
+}
+
+/// Creates, renews and deletes subscriptions, tracking the expiration
+/// of each subscription in a [SubscriptionStore].
+///
+/// Subscriptions are renewed when they expire within the renewal margin
+/// (default 10 minutes), and recreated when renewal returns 404 Not Found.
+/// New expiration times use the maximum lifetime of the resource type
+/// unless a shorter lifetime is set.
+///
+/// # Example
+/// ```rust,ignore
+/// use graph_rs_sdk::prelude::*;
+/// use graph_rs_sdk::subscriptions::*;
+///
+/// let client = Graph::new("ACCESS_TOKEN");
+/// let mut manager = SubscriptionManager::new(client, MemorySubscriptionStore::new());
+/// let events = manager.events();
+///
+/// let spec = SubscriptionSpec::new(
+///     "me/mailFolders('Inbox')/messages",
+///     "created,updated",
+///     "https://webhook.azurewebsites.net/api/send/myNotifyClient",
+/// );
+/// manager.subscribe(spec)?;
+/// manager.renew_due()?;
+/// ```
+pub struct SubscriptionManager<Client, Store> {
+    client: Graph<Client>,
+    store: Store,
+    renewal_margin: Duration,
+    lifetime: Option<Duration>,
+    sender: Option<Sender<SubscriptionEvent>>,
+}
+
+impl<Client, Store> SubscriptionManager<Client, Store>
+where
+    Client: graph_http::RequestClient,
+    Store: SubscriptionStore,
+{
+    pub fn new(client: Graph<Client>, store: Store) -> SubscriptionManager<Client, Store> {
+        SubscriptionManager {
+            client,
+            store,
+            renewal_margin: Duration::minutes(10),
+            lifetime: None,
+            sender: None,
+        }
+    }
+
+    /// Set how long before expiration a subscription is renewed.
+    pub fn renewal_margin(&mut self, margin: Duration) -> &mut Self {
+        self.renewal_margin = margin;
+        self
+    }
+
+    /// Set the lifetime used when creating or renewing subscriptions. The
+    /// maximum lifetime of the resource type is used if the lifetime is longer.
+    pub fn lifetime(&mut self, lifetime: Duration) -> &mut Self {
+        self.lifetime = Some(lifetime);
+        self
+    }
+
+    /// Receive events for subscriptions. Calling this again replaces
+    /// the previous receiver.
+    pub fn events(&mut self) -> Receiver<SubscriptionEvent> {
+        let (sender, receiver) = channel();
+        self.sender = Some(sender);
+        receiver
+    }
+
+    pub fn client(&self) -> &Graph<Client> {
+        &self.client
+    }
+
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// All subscriptions in the store.
+    pub fn subscriptions(&self) -> GraphResult<Vec<ManagedSubscription>> {
+        self.store.load()
+    }
+
+    /// Subscriptions that expire within the renewal margin.
+    pub fn due(&self) -> GraphResult<Vec<ManagedSubscription>> {
+        Ok(self
+            .store
+            .load()?
+            .into_iter()
+            .filter(|s| s.expires_within(self.renewal_margin))
+            .collect())
+    }
+
+    /// The time when the next subscription should be renewed.
+    pub fn next_renewal(&self) -> GraphResult<Option<DateTime<Utc>>> {
+        Ok(self
+            .store
+            .load()?
+            .iter()
+            .map(|s| s.expiration_date_time - self.renewal_margin)
+            .min())
+    }
+
+    fn expiration(&self, spec: &SubscriptionSpec) -> DateTime<Utc> {
+        let max_lifetime = spec.resource_type().max_lifetime();
+        let lifetime = match self.lifetime {
+            Some(lifetime) if lifetime < max_lifetime => lifetime,
+            _ => max_lifetime,
+        };
+        Utc::now() + lifetime
+    }
+
+    fn create_body(&self, spec: &SubscriptionSpec) -> GraphResult<serde_json::Value> {
+        let mut body = serde_json::to_value(spec)?;
+        body["expirationDateTime"] = serde_json::Value::String(
+            self.expiration(spec)
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+        );
+        Ok(body)
+    }
+
+    fn created(
+        &self,
+        spec: SubscriptionSpec,
+        response: GraphResponse<serde_json::Value>,
+    ) -> GraphResult<ManagedSubscription> {
+        let body = response.body();
+        let id = body["id"]
+            .as_str()
+            .ok_or_else(|| GraphFailure::invalid("subscription id"))?;
+        Ok(ManagedSubscription {
+            id: id.to_string(),
+            spec,
+            expiration_date_time: expiration_date_time(body)?,
+        })
+    }
+
+    fn renewed(
+        &mut self,
+        mut subscription: ManagedSubscription,
+        response: GraphResponse<serde_json::Value>,
+    ) -> GraphResult<GraphResult<ManagedSubscription>> {
+        subscription.expiration_date_time = match expiration_date_time(response.body()) {
+            Ok(expiration_date_time) => expiration_date_time,
+            Err(e) => return Ok(Err(self.renewal_failed(&subscription.id, e))),
+        };
+        self.store.save(&subscription)?;
+        self.emit(SubscriptionEvent::Renewed(subscription.clone()));
+        Ok(Ok(subscription))
+    }
+
+    fn recreated(
+        &mut self,
+        previous_id: String,
+        result: GraphResult<ManagedSubscription>,
+    ) -> GraphResult<GraphResult<ManagedSubscription>> {
+        let subscription = match result {
+            Ok(subscription) => subscription,
+            Err(e) => return Ok(Err(self.renewal_failed(&previous_id, e))),
+        };
+        self.store.remove(&previous_id)?;
+        self.store.save(&subscription)?;
+        self.emit(SubscriptionEvent::Recreated {
+            previous_id,
+            subscription: subscription.clone(),
+        });
+        Ok(Ok(subscription))
+    }
+
+    fn renew_body(&self, spec: &SubscriptionSpec) -> serde_json::Value {
+        serde_json::json!({
+            "expirationDateTime": self.expiration(spec).to_rfc3339_opts(SecondsFormat::Secs, true)
+        })
+    }
+
+    fn find(&self, id: &str) -> GraphResult<ManagedSubscription> {
+        self.store
+            .load()?
+            .into_iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| GraphFailure::not_found(&format!("subscription {}", id)))
+    }
+
+    fn emit(&self, event: SubscriptionEvent) {
+        if let Some(sender) = self.sender.as_ref() {
+            let _ = sender.send(event);
+        }
+    }
+
+    fn renewal_failed(&self, id: &str, error: GraphFailure) -> GraphFailure {
+        self.emit(SubscriptionEvent::RenewalFailed {
+            id: id.to_string(),
+            error: error.to_string(),
+        });
+        error
+    }
+}
+
+/// Exponential backoff between the renewal attempts of
+/// [SubscriptionManager::run].
+#[derive(Default)]
+struct RetryBackoff {
+    attempts: u32,
+}
+
+impl RetryBackoff {
+    /// The time to wait after a retryable failure. A Retry-After
+    /// header of the response is used when present.
+    fn next(&mut self, error: &GraphFailure) -> std::time::Duration {
+        let backoff = RETRY_INTERVAL * 2u32.pow(self.attempts.min(8));
+        self.attempts += 1;
+        error
+            .retry_after()
+            .unwrap_or(backoff)
+            .min(MAX_RETRY_INTERVAL)
+    }
+
+    fn reset(&mut self) {
+        self.attempts = 0;
+    }
+}
+
+fn expiration_date_time(body: &serde_json::Value) -> GraphResult<DateTime<Utc>> {
+    body["expirationDateTime"]
+        .as_str()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|expiration_date_time| expiration_date_time.with_timezone(&Utc))
+        .ok_or_else(|| GraphFailure::invalid("subscription expirationDateTime"))
+}
+
+fn is_not_found(error: &GraphFailure) -> bool {
+    match error {
+        GraphFailure::GraphError(e) => e.code == StatusCode::NOT_FOUND,
+        _ => false,
+    }
+}
+
+impl<Store: SubscriptionStore> SubscriptionManager<BlockingHttpClient, Store> {
+    /// Create a subscription and track it in the store.
+    pub fn subscribe(&mut self, spec: SubscriptionSpec) -> GraphResult<ManagedSubscription> {
+        let subscription = self.create(spec)?;
+        self.store.save(&subscription)?;
+        self.emit(SubscriptionEvent::Created(subscription.clone()));
+        Ok(subscription)
+    }
+
+    fn create(&self, spec: SubscriptionSpec) -> GraphResult<ManagedSubscription> {
+        let body = self.create_body(&spec)?;
+        let response = self
+            .client
+            .v1()
+            .subscriptions()
+            .create_subscription(&body)
+            .send()?;
+        self.created(spec, response)
+    }
+
+    /// Renew a subscription. If the subscription no longer exists it
+    /// is recreated.
+    pub fn renew(&mut self, id: &str) -> GraphResult<ManagedSubscription> {
+        self.try_renew(id)?
+    }
+
+    // Errors of the store are returned in the outer result. Failed
+    // renewals are reported as RenewalFailed and returned in the inner result.
+    fn try_renew(&mut self, id: &str) -> GraphResult<GraphResult<ManagedSubscription>> {
+        let subscription = self.find(id)?;
+        let body = self.renew_body(&subscription.spec);
+        let result = self
+            .client
+            .v1()
+            .subscriptions()
+            .update_subscription(id, &body)
+            .send();
+
+        match result {
+            Ok(response) => self.renewed(subscription, response),
+            Err(e) if is_not_found(&e) => self.try_recreate(subscription),
+            Err(e) => Ok(Err(self.renewal_failed(id, e))),
+        }
+    }
+
+    fn try_recreate(
+        &mut self,
+        subscription: ManagedSubscription,
+    ) -> GraphResult<GraphResult<ManagedSubscription>> {
+        let result = self.create(subscription.spec);
+        self.recreated(subscription.id, result)
+    }
+
+    /// Renew all subscriptions that expire within the renewal margin.
+    /// Failures are reported as [SubscriptionEvent::RenewalFailed] and do
+    /// not stop other subscriptions from being renewed. Returns an error
+    /// only if the store fails.
+    pub fn renew_due(&mut self) -> GraphResult<Vec<ManagedSubscription>> {
+        let mut renewed = Vec::new();
+        for subscription in self.due()? {
+            if let Ok(subscription) = self.try_renew(&subscription.id)? {
+                renewed.push(subscription);
+            }
+        }
+        Ok(renewed)
+    }
+
+    /// Delete a subscription and stop tracking it.
+    pub fn unsubscribe(&mut self, id: &str) -> GraphResult<()> {
+        let result = self
+            .client
+            .v1()
+            .subscriptions()
+            .delete_subscription(id)
+            .send();
+        match result {
+            Ok(_) => {}
+            Err(e) if is_not_found(&e) => {}
+            Err(e) => return Err(e),
+        }
+        self.store.remove(id)?;
+        self.emit(SubscriptionEvent::Deleted(id.to_string()));
+        Ok(())
+    }
+
+    /// React to a lifecycle notification. Subscriptions are renewed when
+    /// reauthorization is required and recreated when removed.
+    pub fn handle_lifecycle(&mut self, notification: &ChangeNotification) -> GraphResult<()> {
+        let id = notification.subscription_id.as_str();
+        match notification.lifecycle_event {
+            Some(LifecycleEvent::ReauthorizationRequired) => self.renew(id).map(|_| ()),
+            Some(LifecycleEvent::SubscriptionRemoved) => {
+                let subscription = self.find(id)?;
+                self.try_recreate(subscription)?.map(|_| ())
+            }
+            Some(LifecycleEvent::Missed) => {
+                self.emit(SubscriptionEvent::Missed(id.to_string()));
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Renew subscriptions as they become due. Returns when there are
+    /// no subscriptions left in the store.
+    ///
+    /// Renewals that fail with a retryable error, such as a timeout or
+    /// 503 Service Unavailable, are retried with exponential backoff.
+    /// Subscriptions that cannot be renewed by retrying, such as after
+    /// 403 Forbidden or an invalid notification url, are reported as
+    /// [SubscriptionEvent::RenewalFailed] and removed from the store.
+    /// Returns an error only if the store fails.
+    pub fn run(&mut self) -> GraphResult<()> {
+        let mut backoff = RetryBackoff::default();
+        while let Some(next) = self.next_renewal()? {
+            if let Ok(wait) = (next - Utc::now()).to_std() {
+                std::thread::sleep(wait);
+            }
+            let mut failure = None;
+            for subscription in self.due()? {
+                match self.try_renew(&subscription.id)? {
+                    Ok(_) => {}
+                    Err(e) if e.is_retryable() => failure = Some(e),
+                    Err(_) => self.store.remove(&subscription.id)?,
+                }
+            }
+            match failure {
+                Some(e) => std::thread::sleep(backoff.next(&e)),
+                None => backoff.reset(),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<Store: SubscriptionStore> SubscriptionManager<AsyncHttpClient, Store> {
+    /// Create a subscription and track it in the store.
+    pub async fn subscribe(&mut self, spec: SubscriptionSpec) -> GraphResult<ManagedSubscription> {
+        let subscription = self.create(spec).await?;
+        self.store.save(&subscription)?;
+        self.emit(SubscriptionEvent::Created(subscription.clone()));
+        Ok(subscription)
+    }
+
+    async fn create(&self, spec: SubscriptionSpec) -> GraphResult<ManagedSubscription> {
+        let body = self.create_body(&spec)?;
+        let response = self
+            .client
+            .v1()
+            .subscriptions()
+            .create_subscription(&body)
+            .send()
+            .await?;
+        self.created(spec, response)
+    }
+
+    /// Renew a subscription. If the subscription no longer exists it
+    /// is recreated.
+    pub async fn renew(&mut self, id: &str) -> GraphResult<ManagedSubscription> {
+        self.try_renew(id).await?
+    }
+
+    // Errors of the store are returned in the outer result. Failed
+    // renewals are reported as RenewalFailed and returned in the inner result.
+    async fn try_renew(&mut self, id: &str) -> GraphResult<GraphResult<ManagedSubscription>> {
+        let subscription = self.find(id)?;
+        let body = self.renew_body(&subscription.spec);
+        let result = self
+            .client
+            .v1()
+            .subscriptions()
+            .update_subscription(id, &body)
+            .send()
+            .await;
+
+        match result {
+            Ok(response) => self.renewed(subscription, response),
+            Err(e) if is_not_found(&e) => self.try_recreate(subscription).await,
+            Err(e) => Ok(Err(self.renewal_failed(id, e))),
+        }
+    }
+
+    async fn try_recreate(
+        &mut self,
+        subscription: ManagedSubscription,
+    ) -> GraphResult<GraphResult<ManagedSubscription>> {
+        let result = self.create(subscription.spec).await;
+        self.recreated(subscription.id, result)
+    }
+
+    /// Renew all subscriptions that expire within the renewal margin.
+    /// Failures are reported as [SubscriptionEvent::RenewalFailed] and do
+    /// not stop other subscriptions from being renewed. Returns an error
+    /// only if the store fails.
+    pub async fn renew_due(&mut self) -> GraphResult<Vec<ManagedSubscription>> {
+        let mut renewed = Vec::new();
+        for subscription in self.due()? {
+            if let Ok(subscription) = self.try_renew(&subscription.id).await? {
+                renewed.push(subscription);
+            }
+        }
+        Ok(renewed)
+    }
+
+    /// Delete a subscription and stop tracking it.
+    pub async fn unsubscribe(&mut self, id: &str) -> GraphResult<()> {
+        let result = self
+            .client
+            .v1()
+            .subscriptions()
+            .delete_subscription(id)
+            .send()
+            .await;
+        match result {
+            Ok(_) => {}
+            Err(e) if is_not_found(&e) => {}
+            Err(e) => return Err(e),
+        }
+        self.store.remove(id)?;
+        self.emit(SubscriptionEvent::Deleted(id.to_string()));
+        Ok(())
+    }
+
+    /// React to a lifecycle notification. Subscriptions are renewed when
+    /// reauthorization is required and recreated when removed.
+    pub async fn handle_lifecycle(&mut self, notification: &ChangeNotification) -> GraphResult<()> {
+        let id = notification.subscription_id.as_str();
+        match notification.lifecycle_event {
+            Some(LifecycleEvent::ReauthorizationRequired) => self.renew(id).await.map(|_| ()),
+            Some(LifecycleEvent::SubscriptionRemoved) => {
+                let subscription = self.find(id)?;
+                self.try_recreate(subscription).await?.map(|_| ())
+            }
+            Some(LifecycleEvent::Missed) => {
+                self.emit(SubscriptionEvent::Missed(id.to_string()));
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Renew subscriptions as they become due. Returns when there are
+    /// no subscriptions left in the store.
+    ///
+    /// Renewals that fail with a retryable error, such as a timeout or
+    /// 503 Service Unavailable, are retried with exponential backoff.
+    /// Subscriptions that cannot be renewed by retrying, such as after
+    /// 403 Forbidden or an invalid notification url, are reported as
+    /// [SubscriptionEvent::RenewalFailed] and removed from the store.
+    /// Returns an error only if the store fails.
+    pub async fn run(&mut self) -> GraphResult<()> {
+        let mut backoff = RetryBackoff::default();
+        while let Some(next) = self.next_renewal()? {
+            if let Ok(wait) = (next - Utc::now()).to_std() {
+                tokio::time::sleep(wait).await;
+            }
+            let mut failure = None;
+            for subscription in self.due()? {
+                match self.try_renew(&subscription.id).await? {
+                    Ok(_) => {}
+                    Err(e) if e.is_retryable() => failure = Some(e),
+                    Err(_) => self.store.remove(&subscription.id)?,
+                }
+            }
+            match failure {
+                Some(e) => tokio::time::sleep(backoff.next(&e)).await,
+                None => backoff.reset(),
+            }
+        }
+        Ok(())
+    }
+}
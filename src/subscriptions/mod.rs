@@ -1,3 +1,17 @@
+#[cfg(feature = "notification-decryption")]
+mod encryption;
+mod handler;
+mod lifetime;
+mod manager;
+mod notification;
 mod request;
+mod store;
 
+#[cfg(feature = "notification-decryption")]
+pub use encryption::*;
+pub use handler::*;
+pub use lifetime::*;
+pub use manager::*;
+pub use notification::*;
 pub use request::*;
+pub use store::*;
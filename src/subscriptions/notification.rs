@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The type of change that caused a notification to be sent.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeType {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl AsRef<str> for ChangeType {
+    fn as_ref(&self) -> &str {
+        match self {
+            ChangeType::Created => "created",
+            ChangeType::Updated => "updated",
+            ChangeType::Deleted => "deleted",
+        }
+    }
+}
+
+/// Lifecycle events sent to the lifecycleNotificationUrl of a subscription.
+///
+/// # See
+/// [Reduce missing subscriptions and change notifications](https://docs.microsoft.com/en-us/graph/webhooks-lifecycle)
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LifecycleEvent {
+    /// The access token used by the subscription is about to expire or
+    /// the subscription needs to be reauthorized.
+    ReauthorizationRequired,
+    /// Change notifications may have been missed and the resource
+    /// should be resynchronized.
+    Missed,
+    /// The subscription has been removed and must be recreated.
+    SubscriptionRemoved,
+}
+
+impl AsRef<str> for LifecycleEvent {
+    fn as_ref(&self) -> &str {
+        match self {
+            LifecycleEvent::ReauthorizationRequired => "reauthorizationRequired",
+            LifecycleEvent::Missed => "missed",
+            LifecycleEvent::SubscriptionRemoved => "subscriptionRemoved",
+        }
+    }
+}
+
+/// Properties of the resource that changed.
+#[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ResourceData {
+    #[serde(rename = "@odata.type")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub odata_type: Option<String>,
+    #[serde(rename = "@odata.id")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub odata_id: Option<String>,
+    #[serde(rename = "@odata.etag")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub odata_etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+/// Encrypted resource data included in rich change notifications.
+#[derive(Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedContent {
+    pub data: String,
+    #[serde(rename = "dataSignature")]
+    pub data_signature: String,
+    #[serde(rename = "dataKey")]
+    pub data_key: String,
+    #[serde(rename = "encryptionCertificateId")]
+    pub encryption_certificate_id: String,
+    #[serde(rename = "encryptionCertificateThumbprint")]
+    pub encryption_certificate_thumbprint: String,
+}
+
+impl fmt::Debug for EncryptedContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedContent")
+            .field("data", &"[REDACTED]")
+            .field("data_signature", &self.data_signature)
+            .field("data_key", &"[REDACTED]")
+            .field("encryption_certificate_id", &self.encryption_certificate_id)
+            .field(
+                "encryption_certificate_thumbprint",
+                &self.encryption_certificate_thumbprint,
+            )
+            .finish()
+    }
+}
+
+/// A single change or lifecycle notification.
+///
+/// Lifecycle notifications use the same resource type as change
+/// notifications but set the `lifecycleEvent` property and omit
+/// the change type and resource data.
+#[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChangeNotification {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "subscriptionId")]
+    pub subscription_id: String,
+    #[serde(rename = "subscriptionExpirationDateTime")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscription_expiration_date_time: Option<String>,
+    #[serde(rename = "clientState")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_state: Option<String>,
+    #[serde(rename = "changeType")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_type: Option<ChangeType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource: Option<String>,
+    #[serde(rename = "tenantId")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,
+    #[serde(rename = "resourceData")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_data: Option<ResourceData>,
+    #[serde(rename = "encryptedContent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encrypted_content: Option<EncryptedContent>,
+    #[serde(rename = "lifecycleEvent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lifecycle_event: Option<LifecycleEvent>,
+}
+
+impl ChangeNotification {
+    /// Returns true if this is a lifecycle notification.
+    pub fn is_lifecycle(&self) -> bool {
+        self.lifecycle_event.is_some()
+    }
+}
+
+/// The body of a POST request sent by Microsoft Graph to a
+/// notification url.
+#[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ChangeNotificationCollection {
+    pub value: Vec<ChangeNotification>,
+    #[serde(rename = "validationTokens")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation_tokens: Option<Vec<String>>,
+}
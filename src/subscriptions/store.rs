@@ -0,0 +1,108 @@
+use crate::subscriptions::ManagedSubscription;
+use graph_error::GraphResult;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Storage for subscriptions tracked by a [SubscriptionManager](crate::subscriptions::SubscriptionManager).
+pub trait SubscriptionStore {
+    /// Load all stored subscriptions.
+    fn load(&self) -> GraphResult<Vec<ManagedSubscription>>;
+
+    /// Insert or update a subscription.
+    fn save(&mut self, subscription: &ManagedSubscription) -> GraphResult<()>;
+
+    /// Remove a subscription by id.
+    fn remove(&mut self, id: &str) -> GraphResult<()>;
+}
+
+/// Stores subscriptions in memory. Subscriptions are lost when
+/// the store is dropped.
+#[derive(Debug, Default, Clone)]
+pub struct MemorySubscriptionStore {
+    subscriptions: BTreeMap<String, ManagedSubscription>,
+}
+
+impl MemorySubscriptionStore {
+    pub fn new() -> MemorySubscriptionStore {
+        MemorySubscriptionStore::default()
+    }
+}
+
+impl SubscriptionStore for MemorySubscriptionStore {
+    fn load(&self) -> GraphResult<Vec<ManagedSubscription>> {
+        Ok(self.subscriptions.values().cloned().collect())
+    }
+
+    fn save(&mut self, subscription: &ManagedSubscription) -> GraphResult<()> {
+        self.subscriptions
+            .insert(subscription.id.clone(), subscription.clone());
+        Ok(())
+    }
+
+    fn remove(&mut self, id: &str) -> GraphResult<()> {
+        self.subscriptions.remove(id);
+        Ok(())
+    }
+}
+
+/// Stores subscriptions as JSON in a file.
+#[derive(Debug, Clone)]
+pub struct FileSubscriptionStore {
+    path: PathBuf,
+}
+
+impl FileSubscriptionStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> FileSubscriptionStore {
+        FileSubscriptionStore {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    fn read(&self) -> GraphResult<BTreeMap<String, ManagedSubscription>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let contents = fs::read_to_string(&self.path)?;
+        if contents.trim().is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    fn write(&self, subscriptions: &BTreeMap<String, ManagedSubscription>) -> GraphResult<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        file.write_all(serde_json::to_string_pretty(subscriptions)?.as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+}
+
+impl SubscriptionStore for FileSubscriptionStore {
+    fn load(&self) -> GraphResult<Vec<ManagedSubscription>> {
+        Ok(self.read()?.into_values().collect())
+    }
+
+    fn save(&mut self, subscription: &ManagedSubscription) -> GraphResult<()> {
+        let mut subscriptions = self.read()?;
+        subscriptions.insert(subscription.id.clone(), subscription.clone());
+        self.write(&subscriptions)
+    }
+
+    fn remove(&mut self, id: &str) -> GraphResult<()> {
+        let mut subscriptions = self.read()?;
+        if subscriptions.remove(id).is_some() {
+            self.write(&subscriptions)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use ring::rand::SystemRandom;
+use ring::signature::{self, EcdsaKeyPair, KeyPair, RsaKeyPair};
+use std::fs;
+
+/// Base64 url encode a json value without padding, such as the header or
+/// claims of a JWT.
+pub fn encode(value: &serde_json::Value) -> String {
+    base64::encode_config(serde_json::to_vec(value).unwrap(), base64::URL_SAFE_NO_PAD)
+}
+
+fn rsa_key() -> RsaKeyPair {
+    let pem = fs::read_to_string("./test_files/oauth/client_certificate_key.pem").unwrap();
+    let body: String = pem
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    RsaKeyPair::from_pkcs8(&base64::decode(body).unwrap()).unwrap()
+}
+
+/// Sign a JWT with the test keys of `./test_files/oauth/jwks.json`. ES256
+/// tokens are signed with the EC key and RS256 and PS256 tokens with the
+/// RSA key.
+///
+/// # Example
+/// ```rust,ignore
+/// let token = sign("RS256", "rsa-kid", &serde_json::json!({ "aud": "client_id" }));
+/// ```
+pub fn sign(alg: &str, kid: &str, claims: &serde_json::Value) -> String {
+    let header = serde_json::json!({ "typ": "JWT", "alg": alg, "kid": kid });
+    let message = format!("{}.{}", encode(&header), encode(claims));
+    let rng = SystemRandom::new();
+    let signature = match alg {
+        "ES256" => {
+            let key = EcdsaKeyPair::from_pkcs8(
+                &signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+                &fs::read("./test_files/oauth/jwt_ec_key.der").unwrap(),
+            )
+            .unwrap();
+            assert_eq!(key.public_key().as_ref().len(), 65);
+            key.sign(&rng, message.as_bytes())
+                .unwrap()
+                .as_ref()
+                .to_vec()
+        }
+        _ => {
+            let key = rsa_key();
+            let padding: &dyn signature::RsaEncoding = match alg {
+                "PS256" => &signature::RSA_PSS_SHA256,
+                _ => &signature::RSA_PKCS1_SHA256,
+            };
+            let mut signature = vec![0; key.public_modulus_len()];
+            key.sign(padding, &rng, message.as_bytes(), &mut signature)
+                .unwrap();
+            signature
+        }
+    };
+    format!(
+        "{}.{}",
+        message,
+        base64::encode_config(signature, base64::URL_SAFE_NO_PAD)
+    )
+}
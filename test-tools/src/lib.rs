@@ -8,9 +8,11 @@ extern crate lazy_static;
 pub mod common;
 pub mod drive;
 mod files;
+pub mod jwt;
 pub mod oauth;
 pub mod oauthrequest;
 pub mod support;
+pub mod tokenserver;
 
 pub use files::FileUtils;
 
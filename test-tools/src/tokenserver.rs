@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+/// Parse a form urlencoded body, such as the body of a token request.
+pub fn form(body: &str) -> HashMap<String, String> {
+    url::form_urlencoded::parse(body.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// Serve a response for each status and body, in order, to a new
+/// connection. Returns the url of the server and a handle that returns
+/// the requests converted with `map` when joined.
+pub fn serve<T, F>(responses: Vec<(u16, String)>, map: F) -> (String, thread::JoinHandle<T>)
+where
+    T: Send + 'static,
+    F: FnOnce(Vec<MockRequest>) -> T + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let requests = responses
+            .into_iter()
+            .map(|(status, body)| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let request = MockTokenServer::read(&mut stream);
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+                request
+            })
+            .collect();
+        map(requests)
+    });
+    (url, handle)
+}
+
+/// A request received by a [MockTokenServer].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MockRequest {
+    /// The request line and headers.
+    pub head: String,
+    pub body: String,
+}
+
+impl MockRequest {
+    /// The form urlencoded parameters of the body.
+    pub fn form(&self) -> HashMap<String, String> {
+        form(self.body.as_str())
+    }
+
+    /// The query of the request target.
+    pub fn query(&self) -> HashMap<String, String> {
+        let target = self.head.split_whitespace().nth(1).unwrap();
+        url::Url::parse(&format!("http://127.0.0.1{}", target))
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect()
+    }
+
+    /// The value of a header, ignoring the case of the name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            if key.trim().eq_ignore_ascii_case(name) {
+                Some(value.trim())
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// A local HTTP server for token, discovery and Graph requests in tests.
+/// Each response is served once, in order, to a new connection and the
+/// requests are returned when the server is joined.
+///
+/// # Example
+/// ```rust,ignore
+/// let server = MockTokenServer::json(serde_json::json!({
+///     "token_type": "Bearer",
+///     "expires_in": 3599,
+///     "access_token": "access-token",
+/// }));
+/// oauth.access_token_url(&server.token_url());
+/// // Send the request.
+/// let body = server.request().form();
+/// ```
+pub struct MockTokenServer {
+    url: String,
+    handle: thread::JoinHandle<Vec<MockRequest>>,
+}
+
+impl MockTokenServer {
+    /// Serve a response for each status and body.
+    pub fn new(responses: Vec<(u16, String)>) -> MockTokenServer {
+        let (url, handle) = serve(responses, |requests| requests);
+        MockTokenServer { url, handle }
+    }
+
+    /// Serve one json response with a 200 status.
+    pub fn json(response: serde_json::Value) -> MockTokenServer {
+        MockTokenServer::new(vec![(200, response.to_string())])
+    }
+
+    /// The url of the server, such as `http://127.0.0.1:8000`.
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    /// The url of the server with the `/token` path.
+    pub fn token_url(&self) -> String {
+        format!("{}/token", self.url)
+    }
+
+    /// Wait for the server to serve all responses and return the
+    /// first request.
+    pub fn request(self) -> MockRequest {
+        self.requests().remove(0)
+    }
+
+    /// Wait for the server to serve all responses and return the requests.
+    pub fn requests(self) -> Vec<MockRequest> {
+        self.handle.join().unwrap()
+    }
+
+    fn read(stream: &mut TcpStream) -> MockRequest {
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        loop {
+            let n = stream.read(&mut buf).unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(index) = text.find("\r\n\r\n") {
+                let content_length = text
+                    .lines()
+                    .find_map(|line| {
+                        line.to_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|value| value.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if request.len() >= index + 4 + content_length {
+                    return MockRequest {
+                        head: text[..index].to_string(),
+                        body: text[index + 4..].to_string(),
+                    };
+                }
+            }
+        }
+    }
+}
@@ -73,3 +73,124 @@ pub fn test_deserialize() {
     let _token: AccessToken = serde_json::from_str(ACCESS_TOKEN_INT).unwrap();
     let _token: AccessToken = serde_json::from_str(ACCESS_TOKEN_STRING).unwrap();
 }
+
+// An unsigned JWT with the claims.
+fn jwt(claims: serde_json::Value) -> String {
+    let encode = |value: serde_json::Value| {
+        base64::encode_config(value.to_string(), base64::URL_SAFE_NO_PAD)
+    };
+    format!(
+        "{}.{}.signature",
+        encode(serde_json::json!({"alg": "RS256", "typ": "JWT"})),
+        encode(claims)
+    )
+}
+
+#[test]
+fn expires_within() {
+    let access_token = AccessToken::new("Bearer", 600, "User.Read", "ASODFIUJ34KJ;LADSK");
+    assert!(!access_token.expires_within(Duration::from_secs(0)));
+    assert!(!access_token.expires_within(Duration::from_secs(300)));
+    assert!(access_token.expires_within(Duration::from_secs(900)));
+    assert!(access_token.expires_within(Duration::from_secs(u64::MAX)));
+
+    let mut access_token = AccessToken::default();
+    access_token.set_expires_in(-10);
+    assert!(access_token.expires_within(Duration::from_secs(0)));
+}
+
+#[test]
+fn delegated_token_claims() {
+    let exp = chrono::Utc::now().timestamp() + 3600;
+    let token = jwt(serde_json::json!({
+        "aud": "00000003-0000-0000-c000-000000000000",
+        "exp": exp,
+        "tid": "72f988bf-86f1-41af-91ab-2d7cd011db47",
+        "azp": "6731de76-14a6-49ae-97bc-6eba6914391e",
+        "scp": "User.Read Mail.Send",
+        "roles": ["Reports.Reader"],
+        "name": "Ünïcödé ?>",
+    }));
+    let access_token = AccessToken::new("Bearer", 60, "User.Read", &token);
+    assert_eq!(access_token.timestamp().unwrap().timestamp(), exp);
+    assert!(!access_token.expires_within(Duration::from_secs(300)));
+    assert_eq!(
+        access_token.scopes_set().into_iter().collect::<Vec<_>>(),
+        vec!["Mail.Send".to_string(), "User.Read".to_string()]
+    );
+    assert_eq!(access_token.roles(), vec!["Reports.Reader".to_string()]);
+    assert!(access_token
+        .audience()
+        .unwrap()
+        .contains("00000003-0000-0000-c000-000000000000"));
+    assert_eq!(
+        access_token.tenant_id().as_deref(),
+        Some("72f988bf-86f1-41af-91ab-2d7cd011db47")
+    );
+    assert_eq!(
+        access_token.app_id().as_deref(),
+        Some("6731de76-14a6-49ae-97bc-6eba6914391e")
+    );
+    assert!(!access_token.is_app_only());
+
+    // Claims are also read from deserialized tokens.
+    let access_token: AccessToken =
+        serde_json::from_str(&serde_json::to_string(&access_token).unwrap()).unwrap();
+    assert_eq!(access_token.roles(), vec!["Reports.Reader".to_string()]);
+}
+
+#[test]
+fn app_only_token_claims() {
+    let token = jwt(serde_json::json!({
+        "aud": ["https://graph.microsoft.com"],
+        "appid": "6731de76-14a6-49ae-97bc-6eba6914391e",
+        "roles": ["User.Read.All"],
+    }));
+    let access_token = AccessToken::new("Bearer", 3600, "", &token);
+    assert!(access_token.is_app_only());
+    assert!(access_token.scopes_set().is_empty());
+    assert_eq!(
+        access_token.app_id().as_deref(),
+        Some("6731de76-14a6-49ae-97bc-6eba6914391e")
+    );
+    assert!(access_token
+        .audience()
+        .unwrap()
+        .contains("https://graph.microsoft.com"));
+
+    let token = jwt(serde_json::json!({"idtyp": "app", "scp": "User.Read"}));
+    assert!(AccessToken::new("Bearer", 3600, "", &token).is_app_only());
+    let token = jwt(serde_json::json!({"idtyp": "user"}));
+    assert!(!AccessToken::new("Bearer", 3600, "", &token).is_app_only());
+
+    // Tokens that cannot be parsed have no claims.
+    let access_token = AccessToken::new("Bearer", 3600, "User.Read", "ASODFIUJ34KJ;LADSK");
+    assert!(!access_token.is_app_only());
+    assert!(access_token.roles().is_empty());
+    assert!(access_token.audience().is_none());
+    assert!(access_token.tenant_id().is_none());
+    assert!(access_token.scopes_set().contains("User.Read"));
+}
+
+#[test]
+fn malformed_exp_claim() {
+    for exp in [
+        serde_json::json!("not a number"),
+        serde_json::json!(1.5),
+        serde_json::json!(i64::MAX),
+        serde_json::json!(null),
+    ]
+    .iter()
+    {
+        let token = jwt(serde_json::json!({ "exp": exp }));
+        let access_token = AccessToken::new("Bearer", 3600, "User.Read", &token);
+        // The expires_in of the response is used instead.
+        assert!(!access_token.is_expired());
+        assert!(access_token.expires_within(Duration::from_secs(3700)));
+    }
+
+    let exp = chrono::Utc::now().timestamp() + 60;
+    let token = jwt(serde_json::json!({ "exp": exp.to_string() }));
+    let access_token = AccessToken::new("Bearer", 3600, "User.Read", &token);
+    assert_eq!(access_token.timestamp().unwrap().timestamp(), exp);
+}
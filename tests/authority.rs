@@ -0,0 +1,115 @@
+use graph_rs_sdk::oauth::graphdiscovery::GraphDiscovery;
+use graph_rs_sdk::oauth::{Authority, OAuth, OAuthCredential, CONSUMERS_TENANT_ID};
+
+fn urls(authority: Authority) -> Vec<String> {
+    let mut oauth = OAuth::new();
+    oauth.authority(&authority);
+    vec![
+        OAuthCredential::AuthorizeURL,
+        OAuthCredential::AccessTokenURL,
+        OAuthCredential::RefreshTokenURL,
+        OAuthCredential::LogoutURL,
+    ]
+    .into_iter()
+    .map(|oac| oauth.get(oac).unwrap())
+    .collect()
+}
+
+#[test]
+fn authority_urls() {
+    for (authority, tenant) in [
+        (Authority::Common, "common"),
+        (Authority::Organizations, "organizations"),
+        (Authority::Consumers, "consumers"),
+        (Authority::tenant(CONSUMERS_TENANT_ID), CONSUMERS_TENANT_ID),
+        (
+            Authority::tenant("contoso.onmicrosoft.com"),
+            "contoso.onmicrosoft.com",
+        ),
+    ]
+    .iter()
+    {
+        let base = format!("https://login.microsoftonline.com/{}", tenant);
+        assert_eq!(authority.url(), base);
+        assert_eq!(
+            urls(authority.clone()),
+            vec![
+                format!("{}/oauth2/v2.0/authorize", base),
+                format!("{}/oauth2/v2.0/token", base),
+                format!("{}/oauth2/v2.0/token", base),
+                format!("{}/oauth2/v2.0/logout", base),
+            ]
+        );
+    }
+}
+
+#[test]
+fn authority_b2c_urls() {
+    let base = "https://contoso.b2clogin.com/contoso.onmicrosoft.com/B2C_1_signupsignin";
+    for authority in [
+        Authority::b2c("contoso", "B2C_1_signupsignin"),
+        Authority::b2c("contoso.onmicrosoft.com", "B2C_1_signupsignin"),
+    ]
+    .iter()
+    {
+        assert!(authority.is_b2c());
+        assert!(authority.graph_discovery().is_none());
+        assert_eq!(authority.to_string(), base);
+        assert_eq!(
+            authority.discovery_url(),
+            format!("{}/v2.0/.well-known/openid-configuration", base)
+        );
+        assert_eq!(
+            urls(authority.clone()),
+            vec![
+                format!("{}/oauth2/v2.0/authorize", base),
+                format!("{}/oauth2/v2.0/token", base),
+                format!("{}/oauth2/v2.0/token", base),
+                format!("{}/oauth2/v2.0/logout", base),
+            ]
+        );
+    }
+}
+
+#[test]
+fn authority_graph_discovery() {
+    assert_eq!(
+        Authority::Common.graph_discovery().unwrap().url(),
+        GraphDiscovery::V2.url()
+    );
+    let tenant = "contoso.onmicrosoft.com";
+    assert_eq!(
+        Authority::from(tenant).graph_discovery().unwrap().url(),
+        GraphDiscovery::Tenant(tenant.into()).url()
+    );
+    assert_eq!(Authority::from("Organizations"), Authority::Organizations);
+}
+
+#[test]
+fn authority_issuer() {
+    assert_eq!(
+        Authority::Organizations.issuer(),
+        "https://login.microsoftonline.com/{tenantid}/v2.0"
+    );
+    assert_eq!(
+        Authority::Consumers.issuer(),
+        format!(
+            "https://login.microsoftonline.com/{}/v2.0",
+            CONSUMERS_TENANT_ID
+        )
+    );
+    assert_eq!(
+        Authority::b2c("contoso", "B2C_1_signupsignin").issuer(),
+        "https://contoso.b2clogin.com/{tenantid}/v2.0/"
+    );
+
+    let tid = "72f988bf-86f1-41af-91ab-2d7cd011db47";
+    let iss = format!("https://login.microsoftonline.com/{}/v2.0", tid);
+    assert!(Authority::Common.is_valid_issuer(&iss, Some(tid)));
+    assert!(Authority::Tenant(tid.into()).is_valid_issuer(&iss, Some(tid)));
+    // The tenant of a domain name is not known.
+    assert!(!Authority::Tenant("contoso.com".into()).is_valid_issuer(&iss, Some(tid)));
+    assert!(!Authority::Common.is_valid_issuer(&iss, None));
+    assert!(!Authority::Common.is_valid_issuer(&iss, Some(CONSUMERS_TENANT_ID)));
+    assert!(!Authority::Tenant(CONSUMERS_TENANT_ID.into()).is_valid_issuer(&iss, Some(tid)));
+}
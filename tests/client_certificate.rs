@@ -0,0 +1,198 @@
+use graph_oauth::jwt::Algorithm;
+use graph_rs_sdk::oauth::{ClientCertificateCredential, OAuth};
+use ring::signature::{self, UnparsedPublicKey};
+use std::fs;
+use std::thread;
+use test_tools::tokenserver;
+
+const CLIENT_ID: &str = "6731de76-14a6-49ae-97bc-6eba6914391e";
+const TOKEN_URL: &str = "https://login.microsoftonline.com/common/oauth2/v2.0/token";
+const THUMBPRINT: &str = "8fV5OmvUu64mJPQ1to9m6d44Cjs";
+
+fn credential(key: &str) -> ClientCertificateCredential {
+    ClientCertificateCredential::from_pem(
+        CLIENT_ID,
+        &fs::read(key).unwrap(),
+        &fs::read("./test_files/oauth/client_certificate.pem").unwrap(),
+    )
+    .unwrap()
+}
+
+fn decode(part: &str) -> serde_json::Value {
+    serde_json::from_slice(&base64::decode_config(part, base64::URL_SAFE_NO_PAD).unwrap()).unwrap()
+}
+
+fn verify(assertion: &str, algorithm: &'static dyn signature::VerificationAlgorithm) {
+    let public_key = fs::read("./test_files/oauth/client_certificate_public_key.der").unwrap();
+    let index = assertion.rfind('.').unwrap();
+    let signature =
+        base64::decode_config(&assertion[index + 1..], base64::URL_SAFE_NO_PAD).unwrap();
+    UnparsedPublicKey::new(algorithm, public_key)
+        .verify(&assertion.as_bytes()[..index], &signature)
+        .unwrap();
+}
+
+// Serves one request and returns the form body.
+fn token_server() -> (String, thread::JoinHandle<String>) {
+    let token = r#"{"token_type":"Bearer","expires_in":3600,"access_token":"ASODFIUJ34KJ;LADSK"}"#;
+    let (url, handle) = tokenserver::serve(vec![(200, token.to_string())], |mut requests| {
+        requests.remove(0).body
+    });
+    (format!("{}/token", url), handle)
+}
+
+#[test]
+fn client_assertion_rs256() {
+    let credential = credential("./test_files/oauth/client_certificate_key.pem");
+    assert_eq!(credential.thumbprint(), THUMBPRINT);
+
+    let assertion = credential.client_assertion(TOKEN_URL).unwrap();
+    let parts: Vec<&str> = assertion.split('.').collect();
+    assert_eq!(parts.len(), 3);
+
+    let header = decode(parts[0]);
+    assert_eq!(header["alg"], "RS256");
+    assert_eq!(header["typ"], "JWT");
+    assert_eq!(header["x5t"], THUMBPRINT);
+
+    let claims = decode(parts[1]);
+    assert_eq!(claims["aud"], TOKEN_URL);
+    assert_eq!(claims["iss"], CLIENT_ID);
+    assert_eq!(claims["sub"], CLIENT_ID);
+    assert_eq!(claims["jti"].as_str().unwrap().len(), 36);
+    assert_eq!(
+        claims["exp"].as_i64().unwrap() - claims["nbf"].as_i64().unwrap(),
+        600
+    );
+    verify(&assertion, &signature::RSA_PKCS1_2048_8192_SHA256);
+
+    let next = decode(
+        credential
+            .client_assertion(TOKEN_URL)
+            .unwrap()
+            .split('.')
+            .nth(1)
+            .unwrap(),
+    );
+    assert_ne!(claims["jti"], next["jti"]);
+}
+
+#[test]
+fn client_assertion_ps256_pkcs1() {
+    let mut credential = credential("./test_files/oauth/client_certificate_key_pkcs1.pem");
+    assert_eq!(credential.thumbprint(), THUMBPRINT);
+    assert!(credential.algorithm(Algorithm::HS256).is_err());
+    credential.algorithm(Algorithm::PS256).unwrap();
+
+    let assertion = credential.client_assertion(TOKEN_URL).unwrap();
+    assert_eq!(decode(assertion.split('.').next().unwrap())["alg"], "PS256");
+    verify(&assertion, &signature::RSA_PSS_2048_8192_SHA256);
+}
+
+#[test]
+fn client_certificate_invalid_pem() {
+    let certificate = fs::read("./test_files/oauth/client_certificate.pem").unwrap();
+    assert!(ClientCertificateCredential::from_pem(CLIENT_ID, &certificate, &certificate).is_err());
+    let key = fs::read("./test_files/oauth/client_certificate_key.pem").unwrap();
+    assert!(ClientCertificateCredential::from_pem(CLIENT_ID, &key, &key).is_err());
+}
+
+#[test]
+fn client_certificate_debug_redacted() {
+    let credential = credential("./test_files/oauth/client_certificate_key.pem");
+    let debug = format!("{:?}", credential);
+    assert!(!debug.contains(CLIENT_ID));
+    assert!(debug.contains(THUMBPRINT));
+}
+
+#[test]
+fn client_credentials_with_certificate() {
+    let (url, handle) = token_server();
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id(CLIENT_ID)
+        .client_secret("JqQX2PNo9bpM0uEihUPzyrh")
+        .add_scope("https://graph.microsoft.com/.default")
+        .access_token_url(&url)
+        .client_certificate(credential("./test_files/oauth/client_certificate_key.pem"));
+
+    let access_token = oauth
+        .build()
+        .client_credentials()
+        .access_token()
+        .send()
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "ASODFIUJ34KJ;LADSK");
+
+    let body = handle.join().unwrap();
+    let params: Vec<(String, String)> = url::form_urlencoded::parse(body.as_bytes())
+        .into_owned()
+        .collect();
+    let value = |key: &str| {
+        params
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+    assert_eq!(value("grant_type").as_deref(), Some("client_credentials"));
+    assert_eq!(
+        value("client_assertion_type").as_deref(),
+        Some("urn:ietf:params:oauth:client-assertion-type:jwt-bearer")
+    );
+    assert!(value("client_secret").is_none());
+
+    let assertion = value("client_assertion").unwrap();
+    assert_eq!(decode(assertion.split('.').nth(1).unwrap())["aud"], url);
+    verify(&assertion, &signature::RSA_PKCS1_2048_8192_SHA256);
+}
+
+#[test]
+fn authorization_code_with_certificate() {
+    let (url, handle) = token_server();
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id(CLIENT_ID)
+        .redirect_uri("http://localhost:8000/redirect")
+        .access_code("access-code")
+        .add_scope("User.Read")
+        .access_token_url(&url)
+        .client_certificate(credential("./test_files/oauth/client_certificate_key.pem"));
+
+    oauth
+        .build()
+        .authorization_code_grant()
+        .access_token()
+        .send()
+        .unwrap();
+    let body = handle.join().unwrap();
+    let params: std::collections::HashMap<String, String> =
+        url::form_urlencoded::parse(body.as_bytes())
+            .into_owned()
+            .collect();
+    assert_eq!(params["grant_type"], "authorization_code");
+    assert_eq!(
+        params["client_assertion_type"],
+        "urn:ietf:params:oauth:client-assertion-type:jwt-bearer"
+    );
+    verify(
+        &params["client_assertion"],
+        &signature::RSA_PKCS1_2048_8192_SHA256,
+    );
+}
+
+#[test]
+fn device_code_rejects_certificate() {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id(CLIENT_ID)
+        .add_scope("User.Read")
+        .device_code_url("http://127.0.0.1:1/devicecode")
+        .access_token_url("http://127.0.0.1:1/token")
+        .client_certificate(credential("./test_files/oauth/client_certificate_key.pem"));
+
+    // The device code flow is for public clients, so requests are not
+    // sent without the client authentication that was configured.
+    let mut grant = oauth.build().device_code();
+    assert!(grant.device_code().is_err());
+    assert!(grant.refresh_token().is_err());
+}
@@ -0,0 +1,144 @@
+use from_as::*;
+use graph_rs_sdk::oauth::{
+    AccessToken, EncryptedData, EncryptedOAuth, EncryptionKey, OAuth, OAuthCredential,
+    ENCRYPTION_VERSION, MAX_PBKDF2_ITERATIONS,
+};
+
+const CLIENT_SECRET: &str = "JqQX2PNo9bpM0uEihUPzyrh";
+const PASSWORD: &str = "Sl3Hbs9aKs0#";
+
+fn passphrase(passphrase: &str) -> EncryptionKey {
+    let mut key = EncryptionKey::from_passphrase(passphrase);
+    key.iterations(1000);
+    key
+}
+
+fn oauth() -> OAuth {
+    let mut access_token = AccessToken::new("Bearer", 3600, "User.Read", "bearer-token");
+    access_token.set_refresh_token("refresh-token");
+
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .client_secret(CLIENT_SECRET)
+        .username("adele@contoso.com")
+        .password(PASSWORD)
+        .add_scope("User.Read")
+        .redirect_uri("http://localhost:8000/redirect")
+        .access_token_url("https://login.microsoftonline.com/common/oauth2/v2.0/token")
+        .access_token(access_token);
+    oauth
+}
+
+#[test]
+fn encrypt_oauth() {
+    let oauth = oauth();
+    let key = EncryptionKey::generate().unwrap();
+    let encrypted = oauth.encrypt(&key).unwrap();
+    assert_eq!(encrypted.version(), ENCRYPTION_VERSION);
+    assert!(encrypted.kdf().is_none());
+    assert!(encrypted.scopes().contains("User.Read"));
+    assert!(encrypted.credentials().contains_key("username"));
+    for credential in [
+        OAuthCredential::ClientId,
+        OAuthCredential::ClientSecret,
+        OAuthCredential::Password,
+    ]
+    .iter()
+    {
+        assert!(credential.is_sensitive());
+        assert!(!encrypted.credentials().contains_key(credential.alias()));
+    }
+
+    let json = serde_json::to_string(&encrypted).unwrap();
+    for secret in [CLIENT_SECRET, PASSWORD, "bearer-token", "refresh-token"].iter() {
+        assert!(!json.contains(secret));
+    }
+
+    let decrypted = encrypted.decrypt(&key).unwrap();
+    assert_eq!(decrypted, oauth);
+    assert_eq!(decrypted.get_refresh_token().unwrap(), "refresh-token");
+
+    let key = EncryptionKey::new(key.as_bytes().unwrap()).unwrap();
+    assert_eq!(encrypted.decrypt(&key).unwrap(), oauth);
+}
+
+#[test]
+fn encrypt_oauth_passphrase() {
+    let oauth = oauth();
+    let encrypted = oauth.encrypt(&passphrase("passphrase")).unwrap();
+    assert_eq!(encrypted.kdf().unwrap().iterations, 1000);
+
+    let path = std::env::temp_dir().join(format!("encrypted_oauth_{}.json", std::process::id()));
+    encrypted.as_file(&path).unwrap();
+    let encrypted = EncryptedOAuth::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Decryption uses the salt and iterations stored with the data.
+    let decrypted = encrypted
+        .decrypt(&EncryptionKey::from_passphrase("passphrase"))
+        .unwrap();
+    assert_eq!(
+        decrypted.get(OAuthCredential::ClientSecret).as_deref(),
+        Some(CLIENT_SECRET)
+    );
+
+    assert!(encrypted.decrypt(&passphrase("wrong")).is_err());
+    assert!(encrypted
+        .decrypt(&EncryptionKey::generate().unwrap())
+        .is_err());
+}
+
+#[test]
+fn encrypt_oauth_tampered() {
+    let key = EncryptionKey::generate().unwrap();
+    let encrypted = oauth().encrypt(&key).unwrap();
+    let mut json = serde_json::to_value(&encrypted).unwrap();
+    json["credentials"]["access_token_url"] = serde_json::json!("https://example.com/token");
+    let tampered: EncryptedOAuth = serde_json::from_value(json).unwrap();
+    assert!(tampered.decrypt(&key).is_err());
+
+    let mut json = serde_json::to_value(&encrypted).unwrap();
+    json["version"] = serde_json::json!(ENCRYPTION_VERSION + 1);
+    let unsupported: EncryptedOAuth = serde_json::from_value(json).unwrap();
+    let err = unsupported.decrypt(&key).unwrap_err();
+    assert!(err.to_string().contains("unsupported encryption version"));
+
+    // The iterations are checked before the key is derived.
+    let encrypted = oauth().encrypt(&passphrase("passphrase")).unwrap();
+    let mut json = serde_json::to_value(&encrypted).unwrap();
+    json["kdf"]["iterations"] = serde_json::json!(u32::MAX);
+    let tampered: EncryptedOAuth = serde_json::from_value(json).unwrap();
+    let err = tampered.decrypt(&passphrase("passphrase")).unwrap_err();
+    assert!(err.to_string().contains("iterations must not be more than"));
+
+    let mut key = passphrase("passphrase");
+    key.iterations(MAX_PBKDF2_ITERATIONS + 1);
+    assert!(oauth().encrypt(&key).is_err());
+}
+
+#[test]
+fn encrypt_access_token() {
+    let mut access_token = AccessToken::new("Bearer", 3600, "User.Read", "bearer-token");
+    access_token.set_refresh_token("refresh-token");
+    let key = passphrase("passphrase");
+
+    let encrypted = access_token.encrypt(&key).unwrap();
+    let json = serde_json::to_string(&encrypted).unwrap();
+    assert!(!json.contains("bearer-token"));
+    assert!(!json.contains("refresh-token"));
+
+    let encrypted: EncryptedData = serde_json::from_str(&json).unwrap();
+    let decrypted = AccessToken::decrypt(&encrypted, &key).unwrap();
+    assert_eq!(decrypted, access_token);
+    assert!(AccessToken::decrypt(&encrypted, &passphrase("wrong")).is_err());
+}
+
+#[test]
+fn encryption_key() {
+    assert!(EncryptionKey::new(&[0; 16]).is_err());
+    let key = EncryptionKey::new(&[7; 32]).unwrap();
+    assert_eq!(key.clone().as_bytes(), Some(&[7; 32][..]));
+    assert!(!format!("{:?}", key).contains('7'));
+    assert!(!format!("{:?}", passphrase("hunter2")).contains("hunter2"));
+}
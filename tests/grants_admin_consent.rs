@@ -0,0 +1,169 @@
+use graph_rs_sdk::oauth::{
+    AdminConsentRedirect, Authority, GrantRequest, GrantType, OAuth, OAuthCredential,
+};
+use std::collections::HashMap;
+
+const TENANT_ID: &str = "fa00d692-e9c7-4460-a743-29f2956fd429";
+
+fn oauth() -> OAuth {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .authority(&Authority::Organizations)
+        .redirect_uri("http://localhost:8000/redirect")
+        .add_scope("https://graph.microsoft.com/.default")
+        .state("12345")
+        .client_secret("JqQX2PNo9bpM0uEihUPzyrh");
+    oauth
+}
+
+#[test]
+fn admin_consent_url() {
+    let mut oauth = oauth();
+    let url = oauth.build().admin_consent().url().unwrap();
+    assert_eq!(
+        url.as_str().split('?').next().unwrap(),
+        "https://login.microsoftonline.com/organizations/v2.0/adminconsent"
+    );
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    assert_eq!(query.len(), 4);
+    assert_eq!(query["client_id"], "6731de76-14a6-49ae-97bc-6eba6914391e");
+    assert_eq!(query["redirect_uri"], "http://localhost:8000/redirect");
+    assert_eq!(query["scope"], "https://graph.microsoft.com/.default");
+    assert_eq!(query["state"], "12345");
+
+    let request = oauth.build_async().admin_consent().browser_authorization();
+    assert_eq!(request.unwrap().url().unwrap().query_pairs().count(), 4);
+
+    oauth.authority(&Authority::tenant(TENANT_ID));
+    let url = oauth.build().admin_consent().url().unwrap();
+    assert_eq!(url.path(), format!("/{}/v2.0/adminconsent", TENANT_ID));
+}
+
+#[test]
+fn admin_consent_url_with_query() {
+    let mut oauth = oauth();
+    oauth.admin_consent_url(
+        "https://login.microsoftonline.com/organizations/v2.0/adminconsent?domain_hint=contoso.com",
+    );
+    let url = oauth.build().admin_consent().url().unwrap();
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    assert_eq!(query.len(), 5);
+    assert_eq!(query["domain_hint"], "contoso.com");
+    assert_eq!(query["client_id"], "6731de76-14a6-49ae-97bc-6eba6914391e");
+    assert_eq!(url.as_str().matches('?').count(), 1);
+
+    let uri = oauth
+        .encode_uri(GrantType::AdminConsent, GrantRequest::Authorization)
+        .unwrap();
+    let encoded: HashMap<String, String> = url::Url::parse(uri.as_str())
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect();
+    assert_eq!(encoded, query);
+
+    oauth.admin_consent_url("https://login.microsoftonline.com/organizations/v2.0/adminconsent?");
+    let url = oauth.build().admin_consent().url().unwrap();
+    assert!(!url.as_str().contains("?&"));
+    assert_eq!(url.query_pairs().count(), 4);
+}
+
+#[test]
+fn admin_consent_requires_credentials() {
+    let mut oauth = oauth();
+    oauth.remove(OAuthCredential::RedirectURI);
+    assert!(oauth.build().admin_consent().url().is_err());
+
+    // The scope is required.
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .redirect_uri("http://localhost:8000/redirect")
+        .admin_consent_url("https://login.microsoftonline.com/common/v2.0/adminconsent");
+    assert!(oauth.build().admin_consent().url().is_err());
+
+    // Admin consent has no token requests.
+    assert!(oauth
+        .encode_uri(GrantType::AdminConsent, GrantRequest::AccessToken)
+        .is_err());
+    assert!(GrantType::AdminConsent
+        .available_credentials(GrantRequest::AccessToken)
+        .is_empty());
+}
+
+#[test]
+fn admin_consent_b2c() {
+    let mut oauth = OAuth::new();
+    oauth.authority(&Authority::b2c("contoso", "B2C_1_signupsignin"));
+    assert!(!oauth.contains(OAuthCredential::AdminConsentURL));
+}
+
+#[test]
+fn admin_consent_requires_organization() {
+    for authority in [Authority::Common, Authority::Consumers].iter() {
+        let mut oauth = oauth();
+        oauth.authority(authority);
+        assert!(!oauth.contains(OAuthCredential::AdminConsentURL));
+        assert!(oauth.build().admin_consent().url().is_err());
+    }
+}
+
+#[test]
+fn parse_admin_consent() {
+    let mut oauth = oauth();
+    let response = oauth
+        .parse_admin_consent(&format!(
+            "http://localhost:8000/redirect?admin_consent=True&tenant={}&scope=https%3A%2F%2Fgraph.microsoft.com%2FUser.Read.All+https%3A%2F%2Fgraph.microsoft.com%2FMail.Send&state=12345",
+            TENANT_ID
+        ))
+        .unwrap();
+    match response {
+        AdminConsentRedirect::Granted(response) => {
+            assert_eq!(response.tenant, TENANT_ID);
+            assert_eq!(
+                response.scopes(),
+                vec![
+                    "https://graph.microsoft.com/User.Read.All",
+                    "https://graph.microsoft.com/Mail.Send"
+                ]
+            );
+        }
+        AdminConsentRedirect::Error(_) => panic!("expected admin consent to be granted"),
+    }
+    assert_eq!(oauth.get(OAuthCredential::AdminConsent).unwrap(), "True");
+
+    // The state must match.
+    let mut oauth = self::oauth();
+    assert!(oauth
+        .parse_admin_consent(&format!(
+            "http://localhost:8000/redirect?admin_consent=True&tenant={}&state=67890",
+            TENANT_ID
+        ))
+        .is_err());
+    assert!(!oauth.contains(OAuthCredential::AdminConsent));
+}
+
+#[test]
+fn parse_admin_consent_error() {
+    let mut oauth = oauth();
+    let response = oauth
+        .build()
+        .admin_consent()
+        .parse_response("http://localhost:8000/redirect?error=access_denied&error_description=AADSTS65004%3A+User+declined+to+consent&state=12345")
+        .unwrap();
+    assert!(response.is_error());
+    match response {
+        AdminConsentRedirect::Error(error) => {
+            assert_eq!(error.error, "access_denied");
+            assert_eq!(
+                error.error_description.as_deref(),
+                Some("AADSTS65004: User declined to consent")
+            );
+        }
+        AdminConsentRedirect::Granted(_) => panic!("expected an error response"),
+    }
+
+    assert!(AdminConsentRedirect::parse("admin_consent=False&tenant=common").is_err());
+    assert!(AdminConsentRedirect::parse("admin_consent=True").is_err());
+}
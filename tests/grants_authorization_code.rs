@@ -97,6 +97,7 @@ pub fn access_token_body_contains() {
         .client_assertion("client_assertion")
         .client_assertion_type("client_assertion_type")
         .session_state("session_state")
+        .claims(r#"{"access_token":{"acrs":{"essential":true,"value":"c1"}}}"#)
         .logout_url("https://login.live.com/oauth20_logout.srf?")
         .post_logout_redirect_uri("http://localhost:8000/redirect");
 
@@ -0,0 +1,209 @@
+use graph_error::{GraphError, GraphFailure};
+use graph_rs_sdk::oauth::{
+    DeviceCode, DeviceCodeError, GrantRequest, GrantType, OAuth, OAuthCredential,
+};
+use reqwest::StatusCode;
+use std::thread;
+use test_tools::tokenserver;
+
+fn oauth() -> OAuth {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .add_scope("User.Read")
+        .add_scope("offline_access")
+        .device_code_url("https://login.microsoftonline.com/common/oauth2/v2.0/devicecode")
+        .access_token_url("https://login.microsoftonline.com/common/oauth2/v2.0/token")
+        .refresh_token_url("https://login.microsoftonline.com/common/oauth2/v2.0/token");
+    oauth
+}
+
+fn device_code(interval: u64) -> DeviceCode {
+    serde_json::from_value(serde_json::json!({
+        "device_code": "GMMhmHCXhWEzkobqIHGG_EnNYYsAkukHspeYUk9E8",
+        "user_code": "ABCD-EFGH",
+        "verification_uri": "https://microsoft.com/devicelogin",
+        "expires_in": 900,
+        "interval": interval,
+        "message": "To sign in, use a web browser to open the page https://microsoft.com/devicelogin and enter the code ABCD-EFGH to authenticate."
+    }))
+    .unwrap()
+}
+
+fn token_error(error: &str) -> GraphFailure {
+    let body = serde_json::json!({
+        "error": error,
+        "error_description": "AADSTS70016: OAuth 2.0 device flow error.",
+    });
+    GraphFailure::from(GraphError::from_response_body(
+        None,
+        StatusCode::BAD_REQUEST,
+        body.to_string(),
+    ))
+}
+
+// Serves each response once, in order, and returns the request bodies.
+fn token_server(responses: Vec<(u16, String)>) -> (String, thread::JoinHandle<Vec<String>>) {
+    let (url, handle) = tokenserver::serve(responses, |requests| {
+        requests.into_iter().map(|request| request.body).collect()
+    });
+    (format!("{}/token", url), handle)
+}
+
+fn pending_responses() -> Vec<(u16, String)> {
+    vec![
+        (
+            400,
+            r#"{"error":"authorization_pending","error_description":"AADSTS70016"}"#.to_string(),
+        ),
+        (
+            200,
+            r#"{"token_type":"Bearer","expires_in":3600,"scope":"User.Read","access_token":"ASODFIUJ34KJ;LADSK"}"#.to_string(),
+        ),
+    ]
+}
+
+#[test]
+fn device_code_authorization_body() {
+    let mut oauth = oauth();
+    let body = oauth
+        .encode_uri(GrantType::DeviceCode, GrantRequest::Authorization)
+        .unwrap();
+    assert_eq!(
+        body,
+        "client_id=6731de76-14a6-49ae-97bc-6eba6914391e&scope=User.Read+offline_access"
+    );
+}
+
+#[test]
+fn device_code_access_token_body() {
+    let mut oauth = oauth();
+    oauth.device_code("GMMhmHCXhWEzkobqIHGG_EnNYYsAkukHspeYUk9E8");
+    let body = oauth
+        .encode_uri(GrantType::DeviceCode, GrantRequest::AccessToken)
+        .unwrap();
+    assert_eq!(
+        body,
+        "client_id=6731de76-14a6-49ae-97bc-6eba6914391e&grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Adevice_code&device_code=GMMhmHCXhWEzkobqIHGG_EnNYYsAkukHspeYUk9E8"
+    );
+    assert!(GrantType::DeviceCode
+        .available_credentials(GrantRequest::AccessToken)
+        .contains(&OAuthCredential::DeviceCode));
+}
+
+#[test]
+fn device_code_response() {
+    let device_code = device_code(5);
+    assert_eq!(device_code.user_code(), "ABCD-EFGH");
+    assert_eq!(
+        device_code.verification_uri(),
+        "https://microsoft.com/devicelogin"
+    );
+    assert_eq!(device_code.interval().as_secs(), 5);
+    assert_eq!(device_code.expires_in().as_secs(), 900);
+    assert!(device_code.message().unwrap().contains("ABCD-EFGH"));
+    assert!(!format!("{:?}", device_code).contains(device_code.device_code()));
+
+    let device_code: DeviceCode = serde_json::from_str(
+        r#"{"device_code":"code","user_code":"ABCD-EFGH","verification_uri":"https://microsoft.com/devicelogin","expires_in":900}"#,
+    )
+    .unwrap();
+    assert_eq!(device_code.interval().as_secs(), 5);
+}
+
+#[test]
+fn device_code_polling_errors() {
+    assert_eq!(
+        DeviceCodeError::from_failure(&token_error("authorization_pending")),
+        Some(DeviceCodeError::AuthorizationPending)
+    );
+    assert_eq!(
+        DeviceCodeError::from_failure(&token_error("slow_down")),
+        Some(DeviceCodeError::SlowDown)
+    );
+    assert_eq!(
+        DeviceCodeError::from_failure(&token_error("expired_token")),
+        Some(DeviceCodeError::ExpiredToken)
+    );
+    assert_eq!(
+        DeviceCodeError::from_failure(&token_error("authorization_declined")),
+        Some(DeviceCodeError::AuthorizationDeclined)
+    );
+    assert!(DeviceCodeError::SlowDown.is_pending());
+    assert!(!DeviceCodeError::ExpiredToken.is_pending());
+    assert!(DeviceCodeError::from_failure(&token_error("invalid_grant")).is_none());
+    assert!(DeviceCodeError::from_failure(&GraphFailure::invalid("device_code")).is_none());
+}
+
+#[test]
+fn device_code_poll() {
+    let (url, handle) = token_server(pending_responses());
+    let mut oauth = oauth();
+    oauth.access_token_url(&url);
+    let mut grant = oauth.build().device_code();
+
+    let access_token = grant.poll(&device_code(0)).unwrap();
+    assert_eq!(access_token.bearer_token(), "ASODFIUJ34KJ;LADSK");
+    assert!(grant.as_ref().get_access_token().is_some());
+
+    let bodies = handle.join().unwrap();
+    assert_eq!(bodies.len(), 2);
+    assert!(bodies[0].contains("device_code=GMMhmHCXhWEzkobqIHGG_EnNYYsAkukHspeYUk9E8"));
+}
+
+#[test]
+fn device_code_poll_expired() {
+    let (url, handle) = token_server(vec![(
+        400,
+        r#"{"error":"expired_token","error_description":"AADSTS70020"}"#.to_string(),
+    )]);
+    let mut oauth = oauth();
+    oauth.access_token_url(&url);
+    let mut grant = oauth.build().device_code();
+
+    let err = grant.poll(&device_code(0)).unwrap_err();
+    assert_eq!(
+        DeviceCodeError::from_failure(&err),
+        Some(DeviceCodeError::ExpiredToken)
+    );
+    handle.join().unwrap();
+}
+
+#[test]
+fn device_code_poll_expired_locally() {
+    let (url, handle) = token_server(vec![(
+        400,
+        r#"{"error":"authorization_pending","error_description":"AADSTS70016"}"#.to_string(),
+    )]);
+    let mut oauth = oauth();
+    oauth.access_token_url(&url);
+    let mut grant = oauth.build().device_code();
+
+    let device_code: DeviceCode = serde_json::from_value(serde_json::json!({
+        "device_code": "GMMhmHCXhWEzkobqIHGG_EnNYYsAkukHspeYUk9E8",
+        "user_code": "ABCD-EFGH",
+        "verification_uri": "https://microsoft.com/devicelogin",
+        "expires_in": 0,
+        "interval": 0,
+    }))
+    .unwrap();
+    let err = grant.poll(&device_code).unwrap_err();
+    assert_eq!(
+        DeviceCodeError::from_failure(&err),
+        Some(DeviceCodeError::ExpiredToken)
+    );
+    assert!(!err.is_retryable());
+    handle.join().unwrap();
+}
+
+#[tokio::test]
+async fn async_device_code_poll() {
+    let (url, handle) = token_server(pending_responses());
+    let mut oauth = oauth();
+    oauth.access_token_url(&url);
+    let mut grant = oauth.build_async().device_code();
+
+    let access_token = grant.poll(&device_code(0)).await.unwrap();
+    assert_eq!(access_token.bearer_token(), "ASODFIUJ34KJ;LADSK");
+    assert_eq!(handle.join().unwrap().len(), 2);
+}
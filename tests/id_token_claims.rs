@@ -0,0 +1,303 @@
+use graph_oauth::jwt::{JwtValidationError, JwtValidator};
+use graph_rs_sdk::oauth::graphdiscovery::MicrosoftSigningKeysV2;
+use graph_rs_sdk::oauth::jwtkeys::JWTKeys;
+use graph_rs_sdk::oauth::{AccessToken, IdToken, IdTokenClaims};
+use std::fs;
+use std::thread;
+use test_tools::jwt::sign;
+use test_tools::tokenserver::{self, MockRequest};
+
+const CLIENT_ID: &str = "6731de76-14a6-49ae-97bc-6eba6914391e";
+const TENANT_ID: &str = "72f988bf-86f1-41af-91ab-2d7cd011db47";
+const OBJECT_ID: &str = "a0ca4d4c-b80a-4d26-8a21-9a8a8d1b1f4b";
+
+// Serves one json response and returns the request.
+fn server(
+    status: &'static str,
+    response: serde_json::Value,
+) -> (String, thread::JoinHandle<MockRequest>) {
+    let status = status[..3].parse().unwrap();
+    tokenserver::serve(vec![(status, response.to_string())], |mut requests| {
+        requests.remove(0)
+    })
+}
+
+fn validator() -> JwtValidator {
+    let keys: JWTKeys =
+        serde_json::from_slice(&fs::read("./test_files/oauth/jwks.json").unwrap()).unwrap();
+    let mut validator = JwtValidator::new(keys);
+    validator
+        .issuer("https://login.microsoftonline.com/{tenantid}/v2.0")
+        .audience(CLIENT_ID)
+        .nonce("678910");
+    validator
+}
+
+fn claims(extra: serde_json::Value) -> serde_json::Value {
+    let now = chrono::Utc::now().timestamp();
+    let mut claims = serde_json::json!({
+        "iss": format!("https://login.microsoftonline.com/{}/v2.0", TENANT_ID),
+        "aud": CLIENT_ID,
+        "tid": TENANT_ID,
+        "oid": OBJECT_ID,
+        "sub": "AAAAAAAAAAAAAAAAAAAAAIkzqFVrSaSaFHy782bbtaQ",
+        "preferred_username": "user@contoso.com",
+        "name": "Megan Bowen",
+        "email": "megan@contoso.com",
+        "nonce": "678910",
+        "iat": now,
+        "nbf": now,
+        "exp": now + 3600,
+        "uti": "fqiBqXLPj0eQa82S-IYFAA",
+    });
+    for (key, value) in extra.as_object().unwrap() {
+        claims[key] = value.clone();
+    }
+    claims
+}
+
+#[test]
+fn validated_id_token_claims() {
+    let id_token = sign(
+        "RS256",
+        "rsa-kid",
+        &claims(serde_json::json!({
+            "roles": ["Task.Admin"],
+            "groups": ["8782d9b3-9b5b-4b0a-9b5f-2c1d8a6c5e3f"],
+        })),
+    );
+    let claims = validator().validate_id_token(&id_token).unwrap();
+    assert_eq!(claims.oid.as_deref(), Some(OBJECT_ID));
+    assert_eq!(claims.tid.as_deref(), Some(TENANT_ID));
+    assert_eq!(
+        claims.sub.as_deref(),
+        Some("AAAAAAAAAAAAAAAAAAAAAIkzqFVrSaSaFHy782bbtaQ")
+    );
+    assert_eq!(
+        claims.preferred_username.as_deref(),
+        Some("user@contoso.com")
+    );
+    assert_eq!(claims.name.as_deref(), Some("Megan Bowen"));
+    assert_eq!(claims.email.as_deref(), Some("megan@contoso.com"));
+    assert_eq!(claims.roles, vec!["Task.Admin".to_string()]);
+    assert_eq!(
+        claims.groups,
+        vec!["8782d9b3-9b5b-4b0a-9b5f-2c1d8a6c5e3f".to_string()]
+    );
+    assert!(!claims.has_group_overage());
+    assert_eq!(claims.additional["uti"], "fqiBqXLPj0eQa82S-IYFAA");
+
+    let mut token = IdToken::default();
+    token.id_token(&id_token);
+    assert_eq!(token.validate(&validator()).unwrap(), claims);
+
+    let access_token: AccessToken = serde_json::from_value(serde_json::json!({
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "access_token": "access-token",
+        "id_token": id_token,
+    }))
+    .unwrap();
+    assert_eq!(access_token.id_token_claims(&validator()).unwrap(), claims);
+    assert!(matches!(
+        AccessToken::default().id_token_claims(&validator()),
+        Err(JwtValidationError::Malformed(_))
+    ));
+
+    // The claims are only returned for a valid token.
+    let mut validator = validator();
+    validator.nonce("other");
+    assert_eq!(
+        validator.validate_id_token(&id_token).unwrap_err(),
+        JwtValidationError::InvalidNonce
+    );
+}
+
+#[test]
+fn id_token_requires_audience_and_nonce() {
+    let id_token = sign("RS256", "rsa-kid", &claims(serde_json::json!({})));
+    let keys: JWTKeys =
+        serde_json::from_slice(&fs::read("./test_files/oauth/jwks.json").unwrap()).unwrap();
+
+    let mut validator = JwtValidator::new(keys.clone());
+    validator
+        .issuer("https://login.microsoftonline.com/{tenantid}/v2.0")
+        .nonce("678910");
+    assert_eq!(
+        validator.validate_id_token(&id_token).unwrap_err(),
+        JwtValidationError::NotConfigured("audience")
+    );
+
+    // The token has a nonce so the nonce of the request must be set.
+    let mut validator = JwtValidator::new(keys);
+    validator
+        .issuer("https://login.microsoftonline.com/{tenantid}/v2.0")
+        .audience(CLIENT_ID);
+    assert_eq!(
+        validator.validate_id_token(&id_token).unwrap_err(),
+        JwtValidationError::NotConfigured("nonce")
+    );
+
+    // Tokens for requests without a nonce do not have one.
+    let mut claims = claims(serde_json::json!({}));
+    claims.as_object_mut().unwrap().remove("nonce");
+    assert!(validator
+        .validate_id_token(&sign("RS256", "rsa-kid", &claims))
+        .is_ok());
+}
+
+#[test]
+fn resolve_group_overage() {
+    let (url, handle) = server(
+        "200 OK",
+        serde_json::json!({
+            "value": [
+                "8782d9b3-9b5b-4b0a-9b5f-2c1d8a6c5e3f",
+                "f3c6f5b3-0d8c-4f3a-9d4a-6a3c1f2e8b7d",
+            ]
+        }),
+    );
+    let id_token = sign(
+        "RS256",
+        "rsa-kid",
+        &claims(serde_json::json!({ "hasgroups": true })),
+    );
+    let mut claims = validator().validate_id_token(&id_token).unwrap();
+    assert!(claims.has_group_overage());
+    assert!(claims.groups.is_empty());
+
+    let groups = claims
+        .resolve_groups(&format!("{}/v1.0/", url), "graph-token")
+        .unwrap();
+    assert_eq!(groups.len(), 2);
+    assert!(!claims.has_group_overage());
+
+    let request = handle.join().unwrap();
+    assert!(request
+        .head
+        .starts_with(&format!("POST /v1.0/users/{}/getMemberObjects ", OBJECT_ID)));
+    assert!(request
+        .head
+        .to_lowercase()
+        .contains("authorization: bearer graph-token"));
+    let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+    assert_eq!(body, serde_json::json!({ "securityEnabledOnly": true }));
+
+    // Without an overage the groups of the token are returned.
+    let groups = claims
+        .resolve_groups("http://127.0.0.1:1/v1.0", "graph-token")
+        .unwrap();
+    assert_eq!(groups.len(), 2);
+}
+
+#[test]
+fn distributed_group_claims() {
+    let mut claims: IdTokenClaims = serde_json::from_value(serde_json::json!({
+        "_claim_names": { "groups": "src1" },
+        "_claim_sources": {
+            "src1": {
+                "endpoint": format!("https://graph.windows.net/{}/users/{}/getMemberObjects", TENANT_ID, OBJECT_ID)
+            }
+        },
+    }))
+    .unwrap();
+    assert!(claims.has_group_overage());
+    assert_eq!(
+        claims.group_overage_endpoint(),
+        Some(
+            format!(
+                "https://graph.windows.net/{}/users/{}/getMemberObjects",
+                TENANT_ID, OBJECT_ID
+            )
+            .as_str()
+        )
+    );
+    // The oid claim is needed to request the groups.
+    assert!(claims
+        .resolve_groups("http://127.0.0.1:1/v1.0", "graph-token")
+        .is_err());
+
+    let (url, handle) = server("200 OK", serde_json::json!({ "value": [] }));
+    claims.oid = Some(OBJECT_ID.into());
+    assert!(claims
+        .resolve_groups(&format!("{}/v1.0", url), "graph-token")
+        .unwrap()
+        .is_empty());
+    handle.join().unwrap();
+    assert!(!claims.has_group_overage());
+    assert!(claims.claim_sources.is_empty());
+    assert!(claims.group_overage_endpoint().is_none());
+}
+
+#[test]
+fn user_info_endpoint() {
+    let (url, handle) = server(
+        "200 OK",
+        serde_json::json!({
+            "sub": "OLu859SGc2Sr9ZsqbkG-QbeLgJlb41KcdiPoLYNpSFA",
+            "name": "Megan Bowen",
+            "family_name": "Bowen",
+            "given_name": "Megan",
+            "picture": "https://graph.microsoft.com/v1.0/me/photo/$value",
+            "email": "megan@contoso.com",
+        }),
+    );
+    let mut keys = MicrosoftSigningKeysV2::default();
+    keys.userinfo_endpoint = format!("{}/oidc/userinfo", url);
+    keys.msgraph_host = "graph.microsoft.com".into();
+    assert_eq!(keys.graph_url(), "https://graph.microsoft.com/v1.0");
+
+    let user_info = keys.user_info("openid-token").unwrap();
+    assert_eq!(user_info.sub, "OLu859SGc2Sr9ZsqbkG-QbeLgJlb41KcdiPoLYNpSFA");
+    assert_eq!(user_info.given_name.as_deref(), Some("Megan"));
+    assert_eq!(user_info.email.as_deref(), Some("megan@contoso.com"));
+
+    let request = handle.join().unwrap();
+    assert!(request.head.starts_with("GET /oidc/userinfo "));
+    assert!(request
+        .head
+        .to_lowercase()
+        .contains("authorization: bearer openid-token"));
+
+    let (url, handle) = server(
+        "401 Unauthorized",
+        serde_json::json!({
+            "error": {
+                "code": "InvalidAuthenticationToken",
+                "message": "Access token validation failure.",
+            }
+        }),
+    );
+    let mut keys = MicrosoftSigningKeysV2::default();
+    keys.userinfo_endpoint = format!("{}/oidc/userinfo", url);
+    assert!(keys.user_info("expired-token").is_err());
+    handle.join().unwrap();
+}
+
+#[tokio::test]
+async fn async_user_info_and_groups() {
+    let (url, handle) = server(
+        "200 OK",
+        serde_json::json!({ "sub": "OLu859SGc2Sr9ZsqbkG-QbeLgJlb41KcdiPoLYNpSFA" }),
+    );
+    let mut keys = MicrosoftSigningKeysV2::default();
+    keys.userinfo_endpoint = format!("{}/oidc/userinfo", url);
+    let user_info = keys.async_user_info("openid-token").await.unwrap();
+    assert_eq!(user_info.sub, "OLu859SGc2Sr9ZsqbkG-QbeLgJlb41KcdiPoLYNpSFA");
+    assert!(user_info.email.is_none());
+    handle.join().unwrap();
+
+    let (url, handle) = server(
+        "200 OK",
+        serde_json::json!({ "value": ["8782d9b3-9b5b-4b0a-9b5f-2c1d8a6c5e3f"] }),
+    );
+    let mut claims = IdTokenClaims::default();
+    claims.oid = Some(OBJECT_ID.into());
+    claims.hasgroups = Some(true);
+    let groups = claims
+        .async_resolve_groups(&format!("{}/v1.0", url), "graph-token")
+        .await
+        .unwrap();
+    assert_eq!(groups, ["8782d9b3-9b5b-4b0a-9b5f-2c1d8a6c5e3f".to_string()]);
+    handle.join().unwrap();
+}
@@ -0,0 +1,256 @@
+use graph_rs_sdk::oauth::{AccessToken, EncryptionKey, GrantType, OAuth, OAuthCredential};
+use std::collections::HashMap;
+use std::thread;
+use test_tools::tokenserver;
+
+// Serves one token response and returns the request body.
+fn token_server(response: serde_json::Value) -> (String, thread::JoinHandle<String>) {
+    let (url, handle) = tokenserver::serve(vec![(200, response.to_string())], |mut requests| {
+        requests.remove(0).body
+    });
+    (format!("{}/token", url), handle)
+}
+
+fn form(body: &str) -> HashMap<String, String> {
+    url::form_urlencoded::parse(body.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+fn oauth(token_url: &str) -> OAuth {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .client_secret("client-secret")
+        .redirect_uri("http://localhost:8000/redirect")
+        .authorize_url("https://login.microsoftonline.com/common/oauth2/v2.0/authorize")
+        .access_token_url(token_url)
+        .refresh_token_url(token_url)
+        .add_scope("User.Read")
+        .add_scope("offline_access");
+    oauth
+}
+
+#[test]
+fn access_token_has_scopes() {
+    let access_token = AccessToken::new("Bearer", 3600, "User.Read Mail.Read openid", "token");
+    assert!(access_token.has_scopes(&["mail.read", "User.Read"]));
+    assert!(!access_token.has_scopes::<&str>(&[]));
+    assert!(!access_token.has_scopes(&["offline_access"]));
+    assert!(!access_token.has_scopes(&["Mail.Send"]));
+
+    let access_token = AccessToken::new(
+        "Bearer",
+        3600,
+        "https://graph.microsoft.com/User.Read",
+        "token",
+    );
+    assert!(access_token.has_scopes(&["User.Read"]));
+}
+
+#[test]
+fn held_tokens_are_reused() {
+    let mut oauth = oauth("http://127.0.0.1:1/token");
+    oauth.add_token(AccessToken::new("Bearer", 3600, "User.Read", "graph-token"));
+    oauth.add_token(AccessToken::new(
+        "Bearer",
+        3600,
+        "https://storage.azure.com/user_impersonation",
+        "storage-token",
+    ));
+    // Replaces the token held for the same scopes.
+    oauth.add_token(AccessToken::new(
+        "Bearer",
+        3600,
+        "user.read",
+        "new-graph-token",
+    ));
+    assert_eq!(oauth.tokens().len(), 2);
+
+    let access_token = oauth
+        .acquire_token_for_scopes(GrantType::AuthorizationCode, &["User.Read"])
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "new-graph-token");
+    let access_token = oauth
+        .acquire_token_for_scopes(
+            GrantType::AuthorizationCode,
+            &["https://storage.azure.com/user_impersonation"],
+        )
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "storage-token");
+
+    // Tokens that expire soon are not used.
+    oauth.clear_tokens();
+    oauth.add_token(AccessToken::new(
+        "Bearer",
+        60,
+        "User.Read",
+        "expiring-token",
+    ));
+    assert!(oauth.token_for_scopes(&["User.Read"]).is_none());
+}
+
+#[test]
+fn refresh_token_for_new_scopes() {
+    let (url, handle) = token_server(serde_json::json!({
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "scope": "Mail.Send",
+        "access_token": "mail-token",
+    }));
+    let mut oauth = oauth(&url);
+    let mut access_token = AccessToken::new("Bearer", 3600, "User.Read", "graph-token");
+    access_token.set_refresh_token("refresh-token");
+    oauth.add_token(access_token);
+
+    let access_token = oauth
+        .acquire_token_for_scopes(GrantType::AuthorizationCode, &["Mail.Send"])
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "mail-token");
+    // The refresh token is kept when the response does not have one.
+    assert_eq!(oauth.get_refresh_token().unwrap(), "refresh-token");
+    assert_eq!(oauth.tokens().len(), 2);
+    // The scopes of OAuth are not changed.
+    assert_eq!(oauth.join_scopes(" "), "User.Read offline_access");
+
+    let body = form(&handle.join().unwrap());
+    assert_eq!(body["grant_type"], "refresh_token");
+    assert_eq!(body["refresh_token"], "refresh-token");
+    assert_eq!(body["scope"], "Mail.Send offline_access");
+
+    // Both tokens are now held without another request.
+    assert_eq!(
+        oauth
+            .token_for_scopes(&["User.Read"])
+            .unwrap()
+            .bearer_token(),
+        "graph-token"
+    );
+    assert_eq!(
+        oauth
+            .acquire_token_for_scopes(GrantType::AuthorizationCode, &["Mail.Send"])
+            .unwrap()
+            .bearer_token(),
+        "mail-token"
+    );
+}
+
+#[test]
+fn token_for_other_resource() {
+    let (url, handle) = token_server(serde_json::json!({
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "scope": "https://storage.azure.com/user_impersonation",
+        "access_token": "storage-token",
+    }));
+    let mut oauth = oauth(&url);
+    let mut access_token = AccessToken::new("Bearer", 3600, "User.Read", "graph-token");
+    access_token.set_refresh_token("refresh-token");
+    oauth.access_token(access_token);
+
+    let access_token = oauth
+        .acquire_token_for_scopes(
+            GrantType::AuthorizationCode,
+            &["https://storage.azure.com/user_impersonation"],
+        )
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "storage-token");
+    handle.join().unwrap();
+    // The access token of OAuth is still the Microsoft Graph token.
+    assert_eq!(
+        oauth.get_access_token().unwrap().bearer_token(),
+        "graph-token"
+    );
+    assert_eq!(
+        oauth
+            .token_for_scopes(&["https://storage.azure.com/user_impersonation"])
+            .unwrap()
+            .bearer_token(),
+        "storage-token"
+    );
+}
+
+#[test]
+fn empty_scopes() {
+    let mut oauth = oauth("http://127.0.0.1:1/token");
+    oauth.add_token(AccessToken::new("Bearer", 3600, "User.Read", "graph-token"));
+    assert!(oauth.token_for_scopes::<&str>(&[]).is_none());
+    assert!(oauth
+        .acquire_token_for_scopes::<&str>(GrantType::AuthorizationCode, &[])
+        .is_err());
+    assert!(oauth
+        .acquire_token_for_scopes(GrantType::AuthorizationCode, &["offline_access"])
+        .is_err());
+    assert!(oauth
+        .incremental_consent_url::<&str>(GrantType::AuthorizationCode, &[])
+        .is_err());
+}
+
+#[test]
+fn authorization_code_for_new_scopes() {
+    let (url, handle) = token_server(serde_json::json!({
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "scope": "Mail.Send offline_access",
+        "access_token": "mail-token",
+        "refresh_token": "new-refresh-token",
+    }));
+    let mut oauth = oauth(&url);
+    oauth.access_code("access-code");
+
+    let access_token = oauth
+        .acquire_token_for_scopes(GrantType::AuthorizationCode, &["Mail.Send"])
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "mail-token");
+    assert!(!oauth.contains(OAuthCredential::AccessCode));
+    assert_eq!(oauth.get_refresh_token().unwrap(), "new-refresh-token");
+
+    let body = form(&handle.join().unwrap());
+    assert_eq!(body["grant_type"], "authorization_code");
+    assert_eq!(body["code"], "access-code");
+    assert_eq!(body["scope"], "Mail.Send offline_access");
+}
+
+#[test]
+fn consent_required() {
+    let mut oauth = oauth("http://127.0.0.1:1/token");
+    oauth.add_token(AccessToken::new("Bearer", 3600, "User.Read", "graph-token"));
+
+    let failure = oauth
+        .acquire_token_for_scopes(GrantType::AuthorizationCode, &["Mail.Send"])
+        .unwrap_err();
+    let error = failure.oauth_error().unwrap();
+    assert_eq!(error.error, "consent_required");
+    assert!(error.requires_interaction());
+
+    assert!(oauth
+        .incremental_consent_url(GrantType::AuthorizationCode, &["User.Read"])
+        .unwrap()
+        .is_none());
+    let url = oauth
+        .incremental_consent_url(GrantType::AuthorizationCode, &["Mail.Send"])
+        .unwrap()
+        .unwrap();
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    assert_eq!(query["scope"], "Mail.Send offline_access");
+    assert_eq!(query["response_type"], "code");
+}
+
+#[test]
+fn encrypted_tokens() {
+    let mut oauth = oauth("http://127.0.0.1:1/token");
+    oauth.add_token(AccessToken::new("Bearer", 3600, "User.Read", "graph-token"));
+    oauth.add_token(AccessToken::new("Bearer", 3600, "Mail.Send", "mail-token"));
+
+    let key = EncryptionKey::generate().unwrap();
+    let encrypted = oauth.encrypt(&key).unwrap();
+    let decrypted = encrypted.decrypt(&key).unwrap();
+    assert_eq!(decrypted.tokens().len(), 2);
+    assert_eq!(
+        decrypted
+            .token_for_scopes(&["Mail.Send"])
+            .unwrap()
+            .bearer_token(),
+        "mail-token"
+    );
+}
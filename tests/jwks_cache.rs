@@ -0,0 +1,185 @@
+use graph_oauth::jwt::{JwtClaims, JwtValidator};
+use graph_rs_sdk::oauth::jwkscache::JwksCache;
+use graph_rs_sdk::oauth::jwtkeys::JWTKeys;
+use graph_rs_sdk::oauth::OAuthCredential;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+struct Server {
+    url: String,
+    configuration_requests: Arc<AtomicUsize>,
+    key_requests: Arc<AtomicUsize>,
+}
+
+// Serves the OpenID configuration and the keys. The first request for
+// the keys returns only the EC key and later requests return both keys.
+fn server(cache_control: &'static str) -> Server {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let configuration_requests = Arc::new(AtomicUsize::new(0));
+    let key_requests = Arc::new(AtomicUsize::new(0));
+
+    let host = url.clone();
+    let configuration_count = configuration_requests.clone();
+    let key_count = key_requests.clone();
+    thread::spawn(move || {
+        let keys: serde_json::Value =
+            serde_json::from_slice(&std::fs::read("./test_files/oauth/jwks.json").unwrap())
+                .unwrap();
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request = String::from_utf8_lossy(&request).to_string();
+            let body = if request.starts_with("GET /keys") {
+                if key_count.fetch_add(1, Ordering::SeqCst) == 0 {
+                    serde_json::json!({ "keys": [keys["keys"][1]] })
+                } else {
+                    keys.clone()
+                }
+            } else {
+                configuration_count.fetch_add(1, Ordering::SeqCst);
+                serde_json::json!({
+                    "issuer": "https://login.microsoftonline.com/{tenantid}/v2.0",
+                    "authorization_endpoint": format!("{}/authorize", host),
+                    "token_endpoint": format!("{}/token", host),
+                    "jwks_uri": format!("{}/keys", host),
+                    "id_token_signing_alg_values_supported": ["RS256"],
+                    "tenant_region_scope": null,
+                })
+            }
+            .to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nCache-Control: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                cache_control,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    Server {
+        url: format!("{}/.well-known/openid-configuration", url),
+        configuration_requests,
+        key_requests,
+    }
+}
+
+fn token(kid: &str) -> String {
+    let header = serde_json::json!({ "typ": "JWT", "alg": "RS256", "kid": kid });
+    format!(
+        "{}.e30.c2lnbmF0dXJl",
+        base64::encode_config(header.to_string(), base64::URL_SAFE_NO_PAD)
+    )
+}
+
+#[test]
+fn jwks_cache_keys() {
+    let server = server("public, max-age=86400");
+    let cache = JwksCache::from_url(&server.url);
+
+    let configuration = cache.configuration().unwrap();
+    assert_eq!(
+        configuration.id_token_signing_alg_values_supported,
+        ["RS256"]
+    );
+    assert!(configuration.additional.contains_key("tenant_region_scope"));
+
+    let keys = cache.keys().unwrap();
+    assert!(keys.find("ec-kid").is_some());
+    assert!(cache.clone().keys().unwrap().find("ec-kid").is_some());
+    assert_eq!(server.configuration_requests.load(Ordering::SeqCst), 1);
+    assert_eq!(server.key_requests.load(Ordering::SeqCst), 1);
+
+    let oauth = cache.oauth().unwrap();
+    assert_eq!(
+        oauth.get(OAuthCredential::AccessTokenURL),
+        Some(configuration.token_endpoint.clone())
+    );
+    assert_eq!(server.configuration_requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn jwks_cache_unknown_kid_refresh() {
+    let server = server("max-age=86400");
+    let mut cache = JwksCache::from_url(&server.url);
+    cache.min_refresh_interval(Duration::from_secs(0));
+
+    let keys = cache.keys_for_token(&token("ec-kid")).unwrap();
+    assert!(keys.find("rsa-kid").is_none());
+    assert_eq!(server.key_requests.load(Ordering::SeqCst), 1);
+
+    // The key was rotated in after the keys were cached.
+    let keys = cache.keys_for_token(&token("rsa-kid")).unwrap();
+    assert!(keys.find("rsa-kid").is_some());
+    assert_eq!(server.key_requests.load(Ordering::SeqCst), 2);
+
+    cache.keys_for_token(&token("rsa-kid")).unwrap();
+    assert_eq!(server.key_requests.load(Ordering::SeqCst), 2);
+    assert_eq!(server.configuration_requests.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn jwks_cache_refresh_rate_limit() {
+    let server = server("max-age=86400");
+    let cache = JwksCache::from_url(&server.url);
+
+    cache.keys().unwrap();
+    let keys = cache.keys_for_token(&token("unknown-kid")).unwrap();
+    assert!(keys.find("unknown-kid").is_none());
+    cache.keys_for_token(&token("unknown-kid")).unwrap();
+    assert_eq!(server.key_requests.load(Ordering::SeqCst), 1);
+
+    let validator = JwtValidator::new(JWTKeys::default());
+    let result: Result<JwtClaims, _> = validator.validate_with(&keys, &token("unknown-kid"));
+    assert!(result.is_err());
+
+    cache.refresh().unwrap();
+    assert_eq!(server.key_requests.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn jwks_cache_max_age() {
+    let server = server("no-cache");
+    let mut cache = JwksCache::from_url(&server.url);
+    cache.min_refresh_interval(Duration::from_secs(0));
+
+    cache.keys().unwrap();
+    cache.keys().unwrap();
+    assert_eq!(server.key_requests.load(Ordering::SeqCst), 2);
+    assert_eq!(server.configuration_requests.load(Ordering::SeqCst), 2);
+
+    cache.clear();
+    cache
+        .min_refresh_interval(Duration::from_secs(60))
+        .default_ttl(Duration::from_secs(0));
+    cache.keys().unwrap();
+    cache.keys().unwrap();
+    assert_eq!(server.key_requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn async_jwks_cache_unknown_kid_refresh() {
+    let server = server("max-age=86400");
+    let mut cache = JwksCache::from_url(&server.url);
+    cache.min_refresh_interval(Duration::from_secs(0));
+
+    let keys = cache.async_keys_for_token(&token("ec-kid")).await.unwrap();
+    assert!(keys.find("rsa-kid").is_none());
+    let keys = cache.async_keys_for_token(&token("rsa-kid")).await.unwrap();
+    assert!(keys.find("rsa-kid").is_some());
+    cache.async_keys().await.unwrap();
+
+    assert_eq!(server.key_requests.load(Ordering::SeqCst), 2);
+    assert_eq!(server.configuration_requests.load(Ordering::SeqCst), 1);
+    assert!(cache.async_oauth().await.is_ok());
+}
@@ -0,0 +1,322 @@
+use graph_error::{GraphFailure, GraphResult};
+use graph_oauth::jwt::{Algorithm, JwtClaims, JwtValidationError, JwtValidator};
+use graph_rs_sdk::oauth::jwtkeys::JWTKeys;
+use graph_rs_sdk::oauth::{AccessToken, Authority};
+use std::fs;
+use test_tools::jwt::{encode, sign};
+
+const CLIENT_ID: &str = "6731de76-14a6-49ae-97bc-6eba6914391e";
+const TENANT_ID: &str = "9188040d-6c67-4c5b-b112-36a304b66dad";
+const ISSUER: &str = "https://login.microsoftonline.com/{tenantid}/v2.0";
+
+fn keys() -> JWTKeys {
+    serde_json::from_slice(&fs::read("./test_files/oauth/jwks.json").unwrap()).unwrap()
+}
+
+fn validator() -> JwtValidator {
+    let mut validator = JwtValidator::new(keys());
+    validator
+        .issuer(ISSUER)
+        .audience(CLIENT_ID)
+        .tenant_id(TENANT_ID);
+    validator
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn claims() -> serde_json::Value {
+    serde_json::json!({
+        "iss": format!("https://login.microsoftonline.com/{}/v2.0", TENANT_ID),
+        "aud": CLIENT_ID,
+        "tid": TENANT_ID,
+        "sub": "AAAAAAAAAAAAAAAAAAAAAIkzqFVrSaSaFHy782bbtaQ",
+        "preferred_username": "user@contoso.com",
+        "nonce": "678910",
+        "iat": now(),
+        "nbf": now(),
+        "exp": now() + 3600,
+    })
+}
+
+#[test]
+fn validate_signatures() {
+    let validator = validator();
+    for (alg, kid) in [
+        ("RS256", "rsa-kid"),
+        ("PS256", "rsa-kid"),
+        ("ES256", "ec-kid"),
+    ]
+    .iter()
+    {
+        let claims = validator.validate(&sign(alg, kid, &claims())).unwrap();
+        assert_eq!(claims.tid.as_deref(), Some(TENANT_ID));
+        assert_eq!(
+            claims.preferred_username.as_deref(),
+            Some("user@contoso.com")
+        );
+        assert!(claims.aud.unwrap().contains(CLIENT_ID));
+    }
+}
+
+#[test]
+fn validate_typed_claims() {
+    #[derive(serde::Deserialize)]
+    struct IdTokenClaims {
+        sub: String,
+        nonce: String,
+    }
+
+    let mut validator = validator();
+    validator.nonce("678910");
+    let id_token: IdTokenClaims = validator
+        .validate_as(&sign("RS256", "rsa-kid", &claims()))
+        .unwrap();
+    assert_eq!(id_token.sub, "AAAAAAAAAAAAAAAAAAAAAIkzqFVrSaSaFHy782bbtaQ");
+    assert_eq!(id_token.nonce, "678910");
+
+    let claims: JwtClaims = validator
+        .validate(&sign("RS256", "rsa-kid", &claims()))
+        .unwrap();
+    assert!(claims.additional.is_empty());
+}
+
+#[test]
+fn validate_signature_errors() {
+    let validator = validator();
+    let token = sign("RS256", "rsa-kid", &claims());
+    let mut parts: Vec<&str> = token.split('.').collect();
+    let tampered = encode(&serde_json::json!({ "aud": CLIENT_ID, "exp": now() + 3600 }));
+    parts[1] = tampered.as_str();
+    assert_eq!(
+        validator.validate(&parts.join(".")),
+        Err(JwtValidationError::InvalidSignature)
+    );
+
+    assert_eq!(
+        validator.validate(&sign("RS256", "unknown-kid", &claims())),
+        Err(JwtValidationError::KeyNotFound(Some("unknown-kid".into())))
+    );
+    assert!(matches!(
+        validator.validate(&sign("ES256", "rsa-kid", &claims())),
+        Err(JwtValidationError::InvalidKey(_))
+    ));
+    assert!(matches!(
+        validator.validate("eyJhbGciOiJIUzI1NiJ9.e30"),
+        Err(JwtValidationError::Malformed(_))
+    ));
+
+    let header = encode(&serde_json::json!({ "alg": "none", "kid": "rsa-kid" }));
+    assert_eq!(
+        validator.validate(&format!("{}.{}.", header, encode(&claims()))),
+        Err(JwtValidationError::UnsupportedAlgorithm("none".into()))
+    );
+
+    let mut validator = validator.clone();
+    validator.algorithms(&[Algorithm::ES256]);
+    assert_eq!(
+        validator.validate(&token),
+        Err(JwtValidationError::UnsupportedAlgorithm("RS256".into()))
+    );
+}
+
+#[test]
+fn validation_error_into_graph_failure() {
+    fn validate(token: &str) -> GraphResult<JwtClaims> {
+        Ok(validator().validate(token)?)
+    }
+
+    let err = validate(&sign("RS256", "unknown-kid", &claims())).unwrap_err();
+    assert!(matches!(
+        err,
+        GraphFailure::JwtValidationError(JwtValidationError::KeyNotFound(_))
+    ));
+    assert_eq!(
+        err.jwt_validation_error(),
+        Some(&JwtValidationError::KeyNotFound(Some("unknown-kid".into())))
+    );
+    assert!(validate(&sign("RS256", "rsa-kid", &claims())).is_ok());
+}
+
+#[test]
+fn validate_time_claims() {
+    let mut validator = validator();
+    let mut claims = claims();
+    claims["exp"] = serde_json::json!(now() - 120);
+    let token = sign("RS256", "rsa-kid", &claims);
+    assert!(validator.validate(&token).is_ok());
+
+    validator.clock_skew(std::time::Duration::from_secs(60));
+    assert!(matches!(
+        validator.validate(&token),
+        Err(JwtValidationError::Expired(_))
+    ));
+
+    let mut claims = self::claims();
+    claims["nbf"] = serde_json::json!(now() + 600);
+    assert!(matches!(
+        validator.validate(&sign("RS256", "rsa-kid", &claims)),
+        Err(JwtValidationError::NotYetValid(_))
+    ));
+
+    claims.as_object_mut().unwrap().remove("exp");
+    claims["nbf"] = serde_json::json!(now());
+    assert_eq!(
+        validator.validate(&sign("RS256", "rsa-kid", &claims)),
+        Err(JwtValidationError::MissingClaim("exp"))
+    );
+}
+
+#[test]
+fn validate_identity_claims() {
+    let mut claims = claims();
+    claims["aud"] = serde_json::json!(["api://other", CLIENT_ID]);
+    assert!(validator()
+        .validate(&sign("RS256", "rsa-kid", &claims))
+        .is_ok());
+
+    claims["aud"] = serde_json::json!("api://other");
+    assert_eq!(
+        validator().validate(&sign("RS256", "rsa-kid", &claims)),
+        Err(JwtValidationError::InvalidAudience)
+    );
+
+    let mut claims = self::claims();
+    claims["iss"] = serde_json::json!("https://sts.windows.net/other/");
+    assert_eq!(
+        validator().validate(&sign("RS256", "rsa-kid", &claims)),
+        Err(JwtValidationError::InvalidIssuer(
+            "https://sts.windows.net/other/".into()
+        ))
+    );
+
+    let mut claims = self::claims();
+    claims["tid"] = serde_json::json!("other");
+    claims["iss"] = serde_json::json!("https://login.microsoftonline.com/other/v2.0");
+    assert_eq!(
+        validator().validate(&sign("RS256", "rsa-kid", &claims)),
+        Err(JwtValidationError::InvalidTenant("other".into()))
+    );
+
+    let mut validator = validator();
+    validator.nonce("12345");
+    assert_eq!(
+        validator.validate(&sign("RS256", "rsa-kid", &self::claims())),
+        Err(JwtValidationError::InvalidNonce)
+    );
+}
+
+#[test]
+fn validate_authority_issuer() {
+    let validate = |authority: Authority, claims: &serde_json::Value| {
+        let mut validator = JwtValidator::new(keys());
+        validator.authority(&authority).audience(CLIENT_ID);
+        validator.validate(&sign("RS256", "rsa-kid", claims))
+    };
+
+    // TENANT_ID is the tenant of personal Microsoft accounts.
+    assert!(validate(Authority::Consumers, &claims()).is_ok());
+    assert!(validate(Authority::Common, &claims()).is_ok());
+    assert!(validate(Authority::Tenant(TENANT_ID.into()), &claims()).is_ok());
+    assert_eq!(
+        validate(Authority::Organizations, &claims()),
+        Err(JwtValidationError::InvalidIssuer(format!(
+            "https://login.microsoftonline.com/{}/v2.0",
+            TENANT_ID
+        )))
+    );
+
+    let tid = "72f988bf-86f1-41af-91ab-2d7cd011db47";
+    let mut claims = self::claims();
+    claims["tid"] = serde_json::json!(tid);
+    claims["iss"] = serde_json::json!(format!("https://login.microsoftonline.com/{}/v2.0", tid));
+    assert!(validate(Authority::Organizations, &claims).is_ok());
+    assert!(validate(Authority::Consumers, &claims).is_err());
+    assert!(validate(Authority::Tenant(TENANT_ID.into()), &claims).is_err());
+
+    claims["iss"] = serde_json::json!(format!("https://contoso.b2clogin.com/{}/v2.0/", tid));
+    assert!(validate(Authority::b2c("contoso", "B2C_1_signupsignin"), &claims).is_ok());
+    assert!(validate(Authority::b2c("fabrikam", "B2C_1_signupsignin"), &claims).is_err());
+    assert!(validate(Authority::Common, &claims).is_err());
+}
+
+#[test]
+fn validate_domain_authority_issuer() {
+    let authority = Authority::Tenant("contoso.com".into());
+    let mut validator = JwtValidator::new(keys());
+    validator.authority(&authority).audience(CLIENT_ID);
+    let token = sign("RS256", "rsa-kid", &claims());
+    assert_eq!(
+        validator.validate(&token),
+        Err(JwtValidationError::InvalidIssuer(format!(
+            "https://login.microsoftonline.com/{}/v2.0",
+            TENANT_ID
+        )))
+    );
+
+    validator.tenant_id(TENANT_ID);
+    assert!(validator.validate(&token).is_ok());
+
+    let tid = "72f988bf-86f1-41af-91ab-2d7cd011db47";
+    let mut claims = self::claims();
+    claims["tid"] = serde_json::json!(tid);
+    claims["iss"] = serde_json::json!(format!("https://login.microsoftonline.com/{}/v2.0", tid));
+    assert!(validator
+        .validate(&sign("RS256", "rsa-kid", &claims))
+        .is_err());
+}
+
+#[test]
+fn validate_requires_issuer_and_audience() {
+    let token = sign("RS256", "rsa-kid", &claims());
+    assert_eq!(
+        JwtValidator::new(keys()).validate(&token),
+        Err(JwtValidationError::NotConfigured("issuer"))
+    );
+
+    let mut validator = JwtValidator::new(keys());
+    validator.issuer(ISSUER);
+    assert_eq!(
+        validator.validate(&token),
+        Err(JwtValidationError::NotConfigured("audience"))
+    );
+
+    let mut validator = JwtValidator::new(keys());
+    validator.audience(CLIENT_ID);
+    assert_eq!(
+        validator.validate(&token),
+        Err(JwtValidationError::NotConfigured("issuer"))
+    );
+
+    validator.authority(&Authority::Common);
+    assert!(validator.validate(&token).is_ok());
+}
+
+#[test]
+fn validated_access_token_claims() {
+    let mut claims = claims();
+    claims["azp"] = serde_json::json!("b6a43c2e-5bd6-4b5e-9b7e-2d1c8a6f3e4d");
+    claims["roles"] = serde_json::json!(["Task.Read"]);
+    let token = sign("RS256", "rsa-kid", &claims);
+    let access_token = AccessToken::new("Bearer", 3600, "", &token);
+    let validated = access_token.validated_claims(&validator()).unwrap();
+    assert_eq!(validated.roles(), ["Task.Read".to_string()]);
+    assert_eq!(
+        validated.app_id(),
+        Some("b6a43c2e-5bd6-4b5e-9b7e-2d1c8a6f3e4d")
+    );
+    assert!(validated.is_app_only());
+
+    // The claim helpers of AccessToken read forged tokens.
+    claims["roles"] = serde_json::json!(["Task.Admin"]);
+    let (message, signature) = token.rsplit_once('.').unwrap();
+    let header = message.split('.').next().unwrap();
+    let forged = format!("{}.{}.{}", header, encode(&claims), signature);
+    let access_token = AccessToken::new("Bearer", 3600, "", &forged);
+    assert_eq!(access_token.roles(), vec!["Task.Admin".to_string()]);
+    assert_eq!(
+        access_token.validated_claims(&validator()),
+        Err(JwtValidationError::InvalidSignature)
+    );
+}
@@ -0,0 +1,248 @@
+use graph_rs_sdk::oauth::{AuthorizationRequest, LoopbackServer, OAuth, OAuthCredential};
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+use test_tools::tokenserver;
+use url::Url;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+// Serves one token response and returns the request body.
+fn token_server() -> (String, thread::JoinHandle<String>) {
+    let response = serde_json::json!({
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "scope": "User.Read",
+        "access_token": "access-token",
+        "refresh_token": "refresh-token",
+    });
+    let (url, handle) = tokenserver::serve(vec![(200, response.to_string())], |mut requests| {
+        requests.remove(0).body
+    });
+    (format!("{}/token", url), handle)
+}
+
+fn oauth(token_url: &str) -> OAuth {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .add_scope("User.Read")
+        .authorize_url("https://login.microsoftonline.com/common/oauth2/v2.0/authorize")
+        .access_token_url(token_url);
+    oauth
+}
+
+fn query(request: &AuthorizationRequest) -> HashMap<String, String> {
+    Url::parse(request.uri())
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+// Acts as the browser following the redirect from the authorization server.
+fn redirect(redirect_uri: &str, query: &str) -> thread::JoinHandle<String> {
+    let url = Url::parse(redirect_uri).unwrap();
+    let request = format!(
+        "GET {}?{} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n",
+        url.path(),
+        query
+    );
+    let address = format!("127.0.0.1:{}", url.port().unwrap());
+    thread::spawn(move || {
+        let mut favicon = TcpStream::connect(address.as_str()).unwrap();
+        favicon
+            .write_all(b"GET /favicon.ico HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        favicon.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"));
+
+        let mut stream = TcpStream::connect(address.as_str()).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    })
+}
+
+#[test]
+fn loopback_sign_in() {
+    let (url, handle) = token_server();
+    let mut oauth = oauth(&url);
+    let mut browser = None;
+    let access_token = oauth
+        .loopback_sign_in_with(TIMEOUT, |request| {
+            let query = query(request);
+            assert_eq!(query["response_type"], "code");
+            assert_eq!(query["code_challenge_method"], "S256");
+            assert!(query["redirect_uri"].starts_with("http://127.0.0.1:"));
+            browser = Some(redirect(
+                &query["redirect_uri"],
+                &format!("code=access-code&state={}", query["state"]),
+            ));
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "access-token");
+    assert_eq!(
+        oauth.get_refresh_token().unwrap(),
+        access_token.clone().refresh_token().unwrap()
+    );
+    assert!(!oauth.contains(OAuthCredential::AccessCode));
+
+    let response = browser.unwrap().join().unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("Sign in complete"));
+
+    let body: HashMap<String, String> =
+        url::form_urlencoded::parse(handle.join().unwrap().as_bytes())
+            .into_owned()
+            .collect();
+    assert_eq!(body["grant_type"], "authorization_code");
+    assert_eq!(body["code"], "access-code");
+    assert_eq!(
+        Some(&body["code_verifier"]),
+        oauth.get(OAuthCredential::CodeVerifier).as_ref()
+    );
+    assert_eq!(
+        Some(&body["redirect_uri"]),
+        oauth.get(OAuthCredential::RedirectURI).as_ref()
+    );
+}
+
+#[test]
+fn loopback_sign_in_state_mismatch() {
+    let mut oauth = oauth("http://127.0.0.1:1/token");
+    oauth.state("12345");
+    let mut browser = None;
+    let err = oauth
+        .loopback_sign_in_with(TIMEOUT, |request| {
+            let query = query(request);
+            assert_eq!(query["state"], "12345");
+            browser = Some(redirect(
+                &query["redirect_uri"],
+                "code=access-code&state=67890",
+            ));
+            Ok(())
+        })
+        .unwrap_err();
+    assert!(err.to_string().contains("state"));
+    browser.unwrap().join().unwrap();
+    assert!(oauth.get_access_token().is_none());
+}
+
+#[test]
+fn loopback_sign_in_error_response() {
+    let mut oauth = oauth("http://127.0.0.1:1/token");
+    let mut browser = None;
+    let err = oauth
+        .loopback_sign_in_with(TIMEOUT, |request| {
+            let query = query(request);
+            browser = Some(redirect(
+                &query["redirect_uri"],
+                &format!(
+                    "error=access_denied&error_description=the+user+canceled&state={}",
+                    query["state"]
+                ),
+            ));
+            Ok(())
+        })
+        .unwrap_err();
+    assert!(err.to_string().contains("access_denied the user canceled"));
+    assert!(browser.unwrap().join().unwrap().contains("Sign in failed"));
+}
+
+#[test]
+fn loopback_sign_in_timeout() {
+    let mut oauth = oauth("http://127.0.0.1:1/token");
+    let err = oauth
+        .loopback_sign_in_with(Duration::from_millis(200), |_| Ok(()))
+        .unwrap_err();
+    match err {
+        graph_error::GraphFailure::Io(e) => assert_eq!(e.kind(), ErrorKind::TimedOut),
+        _ => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn loopback_stalled_connection() {
+    let server = LoopbackServer::bind("/redirect").unwrap();
+    let url = Url::parse(&server.redirect_uri()).unwrap();
+    let address = format!("127.0.0.1:{}", url.port().unwrap());
+
+    // A connection that never sends a request is dropped after the
+    // connection timeout instead of ending the sign in.
+    let stalled = TcpStream::connect(address.as_str()).unwrap();
+    let browser = redirect(&server.redirect_uri(), "code=access-code&state=12345");
+    let query = server.wait_for_redirect(TIMEOUT).unwrap();
+    assert_eq!(query["code"], "access-code");
+    assert!(browser.join().unwrap().contains("Sign in complete"));
+    drop(stalled);
+}
+
+#[tokio::test]
+async fn async_loopback_sign_in() {
+    let (url, handle) = token_server();
+    let mut oauth = oauth(&url);
+    let mut browser = None;
+    let access_token = oauth
+        .async_loopback_sign_in_with(TIMEOUT, |request| {
+            let query = query(request);
+            assert_eq!(query["code_challenge_method"], "S256");
+            browser = Some(redirect(
+                &query["redirect_uri"],
+                &format!("code=access-code&state={}", query["state"]),
+            ));
+            Ok(())
+        })
+        .await
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "access-token");
+    assert_eq!(oauth.get_refresh_token().unwrap(), "refresh-token");
+    assert!(!oauth.contains(OAuthCredential::AccessCode));
+    assert!(browser
+        .unwrap()
+        .join()
+        .unwrap()
+        .contains("Sign in complete"));
+
+    let body: HashMap<String, String> =
+        url::form_urlencoded::parse(handle.join().unwrap().as_bytes())
+            .into_owned()
+            .collect();
+    assert_eq!(body["code"], "access-code");
+    assert_eq!(
+        Some(&body["code_verifier"]),
+        oauth.get(OAuthCredential::CodeVerifier).as_ref()
+    );
+}
+
+#[tokio::test]
+async fn async_loopback_stalled_connection() {
+    let server = LoopbackServer::bind("/redirect").unwrap();
+    let url = Url::parse(&server.redirect_uri()).unwrap();
+    let address = format!("127.0.0.1:{}", url.port().unwrap());
+
+    let stalled = TcpStream::connect(address.as_str()).unwrap();
+    let browser = redirect(&server.redirect_uri(), "code=access-code&state=12345");
+    let query = server.async_wait_for_redirect(TIMEOUT).await.unwrap();
+    assert_eq!(query["code"], "access-code");
+    assert!(browser.join().unwrap().contains("Sign in complete"));
+    drop(stalled);
+}
+
+#[tokio::test]
+async fn async_loopback_sign_in_timeout() {
+    let mut oauth = oauth("http://127.0.0.1:1/token");
+    let err = oauth
+        .async_loopback_sign_in_with(Duration::from_millis(200), |_| Ok(()))
+        .await
+        .unwrap_err();
+    match err {
+        graph_error::GraphFailure::Io(e) => assert_eq!(e.kind(), ErrorKind::TimedOut),
+        _ => panic!("unexpected error: {:?}", err),
+    }
+}
@@ -0,0 +1,274 @@
+use graph_rs_sdk::error::{GraphError, GraphFailure, GraphHeaders, OAuthErrorResponse};
+use graph_rs_sdk::oauth::{ClaimsChallenge, GrantType, OAuth, OAuthCredential};
+use reqwest::header::{HeaderMap, HeaderValue, WWW_AUTHENTICATE};
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::thread;
+use test_tools::tokenserver;
+
+const CLAIMS: &str = r#"{"access_token":{"nbf":{"essential":true,"value":"1604106651"}}}"#;
+const ENCODED_CLAIMS: &str =
+    "eyJhY2Nlc3NfdG9rZW4iOnsibmJmIjp7ImVzc2VudGlhbCI6dHJ1ZSwidmFsdWUiOiIxNjA0MTA2NjUxIn19fQ==";
+
+// Serves one token response and returns the request body.
+fn token_server(status: &'static str, response: String) -> (String, thread::JoinHandle<String>) {
+    let status = status[..3].parse().unwrap();
+    let (url, handle) = tokenserver::serve(vec![(status, response)], |mut requests| {
+        requests.remove(0).body
+    });
+    (format!("{}/token", url), handle)
+}
+
+fn form(body: &str) -> HashMap<String, String> {
+    url::form_urlencoded::parse(body.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+#[test]
+fn token_error_response() {
+    let (url, handle) = token_server(
+        "400 Bad Request",
+        serde_json::json!({
+            "error": "invalid_grant",
+            "error_description": "AADSTS50076: Due to a configuration change made by your administrator, you must use multi-factor authentication.",
+            "error_codes": [50076],
+            "timestamp": "2021-03-01 18:01:47Z",
+            "trace_id": "0f9a1b4c-5c3e-4a4e-8f5b-2bd1b6b9a100",
+            "correlation_id": "3a7f2c6e-90c1-4b73-a5f4-6d1c51c7e2a1",
+            "suberror": "basic_action",
+            "claims": CLAIMS,
+        })
+        .to_string(),
+    );
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .refresh_token_url(&url)
+        .add_scope("User.Read");
+    let mut access_token = graph_rs_sdk::oauth::AccessToken::default();
+    access_token.set_refresh_token("refresh-token");
+    oauth.access_token(access_token);
+
+    let failure = oauth
+        .build()
+        .authorization_code_grant()
+        .refresh_token()
+        .send()
+        .unwrap_err();
+    handle.join().unwrap();
+
+    let error = failure.oauth_error().unwrap();
+    assert!(error.is_invalid_grant());
+    assert!(error.requires_interaction());
+    assert!(!error.is_retryable());
+    assert_eq!(error.status, Some(400));
+    assert_eq!(error.error_codes, vec![50076]);
+    assert_eq!(error.suberror.as_deref(), Some("basic_action"));
+    assert_eq!(
+        error.correlation_id.as_deref(),
+        Some("3a7f2c6e-90c1-4b73-a5f4-6d1c51c7e2a1")
+    );
+    assert!(error
+        .to_string()
+        .starts_with("invalid_grant (basic_action): AADSTS50076"));
+    assert!(failure
+        .to_string()
+        .starts_with("OAuth error: invalid_grant (basic_action): AADSTS50076"));
+
+    let challenge = ClaimsChallenge::from_failure(&failure).unwrap();
+    assert_eq!(challenge.claims(), CLAIMS);
+}
+
+#[tokio::test]
+async fn async_token_error_response() {
+    let (url, handle) = token_server(
+        "400 Bad Request",
+        serde_json::json!({
+            "error": "invalid_grant",
+            "error_description": "AADSTS70008: The provided authorization code or refresh token has expired.",
+            "error_codes": [70008],
+        })
+        .to_string(),
+    );
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .refresh_token_url(&url)
+        .add_scope("User.Read");
+    let mut access_token = graph_rs_sdk::oauth::AccessToken::default();
+    access_token.set_refresh_token("refresh-token");
+    oauth.access_token(access_token);
+
+    let failure = oauth
+        .build_async()
+        .authorization_code_grant()
+        .refresh_token()
+        .send()
+        .await
+        .unwrap_err();
+    assert_eq!(form(&handle.join().unwrap())["grant_type"], "refresh_token");
+
+    let error = failure.oauth_error().unwrap();
+    assert!(error.is_invalid_grant());
+    assert_eq!(error.status, Some(400));
+    assert_eq!(error.error_codes, vec![70008]);
+}
+
+#[test]
+fn oauth_error_response_body() {
+    let error = OAuthErrorResponse::from_response_body(
+        503,
+        r#"{"error":"temporarily_unavailable","error_codes":[]}"#,
+    )
+    .unwrap();
+    assert!(error.is_retryable());
+    assert!(!error.requires_interaction());
+    assert_eq!(error.to_string(), "temporarily_unavailable");
+
+    let error = OAuthErrorResponse::from_response_body(
+        400,
+        r#"{"error":"interaction_required","error_description":"AADSTS50079"}"#,
+    )
+    .unwrap();
+    assert!(error.requires_interaction());
+
+    // Graph API errors are not OAuth errors.
+    assert!(OAuthErrorResponse::from_response_body(
+        401,
+        r#"{"error":{"code":"InvalidAuthenticationToken","message":"Access token has expired."}}"#,
+    )
+    .is_none());
+}
+
+#[test]
+fn parse_claims_challenge() {
+    let header = format!(
+        r#"Bearer realm="", authorization_uri="https://login.microsoftonline.com/common/oauth2/authorize", client_id="00000003-0000-0000-c000-000000000000", error="insufficient_claims", claims="{}""#,
+        ENCODED_CLAIMS
+    );
+    let challenge = ClaimsChallenge::parse(&header).unwrap();
+    assert!(challenge.is_insufficient_claims());
+    assert_eq!(challenge.claims(), CLAIMS);
+    assert_eq!(challenge.realm.as_deref(), Some(""));
+    assert_eq!(
+        challenge.authorization_uri.as_deref(),
+        Some("https://login.microsoftonline.com/common/oauth2/authorize")
+    );
+    assert_eq!(
+        challenge.client_id.as_deref(),
+        Some("00000003-0000-0000-c000-000000000000")
+    );
+
+    // Unquoted values and claims without base64 padding.
+    let header = format!(
+        "bearer error=insufficient_claims,claims={}",
+        ENCODED_CLAIMS.trim_end_matches('=')
+    );
+    assert_eq!(ClaimsChallenge::parse(&header).unwrap().claims(), CLAIMS);
+
+    assert!(ClaimsChallenge::parse(r#"Bearer realm="", error="invalid_token""#).is_none());
+    assert!(ClaimsChallenge::parse(r#"Basic realm="graph""#).is_none());
+    assert!(ClaimsChallenge::parse(r#"Bearer claims="not json""#).is_none());
+}
+
+#[test]
+fn claims_challenge_from_failure() {
+    let mut header_map = HeaderMap::new();
+    header_map.append(
+        WWW_AUTHENTICATE,
+        HeaderValue::from_static(r#"Basic realm="graph""#),
+    );
+    header_map.append(
+        WWW_AUTHENTICATE,
+        HeaderValue::from_str(&format!(
+            r#"Bearer error="insufficient_claims", claims="{}""#,
+            ENCODED_CLAIMS
+        ))
+        .unwrap(),
+    );
+    let headers = GraphHeaders::new("https://graph.microsoft.com/v1.0/me", 401, header_map);
+    let error = GraphError::from_response_body(
+        Some(headers),
+        StatusCode::UNAUTHORIZED,
+        r#"{"error":{"code":"InvalidAuthenticationToken","message":"Continuous access evaluation resulted in challenge with result: InteractionRequired and code: TokenIssuedBeforeRevocationTimestamp"}}"#.to_string(),
+    );
+    let failure = GraphFailure::from(error);
+    let challenge = ClaimsChallenge::from_failure(&failure).unwrap();
+    assert_eq!(challenge.claims(), CLAIMS);
+
+    let failure = GraphFailure::from(GraphError::from_response_body(
+        None,
+        StatusCode::UNAUTHORIZED,
+        String::new(),
+    ));
+    assert!(ClaimsChallenge::from_failure(&failure).is_none());
+}
+
+#[test]
+fn acquire_token_for_claims_challenge() {
+    let (url, handle) = token_server(
+        "200 OK",
+        serde_json::json!({
+            "token_type": "Bearer",
+            "expires_in": 3600,
+            "scope": "User.Read",
+            "access_token": "new-access-token",
+        })
+        .to_string(),
+    );
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .refresh_token_url(&url)
+        .add_scope("User.Read");
+    let mut access_token = graph_rs_sdk::oauth::AccessToken::default();
+    access_token.set_refresh_token("refresh-token");
+    oauth.access_token(access_token);
+
+    let challenge = ClaimsChallenge::parse(&format!(
+        r#"Bearer error="insufficient_claims", claims="{}""#,
+        ENCODED_CLAIMS
+    ))
+    .unwrap();
+    let access_token = oauth
+        .acquire_token_for_claims_challenge(GrantType::AuthorizationCode, &challenge)
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "new-access-token");
+    // The refresh token is kept when the response does not have one.
+    assert_eq!(oauth.get_refresh_token().unwrap(), "refresh-token");
+    assert!(!oauth.contains(OAuthCredential::Claims));
+
+    let body = form(&handle.join().unwrap());
+    assert_eq!(body["grant_type"], "refresh_token");
+    assert_eq!(body["refresh_token"], "refresh-token");
+    assert_eq!(body["claims"], CLAIMS);
+}
+
+#[test]
+fn claims_challenge_requires_refresh_token() {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("6731de76-14a6-49ae-97bc-6eba6914391e")
+        .refresh_token_url("http://127.0.0.1:1/token")
+        .authorize_url("https://login.microsoftonline.com/common/oauth2/v2.0/authorize")
+        .redirect_uri("http://localhost:8000/redirect")
+        .add_scope("User.Read");
+    let challenge = ClaimsChallenge::parse(&format!(
+        r#"Bearer error="insufficient_claims", claims="{}""#,
+        ENCODED_CLAIMS
+    ))
+    .unwrap();
+    assert!(oauth
+        .acquire_token_for_claims_challenge(GrantType::AuthorizationCode, &challenge)
+        .is_err());
+
+    // The claims stay set for interactive sign in.
+    let url = oauth
+        .build()
+        .authorization_code_grant()
+        .authorization_url()
+        .unwrap();
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    assert_eq!(query["claims"], CLAIMS);
+}
@@ -1,4 +1,4 @@
-use graph_oauth::oauth::{OAuth, OAuthCredential};
+use graph_oauth::oauth::{AuthorizationRequest, OAuth, OAuthCredential};
 use strum::IntoEnumIterator;
 
 #[test]
@@ -157,3 +157,86 @@ fn setters() {
     test_setter(OAuthCredential::RedirectURI, "https://example.com/redirect");
     test_setter(OAuthCredential::AccessCode, "access_code");
 }
+
+#[test]
+fn logout_urls() {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("client id")
+        .logout_url("https://login.live.com/oauth20_logout.srf?")
+        .redirect_uri("http://localhost:8000/redirect?a=1&b=2");
+
+    let url = oauth.v1_logout_url().unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://login.live.com/oauth20_logout.srf?client_id=client+id&redirect_uri=http%3A%2F%2Flocalhost%3A8000%2Fredirect%3Fa%3D1%26b%3D2"
+    );
+    let url = oauth.v2_logout_url().unwrap();
+    let query: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    assert_eq!(
+        query,
+        vec![(
+            "post_logout_redirect_uri".to_string(),
+            "http://localhost:8000/redirect?a=1&b=2".to_string()
+        )]
+    );
+
+    oauth.post_logout_redirect_uri("http://localhost:8000/logged-out");
+    let query: Vec<(String, String)> = oauth
+        .v1_logout_url()
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect();
+    assert_eq!(query[1].1, "http://localhost:8000/logged-out");
+
+    oauth.remove(OAuthCredential::LogoutURL);
+    assert!(oauth.v2_logout_url().is_err());
+}
+
+fn query(url: url::Url) -> std::collections::HashMap<String, String> {
+    url.query_pairs().into_owned().collect()
+}
+
+#[test]
+fn authorization_request_url() {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id("client_id")
+        .authorize_url("https://login.microsoftonline.com/common/oauth2/v2.0/authorize")
+        .redirect_uri("http://localhost:8000/redirect")
+        .state("a b&c")
+        .add_scope("User.Read")
+        .add_scope("Mail.Send");
+
+    let mut grant = oauth.build().authorization_code_grant();
+    let request = grant.browser_authorization().unwrap();
+    assert_eq!(
+        query(request.url().unwrap()),
+        query(grant.authorization_url().unwrap())
+    );
+    let params = query(request.url().unwrap());
+    assert_eq!(params["state"], "a b&c");
+    assert_eq!(params["scope"], "Mail.Send User.Read");
+
+    let mut grant = oauth.build().token_flow();
+    let implicit = query(grant.browser_authorization().unwrap().url().unwrap());
+    assert_eq!(implicit["response_type"], "token");
+    assert_eq!(implicit["client_id"], "client_id");
+}
+
+#[test]
+fn authorization_url_errors() {
+    let mut oauth = OAuth::new();
+    oauth.client_id("client_id");
+    assert!(oauth.build().implicit_grant().authorization_url().is_err());
+    assert!(oauth.build().token_flow().browser_authorization().is_err());
+    assert!(oauth
+        .build()
+        .authorization_code_grant()
+        .authorization_url()
+        .is_err());
+
+    let request: AuthorizationRequest = serde_json::from_str(r#"{"uri":"not a url"}"#).unwrap();
+    assert!(request.url().is_err());
+}
@@ -0,0 +1,132 @@
+use graph_rs_sdk::oauth::{OAuth, RedirectResponse, ResponseMode};
+
+const REDIRECT_URI: &str = "http://localhost:8000/redirect";
+
+fn oauth() -> OAuth {
+    let mut oauth = OAuth::new();
+    oauth.redirect_uri(REDIRECT_URI).state("12345");
+    oauth
+}
+
+#[test]
+fn parse_redirect_code() {
+    let response = oauth()
+        .parse_redirect(&format!(
+            "{}?code=M0ab92efe&state=12345&session_state=fe1540c3",
+            REDIRECT_URI
+        ))
+        .unwrap();
+    match response {
+        RedirectResponse::Code(response) => {
+            assert_eq!(response.code, "M0ab92efe");
+            assert_eq!(response.session_state.as_deref(), Some("fe1540c3"));
+            assert!(response.id_token().is_none());
+        }
+        _ => panic!("expected a code response: {:?}", response),
+    }
+
+    // Hybrid flow response in a form_post body.
+    let mut oauth = oauth();
+    oauth.response_mode("form_post");
+    let response = oauth
+        .parse_redirect("code=M0ab92efe&id_token=eyJ0eXAi.eyJhdWQi.c2ln&state=12345")
+        .unwrap();
+    match response {
+        RedirectResponse::Code(response) => {
+            let id_token = response.id_token().unwrap();
+            assert_eq!(id_token.get_id_token(), "eyJ0eXAi.eyJhdWQi.c2ln");
+            assert_eq!(id_token.get_code().as_deref(), Some("M0ab92efe"));
+        }
+        _ => panic!("expected a code response: {:?}", response),
+    }
+}
+
+#[test]
+fn parse_redirect_implicit() {
+    let response = oauth()
+        .parse_redirect(&format!(
+            "{}#access_token=eyJ0eXAi&token_type=Bearer&expires_in=3599&scope=User.Read&id_token=eyJhbGci&state=12345",
+            REDIRECT_URI
+        ))
+        .unwrap();
+    let response = match response {
+        RedirectResponse::Implicit(response) => response,
+        _ => panic!("expected an implicit response: {:?}", response),
+    };
+    assert_eq!(response.expires_in, Some(3599));
+    let access_token = response.access_token().unwrap();
+    assert_eq!(access_token.bearer_token(), "eyJ0eXAi");
+    assert_eq!(access_token.id_token().as_deref(), Some("eyJhbGci"));
+    assert_eq!(access_token.scopes().unwrap(), "User.Read");
+
+    let response = RedirectResponse::parse("#id_token=eyJhbGci&state=12345", None).unwrap();
+    match response {
+        RedirectResponse::Implicit(response) => assert!(response.access_token().is_none()),
+        _ => panic!("expected an implicit response: {:?}", response),
+    }
+}
+
+#[test]
+fn parse_redirect_response_mode() {
+    let url = format!(
+        "{}?code=from-query&state=12345#code=from-fragment&state=12345",
+        REDIRECT_URI
+    );
+    let code = |response: RedirectResponse| match response {
+        RedirectResponse::Code(response) => response.code,
+        _ => panic!("expected a code response: {:?}", response),
+    };
+    assert_eq!(code(oauth().parse_redirect(&url).unwrap()), "from-fragment");
+
+    let mut oauth = oauth();
+    oauth.response_mode(ResponseMode::Query.as_ref());
+    assert_eq!(code(oauth.parse_redirect(&url).unwrap()), "from-query");
+    assert_eq!(
+        code(RedirectResponse::parse(&url, Some(ResponseMode::Fragment)).unwrap()),
+        "from-fragment"
+    );
+}
+
+#[test]
+fn parse_redirect_error() {
+    let response = oauth()
+        .parse_redirect(&format!(
+            "{}?error=access_denied&error_description=the+user+canceled+the+authentication",
+            REDIRECT_URI
+        ))
+        .unwrap();
+    assert!(response.is_error());
+    match response {
+        RedirectResponse::Error(error) => {
+            assert_eq!(error.error, "access_denied");
+            assert_eq!(
+                error.to_string(),
+                "access_denied the user canceled the authentication"
+            );
+        }
+        _ => panic!("expected an error response: {:?}", response),
+    }
+
+    assert!(RedirectResponse::parse(REDIRECT_URI, None).is_err());
+    assert!(RedirectResponse::parse("code=M0ab92efe&expires_in=soon", None).is_err());
+}
+
+#[test]
+fn parse_redirect_state() {
+    let oauth = oauth();
+    let err = oauth
+        .parse_redirect(&format!("{}?code=M0ab92efe&state=67890", REDIRECT_URI))
+        .unwrap_err();
+    assert!(err.to_string().contains("does not match"));
+    assert!(oauth
+        .parse_redirect(&format!("{}?code=M0ab92efe", REDIRECT_URI))
+        .is_err());
+    assert!(oauth
+        .parse_redirect(&format!("{}?error=access_denied&state=67890", REDIRECT_URI))
+        .is_err());
+
+    // The state is only checked when OAuth has a state.
+    assert!(OAuth::new()
+        .parse_redirect(&format!("{}?code=M0ab92efe", REDIRECT_URI))
+        .is_ok());
+}
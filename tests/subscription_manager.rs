@@ -0,0 +1,141 @@
+use chrono::{Duration, Utc};
+use graph_rs_sdk::subscriptions::{
+    FileSubscriptionStore, ManagedSubscription, MemorySubscriptionStore, SubscriptionResource,
+    SubscriptionSpec, SubscriptionStore,
+};
+use std::fs;
+
+fn managed_subscription(id: &str, expires_in: Duration) -> ManagedSubscription {
+    ManagedSubscription {
+        id: id.into(),
+        spec: SubscriptionSpec::new(
+            "me/mailFolders('Inbox')/messages",
+            "created,updated",
+            "https://webhook.azurewebsites.net/api/send/myNotifyClient",
+        ),
+        expiration_date_time: Utc::now() + expires_in,
+    }
+}
+
+#[test]
+fn resource_max_lifetime() {
+    let resources = vec![
+        (
+            "me/mailFolders('Inbox')/messages",
+            SubscriptionResource::Outlook,
+        ),
+        ("me/events", SubscriptionResource::Outlook),
+        ("me/contacts", SubscriptionResource::Outlook),
+        ("/me/drive/root", SubscriptionResource::DriveItem),
+        ("drives/1234/root", SubscriptionResource::DriveItem),
+        ("sites/1234/lists/5678", SubscriptionResource::List),
+        ("users", SubscriptionResource::Directory),
+        ("groups", SubscriptionResource::Directory),
+        (
+            "groups/1234/conversations",
+            SubscriptionResource::GroupConversation,
+        ),
+        ("teams/getAllMessages", SubscriptionResource::Teams),
+        ("chats/1234/messages", SubscriptionResource::Teams),
+        (
+            "teams/1234/channels/5678/messages",
+            SubscriptionResource::Teams,
+        ),
+        (
+            "communications/presences?$filter=id in ('1234')",
+            SubscriptionResource::Presence,
+        ),
+        (
+            "communications/callRecords",
+            SubscriptionResource::CallRecord,
+        ),
+        (
+            "security/alerts?$filter=status eq 'newAlert'",
+            SubscriptionResource::Alert,
+        ),
+        ("me/todo/lists/1234/tasks", SubscriptionResource::TodoTask),
+    ];
+
+    for (resource, expected) in resources {
+        assert_eq!(
+            SubscriptionResource::from_resource(resource),
+            expected,
+            "{}",
+            resource
+        );
+    }
+
+    assert_eq!(SubscriptionResource::Outlook.max_lifetime_minutes(), 4230);
+    assert_eq!(
+        SubscriptionResource::DriveItem.max_lifetime_minutes(),
+        42300
+    );
+    assert_eq!(
+        SubscriptionResource::Directory.max_lifetime_minutes(),
+        41760
+    );
+    assert_eq!(SubscriptionResource::Teams.max_lifetime_minutes(), 60);
+    assert_eq!(SubscriptionResource::Alert.max_lifetime_minutes(), 43200);
+    assert_eq!(
+        SubscriptionResource::Presence.max_lifetime(),
+        Duration::minutes(60)
+    );
+}
+
+#[test]
+fn subscription_expiration() {
+    let subscription = managed_subscription("1", Duration::minutes(5));
+    assert!(subscription.expires_within(Duration::minutes(10)));
+    assert!(!subscription.expires_within(Duration::minutes(1)));
+    assert!(!subscription.is_expired());
+    assert!(managed_subscription("2", Duration::minutes(-1)).is_expired());
+}
+
+#[test]
+fn subscription_spec_serialize() {
+    let mut spec = SubscriptionSpec::new(
+        "me/messages",
+        "created",
+        "https://webhook.azurewebsites.net/api/send/myNotifyClient",
+    );
+    spec.client_state = Some("secretClientValue".into());
+    let value = serde_json::to_value(&spec).unwrap();
+    assert_eq!(value["changeType"], "created");
+    assert_eq!(value["clientState"], "secretClientValue");
+    assert!(value.get("lifecycleNotificationUrl").is_none());
+}
+
+#[test]
+fn memory_subscription_store() {
+    let mut store = MemorySubscriptionStore::new();
+    store
+        .save(&managed_subscription("1", Duration::minutes(30)))
+        .unwrap();
+    store
+        .save(&managed_subscription("2", Duration::minutes(30)))
+        .unwrap();
+    assert_eq!(store.load().unwrap().len(), 2);
+
+    store.remove("1").unwrap();
+    let subscriptions = store.load().unwrap();
+    assert_eq!(subscriptions.len(), 1);
+    assert_eq!(subscriptions[0].id, "2");
+}
+
+#[test]
+fn file_subscription_store() {
+    let path = "./test_files/subscriptions/file_subscription_store.json";
+    let _ = fs::remove_file(path);
+
+    let subscription = managed_subscription("1", Duration::minutes(30));
+    let mut store = FileSubscriptionStore::new(path);
+    assert!(store.load().unwrap().is_empty());
+    store.save(&subscription).unwrap();
+
+    let mut store = FileSubscriptionStore::new(path);
+    assert_eq!(store.load().unwrap(), vec![subscription]);
+
+    store.remove("1").unwrap();
+    assert!(store.load().unwrap().is_empty());
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,246 @@
+use graph_rs_sdk::oauth::jwtkeys::JWTKeys;
+#[cfg(feature = "notification-decryption")]
+use graph_rs_sdk::subscriptions::DecryptionKey;
+use graph_rs_sdk::subscriptions::{
+    ChangeNotificationCollection, ChangeType, LifecycleEvent, NotificationHandler,
+    NotificationResponse,
+};
+use std::fs;
+
+static APP_ID: &str = "8e1a4d66-3c6f-4bf4-9ae6-2a3b1a6c4e11";
+static TENANT_ID: &str = "84bd8158-6d4d-4958-8b9f-9d6445542f95";
+
+static NOTIFICATIONS: &str = r##"{
+  "value": [
+    {
+      "id": "lsgTZMr9KwAAA",
+      "subscriptionId": "7f105c7d-2dc5-4530-97cd-4e7ae6534c07",
+      "subscriptionExpirationDateTime": "2016-03-19T22:11:09.952Z",
+      "clientState": "secretClientValue",
+      "changeType": "created",
+      "resource": "users/{user_guid}@{tenant_guid}/messages/{long_id_string}",
+      "tenantId": "84bd8158-6d4d-4958-8b9f-9d6445542f95",
+      "resourceData": {
+        "@odata.type": "#Microsoft.Graph.Message",
+        "@odata.id": "Users/{user_guid}@{tenant_guid}/Messages/{long_id_string}",
+        "@odata.etag": "W/\"CQAAABYAAADkrWGo7bouTKlsgTZMr9KwAAAUWRHf\"",
+        "id": "{long_id_string}"
+      }
+    },
+    {
+      "subscriptionId": "7f105c7d-2dc5-4530-97cd-4e7ae6534c07",
+      "subscriptionExpirationDateTime": "2016-03-19T22:11:09.952Z",
+      "clientState": "secretClientValue",
+      "tenantId": "84bd8158-6d4d-4958-8b9f-9d6445542f95",
+      "lifecycleEvent": "reauthorizationRequired"
+    },
+    {
+      "subscriptionId": "7f105c7d-2dc5-4530-97cd-4e7ae6534c07",
+      "clientState": "wrongClientValue",
+      "changeType": "deleted",
+      "resource": "users/{user_guid}@{tenant_guid}/messages/{long_id_string}"
+    }
+  ]
+}"##;
+
+#[test]
+fn validation_token_handshake() {
+    let handler = NotificationHandler::new();
+    let response = handler
+        .handle(
+            Some("validationToken=Validation%3A+Testing+client+application+reachability"),
+            &[],
+        )
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.content_type(), Some("text/plain"));
+    assert_eq!(
+        response.body(),
+        "Validation: Testing client application reachability"
+    );
+    assert!(response.notifications().is_none());
+}
+
+#[test]
+fn validation_token_from_query() {
+    assert_eq!(
+        NotificationHandler::validation_token("?a=b&validationToken=token"),
+        Some("token".to_string())
+    );
+    assert_eq!(NotificationHandler::validation_token("a=b"), None);
+}
+
+#[test]
+fn parse_notifications() {
+    let mut handler = NotificationHandler::new();
+    handler.client_state("secretClientValue");
+
+    let response = handler.handle(None, NOTIFICATIONS.as_bytes()).unwrap();
+    assert_eq!(response.status(), 202);
+    assert_eq!(response.body(), "");
+
+    if let NotificationResponse::Notifications(notifications) = response {
+        assert_eq!(notifications.changes.len(), 1);
+        assert_eq!(notifications.lifecycle.len(), 1);
+        assert_eq!(notifications.rejected.len(), 1);
+        assert!(notifications.validation_tokens.is_empty());
+
+        let change = &notifications.changes[0];
+        assert_eq!(change.change_type, Some(ChangeType::Created));
+        let resource_data = change.resource_data.as_ref().unwrap();
+        assert_eq!(
+            resource_data.odata_type.as_deref(),
+            Some("#Microsoft.Graph.Message")
+        );
+        assert_eq!(resource_data.id.as_deref(), Some("{long_id_string}"));
+
+        assert_eq!(
+            notifications.lifecycle[0].lifecycle_event,
+            Some(LifecycleEvent::ReauthorizationRequired)
+        );
+        assert_eq!(
+            notifications.rejected[0].client_state.as_deref(),
+            Some("wrongClientValue")
+        );
+    } else {
+        panic!("Expected notifications");
+    }
+}
+
+#[test]
+fn notifications_without_client_state() {
+    let handler = NotificationHandler::new();
+    assert!(handler.parse(NOTIFICATIONS.as_bytes()).is_err());
+    assert!(handler.handle(None, NOTIFICATIONS.as_bytes()).is_err());
+
+    let collection: ChangeNotificationCollection = serde_json::from_str(NOTIFICATIONS).unwrap();
+    let notifications = handler.verify(collection);
+    assert!(notifications.changes.is_empty());
+    assert!(notifications.lifecycle.is_empty());
+    assert_eq!(notifications.rejected.len(), 3);
+}
+
+#[test]
+fn lifecycle_events() {
+    let mut handler = NotificationHandler::new();
+    handler.client_state("secretClientValue");
+    let body = r#"{"value": [
+        {"subscriptionId": "1", "clientState": "secretClientValue", "lifecycleEvent": "missed"},
+        {"subscriptionId": "2", "clientState": "secretClientValue", "lifecycleEvent": "subscriptionRemoved"}
+    ]}"#;
+    let notifications = handler.parse(body.as_bytes()).unwrap();
+    assert!(notifications.changes.is_empty());
+    assert_eq!(
+        notifications.lifecycle[0].lifecycle_event,
+        Some(LifecycleEvent::Missed)
+    );
+    assert_eq!(
+        notifications.lifecycle[1].lifecycle_event,
+        Some(LifecycleEvent::SubscriptionRemoved)
+    );
+}
+
+#[test]
+fn invalid_notification_body() {
+    let mut handler = NotificationHandler::new();
+    handler.client_state("secretClientValue");
+    assert!(handler.handle(None, &[]).is_err());
+    assert!(handler.handle(None, b"not json").is_err());
+}
+
+fn rich_notification() -> ChangeNotificationCollection {
+    let body = fs::read_to_string("./test_files/subscriptions/rich_notification.json").unwrap();
+    serde_json::from_str(&body).unwrap()
+}
+
+#[cfg(feature = "notification-decryption")]
+fn decryption_key() -> DecryptionKey {
+    let pem = fs::read_to_string("./test_files/subscriptions/notification_key.pem").unwrap();
+    DecryptionKey::from_pkcs8_pem(&pem).unwrap()
+}
+
+fn jwt_keys() -> JWTKeys {
+    let keys = fs::read_to_string("./test_files/subscriptions/jwks.json").unwrap();
+    serde_json::from_str(&keys).unwrap()
+}
+
+#[test]
+#[cfg(feature = "notification-decryption")]
+fn decrypt_rich_notification() {
+    let mut key = decryption_key();
+    key.certificate_id("notification-cert");
+    let mut handler = NotificationHandler::new();
+    handler.decryption_key(key);
+
+    let collection = rich_notification();
+    let value: serde_json::Value = handler.decrypt(&collection.value[0]).unwrap().unwrap();
+    assert_eq!(value["id"].as_str(), Some("1565293727947"));
+    assert_eq!(value["body"]["content"].as_str(), Some("<div>Hello</div>"));
+}
+
+#[test]
+#[cfg(feature = "notification-decryption")]
+fn decrypt_without_encrypted_content() {
+    let mut handler = NotificationHandler::new();
+    handler
+        .client_state("secretClientValue")
+        .decryption_key(decryption_key());
+    let notifications = handler.parse(NOTIFICATIONS.as_bytes()).unwrap();
+    let value: Option<serde_json::Value> = handler.decrypt(&notifications.changes[0]).unwrap();
+    assert!(value.is_none());
+}
+
+#[test]
+#[cfg(feature = "notification-decryption")]
+fn decrypt_invalid_data_signature() {
+    let mut collection = rich_notification();
+    let content = collection.value[0].encrypted_content.as_mut().unwrap();
+    content.data_signature = base64::encode([0u8; 32]);
+    assert!(decryption_key().decrypt(content).is_err());
+}
+
+#[test]
+#[cfg(feature = "notification-decryption")]
+fn decrypt_wrong_certificate_id() {
+    let collection = rich_notification();
+    let mut key = decryption_key();
+    key.certificate_id("other-cert");
+    assert!(key
+        .decrypt(collection.value[0].encrypted_content.as_ref().unwrap())
+        .is_err());
+}
+
+#[test]
+fn validate_validation_tokens() {
+    let collection = rich_notification();
+    let tokens = collection.validation_tokens.unwrap();
+    let mut handler = NotificationHandler::new();
+    handler.app_id(APP_ID).tenant_id(TENANT_ID);
+    assert!(handler.validate_tokens(&tokens, &jwt_keys()).is_ok());
+
+    handler.tenant_id("other-tenant");
+    assert!(handler.validate_tokens(&tokens, &jwt_keys()).is_err());
+}
+
+#[test]
+fn reject_invalid_validation_tokens() {
+    let tokens: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string("./test_files/subscriptions/validation_tokens.json").unwrap(),
+    )
+    .unwrap();
+    let mut handler = NotificationHandler::new();
+    handler.app_id(APP_ID);
+
+    let expired = vec![tokens["expired"].as_str().unwrap().to_string()];
+    assert!(handler.validate_tokens(&expired, &jwt_keys()).is_err());
+    let wrong_audience = vec![tokens["wrong_audience"].as_str().unwrap().to_string()];
+    assert!(handler
+        .validate_tokens(&wrong_audience, &jwt_keys())
+        .is_err());
+
+    let collection = rich_notification();
+    let mut tampered = collection.validation_tokens.unwrap();
+    tampered[0].push('A');
+    assert!(handler.validate_tokens(&tampered, &jwt_keys()).is_err());
+    assert!(handler.validate_tokens(&[], &jwt_keys()).is_err());
+}
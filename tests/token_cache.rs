@@ -0,0 +1,293 @@
+use graph_rs_sdk::oauth::{
+    AccessToken, CachedToken, EncryptionKey, FileTokenCache, GrantType, MemoryTokenCache, OAuth,
+    TokenCache, TokenCacheKey,
+};
+use std::thread;
+use test_tools::tokenserver;
+
+const CLIENT_ID: &str = "6731de76-14a6-49ae-97bc-6eba6914391e";
+const TENANT_ID: &str = "9188040d-6c67-4c5b-b112-36a304b66dad";
+
+fn id_token(oid: &str, username: &str) -> String {
+    let encode = |value: serde_json::Value| {
+        base64::encode_config(value.to_string(), base64::URL_SAFE_NO_PAD)
+    };
+    format!(
+        "{}.{}.c2lnbmF0dXJl",
+        encode(serde_json::json!({ "typ": "JWT", "alg": "RS256" })),
+        encode(serde_json::json!({
+            "oid": oid,
+            "tid": TENANT_ID,
+            "preferred_username": username,
+        }))
+    )
+}
+
+fn access_token(
+    oid: &str,
+    username: &str,
+    scope: &str,
+    expires_in: i64,
+    bearer: &str,
+) -> AccessToken {
+    let mut access_token = AccessToken::new("Bearer", expires_in, scope, bearer);
+    access_token
+        .set_id_token(&id_token(oid, username))
+        .set_refresh_token(&format!("refresh-{}", bearer));
+    access_token
+}
+
+fn oauth(url: &str) -> OAuth {
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id(CLIENT_ID)
+        .client_secret("JqQX2PNo9bpM0uEihUPzyrh")
+        .add_scope("https://graph.microsoft.com/User.Read")
+        .add_scope("offline_access")
+        .redirect_uri("http://localhost:8000/redirect")
+        .access_token_url(url)
+        .refresh_token_url(url);
+    oauth
+}
+
+fn account(oid: &str) -> String {
+    format!("{}.{}", oid, TENANT_ID)
+}
+
+// Serves each response once, in order, and returns the request bodies.
+fn token_server(responses: Vec<String>) -> (String, thread::JoinHandle<Vec<String>>) {
+    let responses = responses.into_iter().map(|body| (200, body)).collect();
+    let (url, handle) = tokenserver::serve(responses, |requests| {
+        requests.into_iter().map(|request| request.body).collect()
+    });
+    (format!("{}/token", url), handle)
+}
+
+fn refresh_response() -> String {
+    serde_json::json!({
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "scope": "User.Read Mail.Read",
+        "access_token": "refreshed",
+    })
+    .to_string()
+}
+
+#[test]
+fn token_cache_find_by_scopes() {
+    let oauth = oauth("https://login.microsoftonline.com/common/oauth2/v2.0/token");
+    let mut cache = MemoryTokenCache::new();
+    let cached = cache
+        .insert(
+            &oauth,
+            access_token(
+                "oid-1",
+                "adele@contoso.com",
+                "User.Read Mail.Read",
+                3600,
+                "a",
+            ),
+        )
+        .unwrap();
+    assert_eq!(cached.key().home_account_id(), account("oid-1"));
+    assert_eq!(cached.username().unwrap(), "adele@contoso.com");
+    assert!(cached.key().scopes().contains("mail.read"));
+
+    let authority = cached.key().authority().to_string();
+    let found = cache
+        .find(&authority, CLIENT_ID, &account("oid-1"), oauth.get_scopes())
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.access_token().bearer_token(), "a");
+
+    let scopes = vec!["Files.Read".to_string()].into_iter().collect();
+    assert!(cache
+        .find(&authority, CLIENT_ID, &account("oid-1"), &scopes)
+        .unwrap()
+        .is_none());
+    assert!(cache
+        .find(&authority, CLIENT_ID, &account("oid-2"), oauth.get_scopes())
+        .unwrap()
+        .is_none());
+
+    // Tokens that are about to expire are not returned.
+    cache
+        .insert(
+            &oauth,
+            access_token("oid-1", "adele@contoso.com", "User.Read", 60, "b"),
+        )
+        .unwrap();
+    let found = cache
+        .find(&authority, CLIENT_ID, &account("oid-1"), oauth.get_scopes())
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.access_token().bearer_token(), "a");
+    assert_eq!(
+        cache
+            .refresh_token(&authority, CLIENT_ID, &account("oid-1"))
+            .unwrap()
+            .as_deref(),
+        Some("refresh-a")
+    );
+}
+
+#[test]
+fn token_cache_accounts() {
+    let oauth = oauth("https://login.microsoftonline.com/common/oauth2/v2.0/token");
+    let mut cache = MemoryTokenCache::new();
+    cache
+        .insert(
+            &oauth,
+            access_token("oid-1", "adele@contoso.com", "User.Read", 3600, "a"),
+        )
+        .unwrap();
+    cache
+        .insert(
+            &oauth,
+            access_token("oid-1", "adele@contoso.com", "Mail.Read", 3600, "b"),
+        )
+        .unwrap();
+    cache
+        .insert(
+            &oauth,
+            access_token("oid-2", "alex@contoso.com", "User.Read", 3600, "c"),
+        )
+        .unwrap();
+    assert_eq!(cache.load().unwrap().len(), 3);
+
+    let accounts = cache.accounts().unwrap();
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].username.as_deref(), Some("adele@contoso.com"));
+    assert_eq!(accounts[1].home_account_id, account("oid-2"));
+
+    cache.remove_account(&account("oid-1")).unwrap();
+    assert_eq!(cache.load().unwrap().len(), 1);
+    assert_eq!(cache.accounts().unwrap().len(), 1);
+}
+
+#[test]
+fn file_token_cache() {
+    let path = std::env::temp_dir().join(format!("token_cache_{}.json", std::process::id()));
+    std::fs::write(&path, "").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    }
+    let oauth = oauth("https://login.microsoftonline.com/common/oauth2/v2.0/token");
+    let key = EncryptionKey::generate().unwrap();
+
+    let mut cache = FileTokenCache::new(&path, key.clone());
+    assert!(cache.load().unwrap().is_empty());
+    let cached = cache
+        .insert(
+            &oauth,
+            access_token("oid-1", "adele@contoso.com", "User.Read", 3600, "a"),
+        )
+        .unwrap();
+    cache.save(&cached).unwrap();
+
+    let cache = FileTokenCache::new(&path, key);
+    let tokens = cache.load().unwrap();
+    assert_eq!(tokens, vec![cached.clone()]);
+    assert!(!tokens[0].is_expired());
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("JqQX2PNo9bpM0uEihUPzyrh"));
+    assert!(!contents.contains("refresh-a"));
+    assert!(!contents.contains("adele@contoso.com"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let other = FileTokenCache::new(&path, EncryptionKey::generate().unwrap());
+    assert!(other.load().is_err());
+
+    let mut cache = cache;
+    cache.remove(cached.key()).unwrap();
+    assert!(cache.load().unwrap().is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn acquire_token_silent_cached() {
+    let mut oauth = oauth("https://login.microsoftonline.com/common/oauth2/v2.0/token");
+    let mut cache = MemoryTokenCache::new();
+    cache
+        .insert(
+            &oauth,
+            access_token("oid-1", "adele@contoso.com", "User.Read", 3600, "a"),
+        )
+        .unwrap();
+
+    let access_token = oauth
+        .acquire_token_silent(&mut cache, GrantType::AuthorizationCode, &account("oid-1"))
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "a");
+    assert_eq!(oauth.get_access_token().unwrap().bearer_token(), "a");
+
+    let err = oauth
+        .acquire_token_silent(&mut cache, GrantType::AuthorizationCode, &account("oid-2"))
+        .unwrap_err();
+    assert!(format!("{:?}", err).contains("NotFound"));
+}
+
+#[test]
+fn acquire_token_silent_refresh() {
+    let (url, handle) = token_server(vec![refresh_response()]);
+    let mut oauth = oauth(&url);
+    oauth.add_scope("Mail.Read");
+    let mut cache = MemoryTokenCache::new();
+    let key = TokenCacheKey::new(&url, CLIENT_ID, &account("oid-1"), vec!["User.Read"]);
+    cache
+        .save(&CachedToken::new(
+            key,
+            access_token("oid-1", "adele@contoso.com", "User.Read", 3600, "a"),
+        ))
+        .unwrap();
+
+    let access_token = oauth
+        .acquire_token_silent(&mut cache, GrantType::AuthorizationCode, &account("oid-1"))
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "refreshed");
+    assert_eq!(
+        access_token.clone().refresh_token().as_deref(),
+        Some("refresh-a")
+    );
+
+    let body = handle.join().unwrap().remove(0);
+    assert!(body.contains("grant_type=refresh_token"));
+    assert!(body.contains("refresh_token=refresh-a"));
+
+    // The refreshed token is cached for the account.
+    let access_token = oauth
+        .acquire_token_silent(&mut cache, GrantType::AuthorizationCode, &account("oid-1"))
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "refreshed");
+    assert_eq!(cache.load().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn async_acquire_token_silent_refresh() {
+    let (url, handle) = token_server(vec![refresh_response()]);
+    let mut oauth = oauth(&url);
+    let mut cache = MemoryTokenCache::new();
+    let key = TokenCacheKey::new(&url, CLIENT_ID, &account("oid-1"), vec!["User.Read"]);
+    cache
+        .save(&CachedToken::new(
+            key,
+            access_token("oid-1", "adele@contoso.com", "User.Read", 0, "a"),
+        ))
+        .unwrap();
+
+    let access_token = oauth
+        .async_acquire_token_silent(&mut cache, GrantType::AuthorizationCode, &account("oid-1"))
+        .await
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "refreshed");
+    assert_eq!(handle.join().unwrap().len(), 1);
+    assert_eq!(cache.load().unwrap().len(), 2);
+}
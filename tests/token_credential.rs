@@ -0,0 +1,288 @@
+use graph_rs_sdk::client::Graph;
+use graph_rs_sdk::oauth::{
+    BlockingTokenCredential, ChainedTokenCredential, EnvironmentCredential,
+    ManagedIdentityCredential, TokenCredential, WorkloadIdentityCredential, GRAPH_DEFAULT_SCOPE,
+};
+use std::collections::HashMap;
+use std::thread;
+use test_tools::tokenserver::{self, MockRequest, MockTokenServer};
+
+const CLIENT_ID: &str = "6731de76-14a6-49ae-97bc-6eba6914391e";
+const TENANT_ID: &str = "72f988bf-86f1-41af-91ab-2d7cd011db47";
+
+// Serves one json response and returns the request.
+fn server(response: serde_json::Value) -> (String, thread::JoinHandle<MockRequest>) {
+    tokenserver::serve(vec![(200, response.to_string())], |mut requests| {
+        requests.remove(0)
+    })
+}
+
+fn token_response() -> serde_json::Value {
+    serde_json::json!({
+        "token_type": "Bearer",
+        "expires_in": 3599,
+        "access_token": "app-token",
+    })
+}
+
+fn vars(vars: Vec<(&'static str, String)>) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<&'static str, String> = vars.into_iter().collect();
+    move |key| vars.get(key).cloned()
+}
+
+#[test]
+fn environment_credential() {
+    let (url, handle) = server(token_response());
+    let credential = EnvironmentCredential::from_vars(vars(vec![
+        ("AZURE_CLIENT_ID", CLIENT_ID.into()),
+        ("AZURE_TENANT_ID", TENANT_ID.into()),
+        ("AZURE_CLIENT_SECRET", "client-secret".into()),
+        ("AZURE_AUTHORITY_HOST", url),
+    ]))
+    .unwrap()
+    .unwrap();
+    assert!(!format!("{:?}", credential).contains("client-secret"));
+
+    let access_token = credential.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+    assert_eq!(access_token.bearer_token(), "app-token");
+
+    let request = handle.join().unwrap();
+    assert!(request
+        .head
+        .starts_with(&format!("POST /{}/oauth2/v2.0/token ", TENANT_ID)));
+    let form = request.form();
+    assert_eq!(form["grant_type"], "client_credentials");
+    assert_eq!(form["client_id"], CLIENT_ID);
+    assert_eq!(form["client_secret"], "client-secret");
+    assert_eq!(form["scope"], GRAPH_DEFAULT_SCOPE);
+
+    // Not configured without a secret or certificate.
+    assert!(EnvironmentCredential::from_vars(vars(vec![
+        ("AZURE_CLIENT_ID", CLIENT_ID.into()),
+        ("AZURE_TENANT_ID", TENANT_ID.into()),
+    ]))
+    .unwrap()
+    .is_none());
+    // A certificate path that does not exist is an error.
+    assert!(EnvironmentCredential::from_vars(vars(vec![
+        ("AZURE_CLIENT_ID", CLIENT_ID.into()),
+        ("AZURE_TENANT_ID", TENANT_ID.into()),
+        ("AZURE_CLIENT_CERTIFICATE_PATH", "./missing.pem".into()),
+    ]))
+    .is_err());
+}
+
+#[test]
+fn workload_identity_credential() {
+    let token_file =
+        std::env::temp_dir().join(format!("graph-rs-federated-token-{}", std::process::id()));
+    std::fs::write(&token_file, "federated-token\n").unwrap();
+
+    let (url, handle) = server(token_response());
+    let credential = WorkloadIdentityCredential::from_vars(vars(vec![
+        ("AZURE_CLIENT_ID", CLIENT_ID.into()),
+        ("AZURE_TENANT_ID", TENANT_ID.into()),
+        (
+            "AZURE_FEDERATED_TOKEN_FILE",
+            token_file.to_string_lossy().to_string(),
+        ),
+        ("AZURE_AUTHORITY_HOST", url),
+    ]))
+    .unwrap();
+    let access_token = credential.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+    std::fs::remove_file(&token_file).unwrap();
+    assert_eq!(access_token.bearer_token(), "app-token");
+
+    let form = handle.join().unwrap().form();
+    assert_eq!(form["grant_type"], "client_credentials");
+    assert_eq!(form["client_assertion"], "federated-token");
+    assert_eq!(
+        form["client_assertion_type"],
+        "urn:ietf:params:oauth:client-assertion-type:jwt-bearer"
+    );
+    assert!(!form.contains_key("client_secret"));
+
+    assert!(credential.get_token(&[GRAPH_DEFAULT_SCOPE]).is_err());
+    assert!(WorkloadIdentityCredential::from_vars(vars(vec![(
+        "AZURE_CLIENT_ID",
+        CLIENT_ID.into()
+    )]))
+    .is_none());
+}
+
+#[test]
+fn managed_identity_credential() {
+    let (url, handle) = server(serde_json::json!({
+        "access_token": "imds-token",
+        "expires_in": "86399",
+        "expires_on": "1606175591",
+        "resource": "https://graph.microsoft.com",
+        "token_type": "Bearer",
+    }));
+    let mut credential = ManagedIdentityCredential::new();
+    credential
+        .imds_endpoint(&format!("{}/metadata/identity/oauth2/token", url))
+        .client_id(CLIENT_ID);
+    let access_token = credential.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+    assert_eq!(access_token.bearer_token(), "imds-token");
+    assert_eq!(access_token.expires_in(), 86399);
+    assert!(!access_token.is_expired());
+
+    let request = handle.join().unwrap();
+    assert!(request
+        .head
+        .starts_with("GET /metadata/identity/oauth2/token?"));
+    assert!(request.head.to_lowercase().contains("metadata: true"));
+    let query = request.query();
+    assert_eq!(query["api-version"], "2018-02-01");
+    assert_eq!(query["resource"], "https://graph.microsoft.com");
+    assert_eq!(query["client_id"], CLIENT_ID);
+
+    assert!(credential
+        .get_token(&[GRAPH_DEFAULT_SCOPE, "offline_access"])
+        .is_err());
+}
+
+#[test]
+fn managed_identity_app_service() {
+    let expires_on = chrono::Utc::now().timestamp() + 3600;
+    let (url, handle) = server(serde_json::json!({
+        "access_token": "app-service-token",
+        "expires_on": expires_on.to_string(),
+        "resource": "https://graph.microsoft.com",
+        "token_type": "Bearer",
+    }));
+    let credential = ManagedIdentityCredential::from_vars(vars(vec![
+        ("IDENTITY_ENDPOINT", format!("{}/msi/token", url)),
+        ("IDENTITY_HEADER", "identity-header".into()),
+    ]));
+    let access_token = credential.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+    assert_eq!(access_token.bearer_token(), "app-service-token");
+    assert!(access_token.expires_in() > 3500);
+
+    let request = handle.join().unwrap();
+    assert!(request.head.starts_with("GET /msi/token?"));
+    assert!(request
+        .head
+        .to_lowercase()
+        .contains("x-identity-header: identity-header"));
+    assert_eq!(request.query()["api-version"], "2019-08-01");
+}
+
+#[test]
+fn chained_token_credential() {
+    let (url, handle) = server(token_response());
+    let chain = ChainedTokenCredential::from_vars(vars(vec![
+        ("AZURE_CLIENT_ID", CLIENT_ID.into()),
+        ("AZURE_TENANT_ID", TENANT_ID.into()),
+        ("AZURE_CLIENT_SECRET", "client-secret".into()),
+        ("AZURE_AUTHORITY_HOST", url),
+    ]))
+    .unwrap();
+    // Environment and managed identity.
+    assert_eq!(chain.len(), 2);
+
+    let access_token = chain.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+    assert_eq!(access_token.bearer_token(), "app-token");
+    handle.join().unwrap();
+
+    // The server is gone so the token must come from the cache.
+    let access_token = chain.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+    assert_eq!(access_token.bearer_token(), "app-token");
+    let client = Graph::from_credential(&chain).unwrap();
+    // Refreshing uses the cached token.
+    client.refresh_token(&chain).unwrap();
+
+    chain.clear();
+    assert!(chain.get_token(&[GRAPH_DEFAULT_SCOPE]).is_err());
+    assert!(client.refresh_token(&chain).is_err());
+}
+
+#[test]
+fn chained_token_credential_workload_identity() {
+    let token_file = std::env::temp_dir().join(format!(
+        "graph-rs-chained-federated-token-{}",
+        std::process::id()
+    ));
+    std::fs::write(&token_file, "federated-token").unwrap();
+
+    // Managed identity is tried first and workload identity is used
+    // when the managed identity endpoint does not return a token.
+    let server = MockTokenServer::new(vec![
+        (400, r#"{"error":"invalid_request"}"#.to_string()),
+        (200, token_response().to_string()),
+    ]);
+    let url = server.url().to_string();
+    let chain = ChainedTokenCredential::from_vars(vars(vec![
+        ("AZURE_CLIENT_ID", CLIENT_ID.into()),
+        ("AZURE_TENANT_ID", TENANT_ID.into()),
+        (
+            "AZURE_FEDERATED_TOKEN_FILE",
+            token_file.to_string_lossy().to_string(),
+        ),
+        ("AZURE_AUTHORITY_HOST", url.clone()),
+        ("IDENTITY_ENDPOINT", format!("{}/msi/token", url)),
+        ("IDENTITY_HEADER", "identity-header".into()),
+    ]))
+    .unwrap();
+    assert_eq!(chain.len(), 2);
+
+    let access_token = chain.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+    std::fs::remove_file(&token_file).unwrap();
+    assert_eq!(access_token.bearer_token(), "app-token");
+    let requests = server.requests();
+    assert!(requests[0].head.starts_with("GET /msi/token?"));
+    assert!(requests[1]
+        .head
+        .starts_with(&format!("POST /{}/oauth2/v2.0/token ", TENANT_ID)));
+    assert_eq!(requests[1].form()["client_assertion"], "federated-token");
+}
+
+#[test]
+fn chained_token_credential_order() {
+    let (url, handle) = server(serde_json::json!({
+        "access_token": "imds-token",
+        "expires_in": "3599",
+        "token_type": "Bearer",
+    }));
+    let mut unavailable = ManagedIdentityCredential::new();
+    unavailable.imds_endpoint("http://127.0.0.1:1/metadata/identity/oauth2/token");
+    let mut available = ManagedIdentityCredential::new();
+    available.imds_endpoint(&format!("{}/metadata/identity/oauth2/token", url));
+
+    let mut chain = ChainedTokenCredential::new();
+    chain
+        .push_blocking(unavailable.clone())
+        .push_blocking(available);
+    let access_token = chain.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+    assert_eq!(access_token.bearer_token(), "imds-token");
+    handle.join().unwrap();
+
+    let mut chain = ChainedTokenCredential::new();
+    chain.push_blocking(unavailable);
+    let err = chain.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap_err();
+    assert!(err.to_string().contains("no credential returned a token"));
+}
+
+#[tokio::test]
+async fn chained_token_credential_async_only() {
+    let (url, handle) = server(serde_json::json!({
+        "access_token": "imds-token",
+        "expires_in": "3599",
+        "token_type": "Bearer",
+    }));
+    let mut credential = ManagedIdentityCredential::new();
+    credential.imds_endpoint(&format!("{}/metadata/identity/oauth2/token", url));
+
+    let mut chain = ChainedTokenCredential::new();
+    chain.push(credential);
+    // Credentials added with push are skipped by blocking requests.
+    let err = chain.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("the credential does not support blocking requests"));
+
+    let access_token = chain.async_get_token(&[GRAPH_DEFAULT_SCOPE]).await.unwrap();
+    assert_eq!(access_token.bearer_token(), "imds-token");
+    handle.join().unwrap();
+}
@@ -0,0 +1,193 @@
+use graph_rs_sdk::client::Graph;
+use graph_rs_sdk::oauth::graphdiscovery::MicrosoftSigningKeysV2;
+use graph_rs_sdk::oauth::{
+    AccessToken, BlockingTokenCredential, ManagedIdentityCredential, OAuth, TokenCredential,
+    TokenHttpClient, GRAPH_DEFAULT_SCOPE,
+};
+use reqwest::header::{HeaderMap, HeaderValue};
+use test_tools::tokenserver::MockTokenServer;
+
+const CLIENT_ID: &str = "6731de76-14a6-49ae-97bc-6eba6914391e";
+
+fn headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-transport", HeaderValue::from_static("configured"));
+    headers
+}
+
+fn token_response() -> serde_json::Value {
+    serde_json::json!({
+        "token_type": "Bearer",
+        "expires_in": 3599,
+        "access_token": "configured-token",
+    })
+}
+
+#[test]
+fn blocking_http_client() {
+    let server = MockTokenServer::json(token_response());
+    let client = reqwest::blocking::Client::builder()
+        .default_headers(headers())
+        .build()
+        .unwrap();
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id(CLIENT_ID)
+        .client_secret("client-secret")
+        .access_token_url(&server.token_url())
+        .add_scope("https://graph.microsoft.com/.default");
+    let without_client = oauth.clone();
+    oauth.blocking_http_client(client);
+    assert_ne!(oauth, without_client);
+    // Clones share the configured client.
+    assert_eq!(oauth, oauth.clone());
+
+    let access_token = oauth
+        .build()
+        .client_credentials()
+        .access_token()
+        .send()
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "configured-token");
+    assert_eq!(server.request().header("x-transport"), Some("configured"));
+}
+
+#[tokio::test]
+async fn async_http_client() {
+    let server = MockTokenServer::json(token_response());
+    let client = reqwest::Client::builder()
+        .default_headers(headers())
+        .build()
+        .unwrap();
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id(CLIENT_ID)
+        .refresh_token_url(&server.token_url())
+        .add_scope("User.Read")
+        .http_client(client);
+    let mut access_token = AccessToken::default();
+    access_token.set_refresh_token("refresh-token");
+    oauth.access_token(access_token);
+
+    let access_token = oauth
+        .build_async()
+        .authorization_code_grant()
+        .refresh_token()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "configured-token");
+    assert_eq!(server.request().header("x-transport"), Some("configured"));
+}
+
+#[test]
+fn device_code_http_client() {
+    let server = MockTokenServer::json(serde_json::json!({
+        "device_code": "GMMhmHCXhWEzkobqIHGG_EnNYYsAkukHspeYUk9E8",
+        "user_code": "ABCD-EFGH",
+        "verification_uri": "https://microsoft.com/devicelogin",
+        "expires_in": 900,
+        "interval": 5,
+    }));
+    let client = reqwest::blocking::Client::builder()
+        .default_headers(headers())
+        .build()
+        .unwrap();
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id(CLIENT_ID)
+        .add_scope("User.Read")
+        .device_code_url(&server.token_url())
+        .blocking_http_client(client);
+
+    let device_code = oauth.build().device_code().device_code();
+    let device_code = device_code.unwrap().send().unwrap();
+    assert_eq!(device_code.user_code(), "ABCD-EFGH");
+    assert_eq!(server.request().header("x-transport"), Some("configured"));
+}
+
+fn managed_identity_response() -> String {
+    serde_json::json!({
+        "access_token": "imds-token",
+        "expires_in": "3599",
+        "token_type": "Bearer",
+    })
+    .to_string()
+}
+
+#[test]
+fn shared_http_client() {
+    let server = MockTokenServer::new(vec![
+        (200, managed_identity_response()),
+        (200, serde_json::json!({ "sub": "user-sub" }).to_string()),
+    ]);
+    let mut http_client = TokenHttpClient::new();
+    http_client.blocking_http_client(
+        reqwest::blocking::Client::builder()
+            .default_headers(headers())
+            .build()
+            .unwrap(),
+    );
+
+    let mut credential = ManagedIdentityCredential::new();
+    credential
+        .imds_endpoint(&format!("{}/metadata/identity/oauth2/token", server.url()))
+        .share_http_client(&http_client);
+    let access_token = credential.get_token(&[GRAPH_DEFAULT_SCOPE]).unwrap();
+    assert_eq!(access_token.bearer_token(), "imds-token");
+
+    let mut keys = MicrosoftSigningKeysV2::default();
+    keys.userinfo_endpoint = format!("{}/oidc/userinfo", server.url());
+    keys.share_http_client(&http_client);
+    let user_info = keys.user_info("openid-token").unwrap();
+    assert_eq!(user_info.sub, "user-sub");
+
+    for request in server.requests() {
+        assert_eq!(request.header("x-transport"), Some("configured"));
+    }
+}
+
+#[tokio::test]
+async fn async_shared_http_client() {
+    let server = MockTokenServer::new(vec![(200, managed_identity_response())]);
+    let mut http_client = TokenHttpClient::new();
+    http_client.http_client(
+        reqwest::Client::builder()
+            .default_headers(headers())
+            .build()
+            .unwrap(),
+    );
+
+    let mut credential = ManagedIdentityCredential::new();
+    credential
+        .imds_endpoint(&format!("{}/metadata/identity/oauth2/token", server.url()))
+        .share_http_client(&http_client);
+    let access_token = credential
+        .async_get_token(&[GRAPH_DEFAULT_SCOPE])
+        .await
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "imds-token");
+    assert_eq!(server.request().header("x-transport"), Some("configured"));
+}
+
+#[test]
+fn graph_http_client() {
+    let server = MockTokenServer::json(token_response());
+    let client = Graph::new("ACCESS_TOKEN");
+    let mut oauth = OAuth::new();
+    oauth
+        .client_id(CLIENT_ID)
+        .client_secret("client-secret")
+        .access_token_url(&server.token_url())
+        .add_scope("https://graph.microsoft.com/.default")
+        .share_http_client(&client.token_http_client());
+
+    let access_token = oauth
+        .build()
+        .client_credentials()
+        .access_token()
+        .send()
+        .unwrap();
+    assert_eq!(access_token.bearer_token(), "configured-token");
+    server.request();
+}